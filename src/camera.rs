@@ -52,6 +52,7 @@ pub struct Camera {
     v: DVec3,
     lens_radius: f64,
     shutter_time: f64,
+    viewport_height: f64,
 }
 
 impl Camera {
@@ -87,9 +88,15 @@ impl Camera {
             v,
             lens_radius,
             shutter_time,
+            viewport_height,
         }
     }
 
+    /// Angle subtended by a single pixel, for an image with the given height.
+    pub fn pixel_spread(&self, image_height: usize) -> f64 {
+        self.viewport_height / image_height as f64
+    }
+
     pub fn get_ray(&self, u: f64, v: f64) -> Ray {
         let mut rng = rand::thread_rng();
         let rd = self.lens_radius * random_in_unit_disk(&mut rng);
@@ -99,6 +106,8 @@ impl Camera {
             origin: self.origin + offset,
             direction: self.lower_left + u * self.horizontal + v * self.vertical - offset,
             time: rng.gen_range(0.0..=self.shutter_time),
+            width: 0.0,
+            spread: 0.0,
         }
     }
 }
@@ -0,0 +1,146 @@
+use crate::{
+    hittable::{Aabb, Face, HitRecord, Hittable},
+    ray::Ray,
+};
+
+// CSG works on closed shapes, whose hits report `Face::Front` where the ray
+// enters the shape and `Face::Back` where it leaves. Each operand is split
+// into the list of its crossings along the ray, and the lists are merged
+// while tracking whether the ray is inside the result.
+
+/// Distance to step past a crossing before looking for the next one.
+const EPSILON: f64 = 1.0e-6;
+
+/// Every crossing of `shape` along the ray within `t_min..t_max`, and
+/// whether the ray is inside the shape at `t_min`.
+fn crossings<'a, H: Hittable>(
+    shape: &'a H,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> (bool, Vec<HitRecord<'a>>) {
+    let mut hits = Vec::new();
+    let mut t = t_min;
+    while let Some(hit) = shape.hit(ray, t, t_max) {
+        t = hit.t + EPSILON;
+        hits.push(hit);
+    }
+    let inside = match hits.first() {
+        Some(hit) => hit.face == Face::Back,
+        // Without crossings, the ray is inside if it leaves later on.
+        None => shape
+            .hit(ray, t_max, f64::INFINITY)
+            .is_some_and(|hit| hit.face == Face::Back),
+    };
+    (inside, hits)
+}
+
+/// Finds the first crossing of the boundary of the set of points for which
+/// `inside(in_a, in_b)` holds.
+fn combine<'a, A: Hittable, B: Hittable>(
+    a: &'a A,
+    b: &'a B,
+    inside: fn(bool, bool) -> bool,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    let (mut in_a, hits_a) = crossings(a, ray, t_min, t_max);
+    let (mut in_b, hits_b) = crossings(b, ray, t_min, t_max);
+    let mut hits_a = hits_a.into_iter().peekable();
+    let mut hits_b = hits_b.into_iter().peekable();
+    let mut was_inside = inside(in_a, in_b);
+
+    loop {
+        let from_a = match (hits_a.peek(), hits_b.peek()) {
+            (Some(hit_a), Some(hit_b)) => hit_a.t <= hit_b.t,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => return None,
+        };
+        let mut hit = if from_a {
+            let hit = hits_a.next()?;
+            in_a = hit.face == Face::Front;
+            hit
+        } else {
+            let hit = hits_b.next()?;
+            in_b = hit.face == Face::Front;
+            hit
+        };
+
+        let is_inside = inside(in_a, in_b);
+        if is_inside != was_inside {
+            // The normal already faces the ray, only which side of the
+            // result it is on can differ from the operand, e.g. where a
+            // difference leaves the subtracted shape.
+            hit.face = if is_inside { Face::Front } else { Face::Back };
+            return Some(hit);
+        }
+        was_inside = is_inside;
+    }
+}
+
+/// Points inside either `a` or `b`. Unlike adding both to a
+/// [`World`](crate::hittable::World), surfaces inside the other shape are
+/// removed, which matters for transparent materials.
+#[derive(Debug, Clone)]
+pub struct Union<A, B> {
+    pub a: A,
+    pub b: B,
+}
+
+impl<A: Hittable, B: Hittable> Hittable for Union<A, B> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        combine(&self.a, &self.b, |a, b| a || b, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<Aabb> {
+        let a = self.a.bounding_box(start_time, end_time)?;
+        let b = self.b.bounding_box(start_time, end_time)?;
+        Some(a.union(&b))
+    }
+}
+
+/// Points inside both `a` and `b`.
+#[derive(Debug, Clone)]
+pub struct Intersection<A, B> {
+    pub a: A,
+    pub b: B,
+}
+
+impl<A: Hittable, B: Hittable> Hittable for Intersection<A, B> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        combine(&self.a, &self.b, |a, b| a && b, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<Aabb> {
+        match (
+            self.a.bounding_box(start_time, end_time),
+            self.b.bounding_box(start_time, end_time),
+        ) {
+            (Some(a), Some(b)) => Some(Aabb {
+                minimum: a.minimum.max(b.minimum),
+                maximum: a.maximum.min(b.maximum).max(a.minimum.max(b.minimum)),
+            }),
+            (a, b) => a.or(b),
+        }
+    }
+}
+
+/// Points inside `a` but not inside `b`. Surfaces of `b` keep their own
+/// material, so carved out parts can look different.
+#[derive(Debug, Clone)]
+pub struct Difference<A, B> {
+    pub a: A,
+    pub b: B,
+}
+
+impl<A: Hittable, B: Hittable> Hittable for Difference<A, B> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        combine(&self.a, &self.b, |a, b| a && !b, ray, t_min, t_max)
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<Aabb> {
+        self.a.bounding_box(start_time, end_time)
+    }
+}
@@ -0,0 +1,242 @@
+use std::f64::consts as f64;
+
+use glam::{DQuat, DVec2, DVec3, Vec3Swizzles};
+
+use crate::{
+    hittable::{compute_face_normal, Aabb, HitRecord, Hittable},
+    material::Material,
+    microfacet::Frame,
+    ray::Ray,
+};
+
+/// How the cross section of a [`Curve`] is shaped.
+#[derive(Debug, Clone, Copy)]
+pub enum CurveShape {
+    /// A flat strip that always faces the ray, cheap for distant hair.
+    Flat,
+    /// Like `Flat`, but with normals bent across the width as if it were a
+    /// tube.
+    Cylinder,
+    /// A strip oriented by normals at the start and end, e.g. for grass
+    /// blades.
+    Ribbon { normals: [DVec3; 2] },
+}
+
+/// A cubic Bézier curve whose width changes linearly from `widths[0]` at
+/// the start to `widths[1]` at the end. u runs along the curve and v across
+/// it.
+///
+/// Rays are intersected by splitting the curve until the pieces are almost
+/// straight, in a space where the ray runs along +Z (Nakamaru and Ohno, "Ray
+/// Tracing for Curves Primitive", 2002).
+#[derive(Debug, Clone)]
+pub struct Curve<Mat> {
+    pub control_points: [DVec3; 4],
+    pub widths: [f64; 2],
+    pub shape: CurveShape,
+    pub material: Mat,
+}
+
+/// An intersection in ray space.
+struct Candidate {
+    z: f64,
+    u: f64,
+    v: f64,
+    width: f64,
+}
+
+/// State of the search for the closest intersection along a ray.
+struct Search {
+    /// The normalized ray direction in world space.
+    direction: DVec3,
+    z_min: f64,
+    /// Distance to the closest intersection found so far, or the end of the
+    /// ray.
+    z_max: f64,
+    closest: Option<Candidate>,
+}
+
+fn bezier(cp: &[DVec3; 4], u: f64) -> (DVec3, DVec3) {
+    let a = cp[0].lerp(cp[1], u);
+    let b = cp[1].lerp(cp[2], u);
+    let c = cp[2].lerp(cp[3], u);
+    let d = a.lerp(b, u);
+    let e = b.lerp(c, u);
+    (d.lerp(e, u), 3.0 * (e - d))
+}
+
+/// Corners of the box around the control points, which contains the curve.
+fn hull(cp: &[DVec3; 4]) -> (DVec3, DVec3) {
+    let min = cp
+        .iter()
+        .fold(DVec3::splat(f64::INFINITY), |m, &p| m.min(p));
+    let max = cp
+        .iter()
+        .fold(DVec3::splat(-f64::INFINITY), |m, &p| m.max(p));
+    (min, max)
+}
+
+fn split(cp: &[DVec3; 4]) -> [[DVec3; 4]; 2] {
+    let a = cp[0].lerp(cp[1], 0.5);
+    let b = cp[1].lerp(cp[2], 0.5);
+    let c = cp[2].lerp(cp[3], 0.5);
+    let d = a.lerp(b, 0.5);
+    let e = b.lerp(c, 0.5);
+    let mid = d.lerp(e, 0.5);
+    [[cp[0], a, d, mid], [mid, e, c, cp[3]]]
+}
+
+impl<Mat> Curve<Mat> {
+    fn width_at(&self, u: f64) -> f64 {
+        self.widths[0] + u * (self.widths[1] - self.widths[0])
+    }
+
+    fn ribbon_normal(&self, u: f64) -> Option<DVec3> {
+        match self.shape {
+            CurveShape::Ribbon { normals } => Some(normals[0].lerp(normals[1], u).normalize()),
+            _ => None,
+        }
+    }
+
+    /// Looks for intersections of the piece of the curve from `u0` to `u1`,
+    /// given by its control points `cp` in ray space, with the +Z axis.
+    fn intersect(&self, cp: &[DVec3; 4], (u0, u1): (f64, f64), depth: u32, search: &mut Search) {
+        let half_width = 0.5 * self.width_at(u0).max(self.width_at(u1));
+        let (min, max) = hull(cp);
+        let (min, max) = (min - half_width, max + half_width);
+        if min.x > 0.0 || max.x < 0.0 || min.y > 0.0 || max.y < 0.0 {
+            return;
+        }
+        if max.z < search.z_min || min.z > search.z_max {
+            return;
+        }
+
+        if depth > 0 {
+            let u_mid = 0.5 * (u0 + u1);
+            let [first, second] = split(cp);
+            self.intersect(&first, (u0, u_mid), depth - 1, search);
+            self.intersect(&second, (u_mid, u1), depth - 1, search);
+            return;
+        }
+
+        // The piece is nearly straight: the ray must pass between the lines
+        // perpendicular to it at both ends.
+        let start_edge = (cp[1].y - cp[0].y) * -cp[0].y + cp[0].x * (cp[0].x - cp[1].x);
+        let end_edge = (cp[2].y - cp[3].y) * -cp[3].y + cp[3].x * (cp[3].x - cp[2].x);
+        if start_edge < 0.0 || end_edge < 0.0 {
+            return;
+        }
+
+        // Parameter of the point on the segment closest to the ray.
+        let segment = cp[3].xy() - cp[0].xy();
+        let length_squared = segment.length_squared();
+        if length_squared == 0.0 {
+            return;
+        }
+        let w = (-cp[0].xy().dot(segment) / length_squared).clamp(0.0, 1.0);
+        let u = u0 + w * (u1 - u0);
+
+        let mut width = self.width_at(u);
+        if let Some(normal) = self.ribbon_normal(u) {
+            width *= normal.dot(search.direction).abs();
+        }
+
+        let (point, tangent) = bezier(cp, w);
+        let distance_squared = point.xy().length_squared();
+        if distance_squared > 0.25 * width * width
+            || point.z < search.z_min
+            || point.z > search.z_max
+        {
+            return;
+        }
+        let distance = distance_squared.sqrt();
+        let side = tangent.x * -point.y + point.x * tangent.y;
+        let v = if side > 0.0 {
+            0.5 + distance / width
+        } else {
+            0.5 - distance / width
+        };
+
+        search.z_max = point.z;
+        search.closest = Some(Candidate {
+            z: point.z,
+            u,
+            v,
+            width,
+        });
+    }
+}
+
+impl<Mat: Material> Hittable for Curve<Mat> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        let length = ray.direction.length();
+        let direction = ray.direction / length;
+        let frame = Frame::from_normal(direction);
+        let cp = self.control_points.map(|p| frame.to_local(p - ray.origin));
+
+        // Split often enough that the pieces deviate from straight lines by
+        // a small fraction of the width.
+        let curvature = (0..2)
+            .map(|i| (cp[i] - 2.0 * cp[i + 1] + cp[i + 2]).abs().max_element())
+            .fold(0.0, f64::max);
+        let epsilon = 0.05 * self.widths[0].max(self.widths[1]);
+        let depth = if curvature > 0.0 && epsilon > 0.0 {
+            let depth = (f64::SQRT_2 * 6.0 * curvature / (8.0 * epsilon)).log2() * 0.5;
+            depth.round().clamp(0.0, 10.0) as u32
+        } else {
+            0
+        };
+
+        let mut search = Search {
+            direction,
+            z_min: t_min * length,
+            z_max: t_max * length,
+            closest: None,
+        };
+        self.intersect(&cp, (0.0, 1.0), depth, &mut search);
+        let Candidate { z, u, v, width } = search.closest?;
+
+        let t = z / length;
+        let (_, dpdu) = bezier(&self.control_points, u);
+        let dpdv = match self.ribbon_normal(u) {
+            Some(normal) => normal.cross(dpdu).normalize() * width,
+            None => {
+                let dpdu_local = frame.to_local(dpdu);
+                let mut dpdv_local =
+                    DVec3::new(-dpdu_local.y, dpdu_local.x, 0.0).normalize() * width;
+                if let CurveShape::Cylinder = self.shape {
+                    // Bend the normal from one edge around to the other.
+                    let theta = (v - 0.5) * f64::PI;
+                    dpdv_local = DQuat::from_axis_angle(dpdu_local.normalize(), theta) * dpdv_local;
+                }
+                frame.to_world(dpdv_local)
+            }
+        };
+        let outward_normal = dpdu.cross(dpdv).normalize();
+        let (normal, face) = compute_face_normal(ray, outward_normal);
+
+        let point = ray.at(t);
+        Some(HitRecord {
+            t,
+            point,
+            normal,
+            object_point: point,
+            object_rotation: DQuat::IDENTITY,
+            uv: DVec2::new(u, v),
+            dpdu,
+            dpdv,
+            face,
+            color: DVec3::ONE,
+            material: &self.material,
+        })
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<Aabb> {
+        let half_width = DVec3::splat(0.5 * self.widths[0].max(self.widths[1]));
+        let (min, max) = hull(&self.control_points);
+        Some(Aabb {
+            minimum: min - half_width,
+            maximum: max + half_width,
+        })
+    }
+}
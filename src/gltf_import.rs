@@ -0,0 +1,407 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{bail, Context};
+use base64::Engine;
+use glam::{DAffine3, DMat4, DVec2, DVec3, DVec4};
+use gltf::{camera::Projection, khr_lights_punctual::Kind, mesh::Mode, texture::WrappingMode};
+
+use crate::{
+    camera::{Camera, CameraDescriptor},
+    hittable::{BvhNode, Hittable, Transform, World},
+    light::{Directional, Light, Point, Spot},
+    material::{Material, NormalMap, Principled},
+    mesh::Mesh,
+    mipmap::{ColorSpace, MipMap, Wrap},
+    scene::Scene,
+    sky::{Background, Sky, SkyDescriptor},
+    texture::{Channel, Image, Multiply, Solid, Texture, VertexColor},
+    texture_manager::TextureManager,
+};
+
+/// Converts the photometric units of glTF lights to radiometric ones.
+const LUMENS_PER_WATT: f64 = 683.0;
+
+/// Loads the default scene of a `.gltf` or `.glb` file.
+///
+/// Meshes become triangle hierarchies placed by the transforms of their
+/// nodes, materials become [`Principled`] with the metallic-roughness
+/// parameters, emission, normal map and vertex colors of the file, and
+/// punctual lights (`KHR_lights_punctual`) become [`Point`], [`Spot`] and
+/// [`Directional`] lights. The first perspective camera is used, or one
+/// looking at the whole scene if there is none, and files without lights are
+/// lit by a [`Sky`].
+///
+/// Only the first set of texture coordinates is read, and alpha, occlusion,
+/// skins, morph targets and animations are ignored.
+pub fn load(path: impl AsRef<Path>, aspect_ratio: f64) -> anyhow::Result<Scene> {
+    let path = path.as_ref();
+    Importer::open(path)
+        .and_then(|importer| importer.scene(aspect_ratio))
+        .with_context(|| format!("failed to load glTF scene {}", path.display()))
+}
+
+struct Importer {
+    document: gltf::Document,
+    buffers: Vec<Vec<u8>>,
+    /// Directory against which relative URIs are resolved.
+    directory: PathBuf,
+    textures: TextureManager,
+    /// Images stored inside the file, by index and whether they are sRGB.
+    embedded_images: HashMap<(usize, bool), Arc<MipMap>>,
+    meshes: HashMap<usize, Vec<Arc<BvhNode>>>,
+    world: World,
+    lights: Vec<Box<dyn Light>>,
+    camera: Option<CameraDescriptor>,
+}
+
+fn vec3(v: [f32; 3]) -> DVec3 {
+    DVec3::from(v.map(f64::from))
+}
+
+fn wrap(mode: WrappingMode) -> Wrap {
+    match mode {
+        WrappingMode::ClampToEdge => Wrap::Clamp,
+        WrappingMode::MirroredRepeat => Wrap::Mirror,
+        WrappingMode::Repeat => Wrap::Repeat,
+    }
+}
+
+impl Importer {
+    fn open(path: &Path) -> anyhow::Result<Self> {
+        let gltf::Gltf { document, mut blob } = gltf::Gltf::open(path)?;
+        let directory = path.parent().unwrap_or(Path::new("")).to_owned();
+        let mut buffers = Vec::new();
+        for buffer in document.buffers() {
+            let data = match buffer.source() {
+                gltf::buffer::Source::Bin => blob.take().context("missing binary chunk")?,
+                gltf::buffer::Source::Uri(uri) => read_uri(&directory, uri)?,
+            };
+            if data.len() < buffer.length() {
+                bail!("buffer {} is too short", buffer.index());
+            }
+            buffers.push(data);
+        }
+        Ok(Self {
+            document,
+            buffers,
+            directory,
+            textures: TextureManager::default(),
+            embedded_images: HashMap::new(),
+            meshes: HashMap::new(),
+            world: World::new(),
+            lights: Vec::new(),
+            camera: None,
+        })
+    }
+
+    fn scene(mut self, aspect_ratio: f64) -> anyhow::Result<Scene> {
+        let document = self.document.clone();
+        let scene = document
+            .default_scene()
+            .or_else(|| document.scenes().next())
+            .context("file has no scenes")?;
+        for node in scene.nodes() {
+            self.visit(node, DAffine3::IDENTITY)?;
+        }
+
+        let camera_desc = match self.camera.take() {
+            Some(desc) => CameraDescriptor {
+                aspect_ratio,
+                ..desc
+            },
+            None => self.overview_camera(aspect_ratio),
+        };
+        let background: Box<dyn Background> = if self.lights.is_empty() {
+            let sky = Sky::new(&SkyDescriptor::default());
+            self.lights.push(Box::new(sky.sun()));
+            Box::new(sky)
+        } else {
+            Box::new(DVec3::ZERO)
+        };
+        Ok(Scene {
+            world: self.world,
+            camera: Camera::new(&camera_desc),
+            background,
+            lights: self.lights,
+        })
+    }
+
+    /// Adds the contents of `node` and its children, with `parent` being the
+    /// transformation to world space of the parent node.
+    fn visit(&mut self, node: gltf::Node, parent: DAffine3) -> anyhow::Result<()> {
+        let local = DMat4::from_cols_array_2d(&node.transform().matrix().map(|c| c.map(f64::from)));
+        let matrix = parent * DAffine3::from_mat4(local);
+        let position = matrix.translation;
+
+        if let Some(mesh) = node.mesh() {
+            for primitives in self.mesh(mesh)? {
+                self.world.add(Transform::new(matrix, primitives));
+            }
+        }
+
+        if let Some(camera) = node.camera() {
+            if let (None, Projection::Perspective(perspective)) =
+                (&self.camera, camera.projection())
+            {
+                self.camera = Some(CameraDescriptor {
+                    vfov: f64::from(perspective.yfov()).to_degrees(),
+                    origin: position,
+                    look_at: position + matrix.transform_vector3(DVec3::NEG_Z),
+                    vup: matrix.transform_vector3(DVec3::Y),
+                    ..Default::default()
+                });
+            }
+        }
+
+        if let Some(light) = node.light() {
+            let power = vec3(light.color()) * f64::from(light.intensity()) / LUMENS_PER_WATT;
+            let forward = matrix.transform_vector3(DVec3::NEG_Z).normalize();
+            let light: Box<dyn Light> = match light.kind() {
+                Kind::Directional => Box::new(Directional {
+                    direction: -forward,
+                    irradiance: power,
+                }),
+                Kind::Point => Box::new(Point {
+                    position,
+                    intensity: power,
+                }),
+                Kind::Spot {
+                    inner_cone_angle,
+                    outer_cone_angle,
+                } => Box::new(Spot {
+                    position,
+                    direction: forward,
+                    intensity: power,
+                    cone_angle: outer_cone_angle.into(),
+                    falloff_start: inner_cone_angle.into(),
+                }),
+            };
+            self.lights.push(light);
+        }
+
+        for child in node.children() {
+            self.visit(child, matrix)?;
+        }
+        Ok(())
+    }
+
+    /// Builds a hierarchy of triangles per primitive of `mesh`, shared by
+    /// all nodes using it.
+    fn mesh(&mut self, mesh: gltf::Mesh) -> anyhow::Result<Vec<Arc<BvhNode>>> {
+        if let Some(primitives) = self.meshes.get(&mesh.index()) {
+            return Ok(primitives.clone());
+        }
+
+        let mut primitives = Vec::new();
+        for primitive in mesh.primitives() {
+            // Points and lines have no area to hit.
+            if primitive.mode() != Mode::Triangles {
+                continue;
+            }
+            let gltf_material = primitive.material();
+            let material = self.material(&gltf_material)?;
+
+            let buffers = &self.buffers;
+            let reader = primitive.reader(|buffer| buffers.get(buffer.index()).map(Vec::as_slice));
+            let positions: Vec<DVec3> = reader
+                .read_positions()
+                .context("primitive has no positions")?
+                .map(vec3)
+                .collect();
+            let indices: Vec<usize> = match reader.read_indices() {
+                Some(indices) => indices.into_u32().map(|i| i as usize).collect(),
+                None => (0..positions.len()).collect(),
+            };
+            if let Some(&index) = indices.iter().find(|&&i| i >= positions.len()) {
+                bail!("vertex index {index} out of range");
+            }
+            let mut mesh = Mesh::new(
+                positions,
+                indices.chunks_exact(3).map(<[_]>::to_vec).collect(),
+            );
+            if mesh.faces.is_empty() {
+                continue;
+            }
+            if let Some(normals) = reader.read_normals() {
+                mesh.normals = normals.map(vec3).collect();
+            }
+            if let Some(uvs) = reader.read_tex_coords(0) {
+                mesh.uvs = uvs
+                    .into_f32()
+                    .map(|[u, v]| DVec2::new(u.into(), v.into()))
+                    .collect();
+            }
+            if let Some(colors) = reader.read_colors(0) {
+                mesh.colors = colors.into_rgb_f32().map(vec3).collect();
+            }
+            if let Some(tangents) = reader.read_tangents() {
+                mesh.tangents = tangents.map(|t| DVec4::from(t.map(f64::from))).collect();
+            } else if gltf_material.normal_texture().is_some() {
+                // glTF normal maps point green towards decreasing v.
+                mesh.compute_tangents();
+                for tangent in &mut mesh.tangents {
+                    tangent.w = -tangent.w;
+                }
+            }
+            primitives.push(Arc::new(mesh.to_bvh(material)));
+        }
+        self.meshes.insert(mesh.index(), primitives.clone());
+        Ok(primitives)
+    }
+
+    fn material(&mut self, material: &gltf::Material) -> anyhow::Result<Arc<dyn Material>> {
+        let pbr = material.pbr_metallic_roughness();
+        let [r, g, b, _] = pbr.base_color_factor();
+        let base_color = self.factor_texture(
+            vec3([r, g, b]),
+            pbr.base_color_texture().map(|info| info.texture()),
+            None,
+        )?;
+        let metallic_roughness = pbr.metallic_roughness_texture().map(|info| info.texture());
+        let metallic = self.factor_texture(
+            DVec3::splat(pbr.metallic_factor().into()),
+            metallic_roughness.clone(),
+            Some(2),
+        )?;
+        let roughness = self.factor_texture(
+            DVec3::splat(pbr.roughness_factor().into()),
+            metallic_roughness,
+            Some(1),
+        )?;
+        let emission = self.factor_texture(
+            vec3(material.emissive_factor()),
+            material.emissive_texture().map(|info| info.texture()),
+            None,
+        )?;
+        let principled = Principled {
+            // Vertex colors are white for primitives without them.
+            base_color: Arc::new(Multiply {
+                a: base_color,
+                b: VertexColor,
+            }),
+            metallic,
+            roughness,
+            emission,
+            ..Default::default()
+        };
+
+        Ok(match material.normal_texture() {
+            Some(normal) => Arc::new(NormalMap {
+                inner: principled,
+                normals: self.image(normal.texture(), ColorSpace::Linear)?,
+                strength: normal.scale().into(),
+            }),
+            None => Arc::new(principled),
+        })
+    }
+
+    /// `factor` times the texture if there is one, optionally spreading one
+    /// of its channels. Colors are sRGB encoded, other parameters linear.
+    fn factor_texture(
+        &mut self,
+        factor: DVec3,
+        texture: Option<gltf::Texture>,
+        channel: Option<usize>,
+    ) -> anyhow::Result<Arc<dyn Texture>> {
+        let factor = Solid { color: factor };
+        let Some(texture) = texture else {
+            return Ok(Arc::new(factor));
+        };
+        Ok(match channel {
+            Some(channel) => Arc::new(Multiply {
+                a: factor,
+                b: Channel {
+                    inner: self.image(texture, ColorSpace::Linear)?,
+                    channel,
+                },
+            }),
+            None => Arc::new(Multiply {
+                a: factor,
+                b: self.image(texture, ColorSpace::Srgb)?,
+            }),
+        })
+    }
+
+    fn image(&mut self, texture: gltf::Texture, color_space: ColorSpace) -> anyhow::Result<Image> {
+        let source = texture.source();
+        let mipmap = match source.source() {
+            gltf::image::Source::Uri { uri, .. } if !uri.starts_with("data:") => {
+                let path = self.directory.join(urlencoding::decode(uri)?.as_ref());
+                self.textures.load(path, color_space)?
+            }
+            image_source => {
+                let key = (source.index(), color_space == ColorSpace::Srgb);
+                match self.embedded_images.get(&key) {
+                    Some(mipmap) => mipmap.clone(),
+                    None => {
+                        let bytes = match image_source {
+                            gltf::image::Source::View { view, .. } => {
+                                let buffer = &self.buffers[view.buffer().index()];
+                                buffer
+                                    .get(view.offset()..view.offset() + view.length())
+                                    .context("image buffer view out of range")?
+                                    .to_vec()
+                            }
+                            gltf::image::Source::Uri { uri, .. } => read_uri(&self.directory, uri)?,
+                        };
+                        let image = image::load_from_memory(&bytes).with_context(|| {
+                            format!("failed to decode image {}", source.index())
+                        })?;
+                        let mipmap = Arc::new(MipMap::with_format(
+                            &image,
+                            color_space,
+                            self.textures.format,
+                        ));
+                        self.embedded_images.insert(key, mipmap.clone());
+                        mipmap
+                    }
+                }
+            }
+        };
+        Ok(Image {
+            wrap: wrap(texture.sampler().wrap_s()),
+            ..Image::new(mipmap)
+        })
+    }
+
+    /// A camera looking at the whole scene along -z.
+    fn overview_camera(&self, aspect_ratio: f64) -> CameraDescriptor {
+        let vfov: f64 = 40.0;
+        let (center, radius) = match self.world.bounding_box(0.0, 0.0) {
+            Some(bounds) => (
+                0.5 * (bounds.minimum + bounds.maximum),
+                0.5 * (bounds.maximum - bounds.minimum).length(),
+            ),
+            None => (DVec3::ZERO, 1.0),
+        };
+        let distance = radius / (0.5 * vfov.to_radians()).sin();
+        CameraDescriptor {
+            vfov,
+            aspect_ratio,
+            origin: center + distance * DVec3::Z,
+            look_at: center,
+            ..Default::default()
+        }
+    }
+}
+
+/// Reads a file relative to `directory`, or decodes a base64 data URI.
+fn read_uri(directory: &Path, uri: &str) -> anyhow::Result<Vec<u8>> {
+    match uri.strip_prefix("data:") {
+        Some(data) => {
+            let (_, encoded) = data
+                .split_once(";base64,")
+                .context("unsupported data URI")?;
+            Ok(base64::engine::general_purpose::STANDARD.decode(encoded)?)
+        }
+        None => {
+            let path = directory.join(urlencoding::decode(uri)?.as_ref());
+            fs::read(&path).with_context(|| format!("failed to read {}", path.display()))
+        }
+    }
+}
@@ -0,0 +1,294 @@
+use std::path::Path;
+
+use anyhow::{bail, Context};
+use glam::{DVec2, DVec3};
+
+use crate::{
+    hittable::{hit_triangle, Aabb, HitRecord, Hittable, VertexAttributes},
+    material::Material,
+    perlin::Perlin,
+    ray::Ray,
+};
+
+/// A grid of height samples, usually in `0..=1`, stored row by row.
+#[derive(Debug, Clone)]
+pub struct HeightMap {
+    pub width: usize,
+    pub depth: usize,
+    pub heights: Vec<f64>,
+}
+
+impl HeightMap {
+    /// Reads the brightness of a grayscale image, with rows running along z.
+    /// The image needs at least 2x2 pixels.
+    pub fn from_image(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let image = image::open(path)
+            .with_context(|| format!("failed to load height map {}", path.display()))?
+            .into_luma16();
+        if image.width() < 2 || image.height() < 2 {
+            bail!(
+                "height map {} needs at least 2x2 pixels, found {}x{}",
+                path.display(),
+                image.width(),
+                image.height()
+            );
+        }
+        Ok(Self {
+            width: image.width() as usize,
+            depth: image.height() as usize,
+            heights: image
+                .pixels()
+                .map(|pixel| f64::from(pixel.0[0]) / f64::from(u16::MAX))
+                .collect(),
+        })
+    }
+
+    /// Samples `octaves` of fractal noise, with `frequency` lattice cells
+    /// across the map. The map needs at least 2x2 samples.
+    pub fn from_noise(
+        perlin: &Perlin,
+        width: usize,
+        depth: usize,
+        frequency: f64,
+        octaves: usize,
+    ) -> anyhow::Result<Self> {
+        if width < 2 || depth < 2 {
+            bail!("height map needs at least 2x2 samples, found {width}x{depth}");
+        }
+        let mut heights = Vec::with_capacity(width * depth);
+        for j in 0..depth {
+            for i in 0..width {
+                // Offset from the lattice planes, where the noise is zero.
+                let point = DVec3::new(
+                    frequency * i as f64 / width as f64,
+                    0.5,
+                    frequency * j as f64 / depth as f64,
+                );
+                let noise = perlin.fbm(point, octaves, 2.0, 0.5);
+                heights.push((0.5 + 0.5 * noise).clamp(0.0, 1.0));
+            }
+        }
+        Ok(Self {
+            width,
+            depth,
+            heights,
+        })
+    }
+
+    fn get(&self, i: usize, j: usize) -> f64 {
+        self.heights[j * self.width + i]
+    }
+}
+
+/// Terrain from a [`HeightMap`], with two triangles between each four
+/// neighboring samples. Rays walk the grid cell by cell, skipping cells whose
+/// height range they pass above or below.
+pub struct Heightfield<Mat> {
+    map: HeightMap,
+    /// Corner with the smallest x and z, at a height of 0.
+    origin: DVec3,
+    /// Extent along x and z, and the height of a sample of 1.
+    size: DVec3,
+    cell: DVec3,
+    normals: Vec<DVec3>,
+    /// Lowest and highest sample around each cell.
+    ranges: Vec<(f64, f64)>,
+    bounds: Aabb,
+    material: Mat,
+}
+
+impl<Mat> Heightfield<Mat> {
+    /// Fails if `map` has fewer than 2x2 samples, or not one per grid point.
+    pub fn new(map: HeightMap, origin: DVec3, size: DVec3, material: Mat) -> anyhow::Result<Self> {
+        if map.width < 2 || map.depth < 2 {
+            bail!(
+                "height map needs at least 2x2 samples, found {}x{}",
+                map.width,
+                map.depth
+            );
+        }
+        if map.heights.len() != map.width * map.depth {
+            bail!(
+                "expected {} heights for a {}x{} map, found {}",
+                map.width * map.depth,
+                map.width,
+                map.depth,
+                map.heights.len()
+            );
+        }
+        let cell = DVec3::new(
+            size.x / (map.width - 1) as f64,
+            size.y,
+            size.z / (map.depth - 1) as f64,
+        );
+
+        let mut normals = Vec::with_capacity(map.heights.len());
+        for j in 0..map.depth {
+            for i in 0..map.width {
+                let (i0, i1) = (i.saturating_sub(1), (i + 1).min(map.width - 1));
+                let (j0, j1) = (j.saturating_sub(1), (j + 1).min(map.depth - 1));
+                let dx = (map.get(i1, j) - map.get(i0, j)) * size.y / ((i1 - i0) as f64 * cell.x);
+                let dz = (map.get(i, j1) - map.get(i, j0)) * size.y / ((j1 - j0) as f64 * cell.z);
+                normals.push(DVec3::new(-dx, 1.0, -dz).normalize());
+            }
+        }
+
+        let mut ranges = Vec::with_capacity((map.width - 1) * (map.depth - 1));
+        for j in 0..map.depth - 1 {
+            for i in 0..map.width - 1 {
+                let corners = [
+                    map.get(i, j),
+                    map.get(i + 1, j),
+                    map.get(i, j + 1),
+                    map.get(i + 1, j + 1),
+                ];
+                let low = corners.into_iter().fold(f64::INFINITY, f64::min);
+                let high = corners.into_iter().fold(f64::NEG_INFINITY, f64::max);
+                ranges.push((low, high));
+            }
+        }
+
+        let low = map.heights.iter().copied().fold(f64::INFINITY, f64::min);
+        let high = map
+            .heights
+            .iter()
+            .copied()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let epsilon = DVec3::splat(0.0001);
+        let bounds = Aabb {
+            minimum: origin + DVec3::new(0.0, low * size.y, 0.0) - epsilon,
+            maximum: origin + DVec3::new(size.x, high * size.y, size.z) + epsilon,
+        };
+
+        Ok(Self {
+            map,
+            origin,
+            size,
+            cell,
+            normals,
+            ranges,
+            bounds,
+            material,
+        })
+    }
+
+    fn vertex(&self, i: usize, j: usize) -> DVec3 {
+        self.origin
+            + DVec3::new(
+                i as f64 * self.cell.x,
+                self.map.get(i, j) * self.size.y,
+                j as f64 * self.cell.z,
+            )
+    }
+
+    fn uv(&self, i: usize, j: usize) -> DVec2 {
+        DVec2::new(
+            i as f64 / (self.map.width - 1) as f64,
+            j as f64 / (self.map.depth - 1) as f64,
+        )
+    }
+}
+
+impl<Mat: Material> Heightfield<Mat> {
+    fn cell_hit<'a>(
+        &'a self,
+        i: usize,
+        j: usize,
+        ray: &Ray,
+        t_min: f64,
+        t_max: f64,
+    ) -> Option<HitRecord<'a>> {
+        let width = self.map.width;
+        let corners = [(i, j), (i + 1, j + 1), (i + 1, j), (i, j + 1)];
+        let vertices = corners.map(|(i, j)| self.vertex(i, j));
+        let normals = corners.map(|(i, j)| self.normals[j * width + i]);
+        let uvs = corners.map(|(i, j)| self.uv(i, j));
+
+        // Split along the diagonal from (i, j) to (i + 1, j + 1), wound so
+        // that the geometric normals point up.
+        let mut closest = None;
+        let mut t_max = t_max;
+        for [a, b, c] in [[0, 1, 2], [0, 3, 1]] {
+            let attributes = VertexAttributes {
+                normals: Some([normals[a], normals[b], normals[c]]),
+                tangents: None,
+                uvs: [uvs[a], uvs[b], uvs[c]],
+                colors: None,
+            };
+            if let Some(hit) = hit_triangle(
+                [vertices[a], vertices[b], vertices[c]],
+                &attributes,
+                &self.material,
+                ray,
+                t_min,
+                t_max,
+            ) {
+                t_max = hit.t;
+                closest = Some(hit);
+            }
+        }
+        closest
+    }
+}
+
+impl<Mat: Material> Hittable for Heightfield<Mat> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        let (t_enter, t_exit) = self.bounds.clip(ray, t_min, t_max)?;
+        let cells = [self.map.width - 1, self.map.depth - 1];
+        let cell_size = [self.cell.x, self.cell.z];
+        let start = ray.at(t_enter) - self.origin;
+        let start = [start.x, start.z];
+        let direction = [ray.direction.x, ray.direction.z];
+
+        // Grid DDA over the cells in the xz plane.
+        let mut index = [0; 2];
+        let mut step = [0; 2];
+        let mut t_next = [f64::INFINITY; 2];
+        let mut t_delta = [f64::INFINITY; 2];
+        for axis in 0..2 {
+            let cell = (start[axis] / cell_size[axis]).floor() as isize;
+            index[axis] = cell.clamp(0, cells[axis] as isize - 1);
+            if direction[axis] > 0.0 {
+                step[axis] = 1;
+                let boundary = (index[axis] + 1) as f64 * cell_size[axis];
+                t_next[axis] = t_enter + (boundary - start[axis]) / direction[axis];
+                t_delta[axis] = cell_size[axis] / direction[axis];
+            } else if direction[axis] < 0.0 {
+                step[axis] = -1;
+                let boundary = index[axis] as f64 * cell_size[axis];
+                t_next[axis] = t_enter + (boundary - start[axis]) / direction[axis];
+                t_delta[axis] = -cell_size[axis] / direction[axis];
+            }
+        }
+
+        let mut t = t_enter;
+        loop {
+            let t_leave = t_next[0].min(t_next[1]).min(t_exit);
+            let (i, j) = (index[0] as usize, index[1] as usize);
+            let (low, high) = self.ranges[j * cells[0] + i];
+            let y0 = ray.at(t).y - self.origin.y;
+            let y1 = ray.at(t_leave).y - self.origin.y;
+            if y0.min(y1) <= high * self.size.y && y0.max(y1) >= low * self.size.y {
+                if let Some(hit) = self.cell_hit(i, j, ray, t_min, t_max) {
+                    return Some(hit);
+                }
+            }
+            if t_leave >= t_exit {
+                return None;
+            }
+
+            let axis = if t_next[0] < t_next[1] { 0 } else { 1 };
+            index[axis] += step[axis];
+            if index[axis] < 0 || index[axis] >= cells[axis] as isize {
+                return None;
+            }
+            t = t_next[axis];
+            t_next[axis] += t_delta[axis];
+        }
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<Aabb> {
+        Some(self.bounds)
+    }
+}
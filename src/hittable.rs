@@ -1,7 +1,7 @@
 use std::{f64::consts as f64, fmt::Debug, ops::Deref, sync::Arc};
 
-use crate::{material::Material, ray::Ray};
-use glam::{DVec2, DVec3, Vec3Swizzles};
+use crate::{material::Material, microfacet::Frame, ray::Ray};
+use glam::{DAffine3, DMat3, DQuat, DVec2, DVec3, DVec4, Vec3Swizzles};
 use rand::Rng;
 
 #[derive(Clone)]
@@ -9,18 +9,48 @@ pub struct HitRecord<'a> {
     pub t: f64,
     pub point: DVec3,
     pub normal: DVec3,
+    /// `point` in the space of the primitive that was hit, before transforms
+    /// such as [`Translate`] or [`RotateY`] are applied.
+    pub object_point: DVec3,
+    /// Rotation from the space of the primitive to world space.
+    pub object_rotation: DQuat,
     pub uv: DVec2,
+    /// Partial derivatives of `point` with respect to `uv`.
+    pub dpdu: DVec3,
+    pub dpdv: DVec3,
     pub face: Face,
+    /// Color of the vertices or particles of the shape around the hit,
+    /// white for shapes without colors. See
+    /// [`VertexColor`](crate::texture::VertexColor).
+    pub color: DVec3,
     pub material: &'a dyn Material,
 }
 
+impl HitRecord<'_> {
+    /// Extent in uv space of the cone of rays around `ray` where it hits the
+    /// surface, for filtering texture lookups.
+    pub fn uv_footprint(&self, ray: &Ray) -> DVec2 {
+        let cos = ray.direction.normalize().dot(self.normal).abs().max(0.1);
+        let width = ray.width_at(self.t) / cos;
+        let per_unit = |d: DVec3| {
+            let length = d.length();
+            if length > 0.0 {
+                width / length
+            } else {
+                0.0
+            }
+        };
+        DVec2::new(per_unit(self.dpdu), per_unit(self.dpdv))
+    }
+}
+
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum Face {
     Front,
     Back,
 }
 
-fn compute_face_normal(ray: &Ray, outward_normal: DVec3) -> (DVec3, Face) {
+pub(crate) fn compute_face_normal(ray: &Ray, outward_normal: DVec3) -> (DVec3, Face) {
     if ray.direction.dot(outward_normal) < 0.0 {
         (outward_normal, Face::Front)
     } else {
@@ -36,11 +66,16 @@ pub struct Aabb {
 
 impl Aabb {
     pub fn is_hit_by(&self, ray: &Ray, t_min: f64, t_max: f64) -> bool {
+        self.clip(ray, t_min, t_max).is_some()
+    }
+
+    /// The part of `t_min..t_max` where the ray is inside the box.
+    pub fn clip(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(f64, f64)> {
         let a = (self.minimum - ray.origin) / ray.direction;
         let b = (self.maximum - ray.origin) / ray.direction;
         let t0 = a.min(b).max_element().max(t_min);
         let t1 = a.max(b).min_element().min(t_max);
-        t0 < t1
+        (t0 < t1).then_some((t0, t1))
     }
 
     pub fn union(&self, other: &Self) -> Self {
@@ -58,10 +93,53 @@ impl Aabb {
     }
 }
 
-pub trait Hittable {
+pub trait Hittable: Send + Sync {
     fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>>;
 
     fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<Aabb>;
+
+    /// Chooses a direction from `origin` towards a random point on the
+    /// surface, so that the object can be sampled as an area light. Returns
+    /// `None` for objects that don't support this.
+    fn sample_direction(&self, origin: DVec3) -> Option<DVec3> {
+        let _ = origin;
+        None
+    }
+
+    /// Solid angle density with which [`Hittable::sample_direction`] would
+    /// choose the normalized `direction`.
+    fn direction_pdf(&self, origin: DVec3, direction: DVec3) -> f64 {
+        let _ = (origin, direction);
+        0.0
+    }
+}
+
+/// Solid angle density of `direction` from `origin`, for a shape whose
+/// surface of the given `area` is sampled uniformly.
+fn area_to_solid_angle_pdf<H: Hittable + ?Sized>(
+    shape: &H,
+    area: f64,
+    origin: DVec3,
+    direction: DVec3,
+) -> f64 {
+    let ray = Ray {
+        origin,
+        direction,
+        time: 0.0,
+        width: 0.0,
+        spread: 0.0,
+    };
+    let hit = match shape.hit(&ray, 0.001, f64::INFINITY) {
+        Some(hit) => hit,
+        None => return 0.0,
+    };
+    let distance = hit.t * direction.length();
+    let cos = direction.normalize().dot(hit.normal).abs();
+    if cos * area > 0.0 {
+        distance * distance / (cos * area)
+    } else {
+        0.0
+    }
 }
 
 impl<T: Hittable> Hittable for &T {
@@ -72,15 +150,31 @@ impl<T: Hittable> Hittable for &T {
     fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<Aabb> {
         T::bounding_box(*self, start_time, end_time)
     }
+
+    fn sample_direction(&self, origin: DVec3) -> Option<DVec3> {
+        T::sample_direction(*self, origin)
+    }
+
+    fn direction_pdf(&self, origin: DVec3, direction: DVec3) -> f64 {
+        T::direction_pdf(*self, origin, direction)
+    }
 }
 
 impl<T: Hittable> Hittable for Box<T> {
     fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
-        T::hit(&self, ray, t_min, t_max)
+        T::hit(self, ray, t_min, t_max)
     }
 
     fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<Aabb> {
-        T::bounding_box(&self, start_time, end_time)
+        T::bounding_box(self, start_time, end_time)
+    }
+
+    fn sample_direction(&self, origin: DVec3) -> Option<DVec3> {
+        T::sample_direction(self, origin)
+    }
+
+    fn direction_pdf(&self, origin: DVec3, direction: DVec3) -> f64 {
+        T::direction_pdf(self, origin, direction)
     }
 }
 
@@ -93,15 +187,31 @@ impl Hittable for Box<dyn Hittable> {
     fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<Aabb> {
         self.deref().bounding_box(start_time, end_time)
     }
+
+    fn sample_direction(&self, origin: DVec3) -> Option<DVec3> {
+        self.deref().sample_direction(origin)
+    }
+
+    fn direction_pdf(&self, origin: DVec3, direction: DVec3) -> f64 {
+        self.deref().direction_pdf(origin, direction)
+    }
 }
 
 impl<T: Hittable> Hittable for Arc<T> {
     fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
-        T::hit(&self, ray, t_min, t_max)
+        T::hit(self, ray, t_min, t_max)
     }
 
     fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<Aabb> {
-        T::bounding_box(&self, start_time, end_time)
+        T::bounding_box(self, start_time, end_time)
+    }
+
+    fn sample_direction(&self, origin: DVec3) -> Option<DVec3> {
+        T::sample_direction(self, origin)
+    }
+
+    fn direction_pdf(&self, origin: DVec3, direction: DVec3) -> f64 {
+        T::direction_pdf(self, origin, direction)
     }
 }
 
@@ -127,6 +237,7 @@ impl<T: Hittable> Hittable for [T] {
     }
 }
 
+#[derive(Default)]
 pub struct World {
     pub objects: Vec<Box<dyn Hittable>>,
 }
@@ -144,7 +255,7 @@ impl World {
 }
 
 impl Hittable for World {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         self.objects.hit(ray, t_min, t_max)
     }
 
@@ -166,10 +277,25 @@ impl<Mat: Material> Sphere<Mat> {
         let longitude = (-point.z).atan2(point.x) + f64::PI;
         DVec2::new(longitude / f64::TAU, latitude / f64::PI)
     }
+
+    /// Partial derivatives of the surface with respect to the coordinates
+    /// returned by `get_uv`, at the given point on the unit sphere.
+    fn get_dpduv(&self, point: DVec3) -> (DVec3, DVec3) {
+        let dpdu = f64::TAU * self.radius * DVec3::new(point.z, 0.0, -point.x);
+        let sin_latitude = (1.0 - point.y * point.y).max(1.0e-8).sqrt();
+        let dpdv = f64::PI
+            * self.radius
+            * DVec3::new(
+                point.y * point.x / sin_latitude,
+                -sin_latitude,
+                point.y * point.z / sin_latitude,
+            );
+        (dpdu, dpdv)
+    }
 }
 
 impl<Mat: Material> Hittable for Sphere<Mat> {
-    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord> {
+    fn hit(&self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'_>> {
         let center_to_origin = ray.origin - self.center;
 
         let a = ray.direction.length_squared();
@@ -195,13 +321,19 @@ impl<Mat: Material> Hittable for Sphere<Mat> {
         let outward_normal = (point - self.center) / self.radius;
         let (normal, face) = compute_face_normal(ray, outward_normal);
         let uv = self.get_uv(outward_normal);
+        let (dpdu, dpdv) = self.get_dpduv(outward_normal);
 
         Some(HitRecord {
             t,
             point,
             normal,
+            object_point: point,
+            object_rotation: DQuat::IDENTITY,
             uv,
+            dpdu,
+            dpdv,
             face,
+            color: DVec3::ONE,
             material: &self.material,
         })
     }
@@ -212,6 +344,36 @@ impl<Mat: Material> Hittable for Sphere<Mat> {
             maximum: self.center + DVec3::splat(self.radius),
         })
     }
+
+    fn sample_direction(&self, origin: DVec3) -> Option<DVec3> {
+        // Uniformly samples the cone of directions subtended by the sphere.
+        let to_center = self.center - origin;
+        let distance_squared = to_center.length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return None;
+        }
+        let cos_max = (1.0 - self.radius * self.radius / distance_squared).sqrt();
+        let mut rng = rand::thread_rng();
+        let cos_theta = 1.0 - rng.gen::<f64>() * (1.0 - cos_max);
+        let sin_theta = (1.0 - cos_theta * cos_theta).max(0.0).sqrt();
+        let phi = f64::TAU * rng.gen::<f64>();
+        let axis = to_center / distance_squared.sqrt();
+        let (u, v) = axis.any_orthonormal_pair();
+        Some(sin_theta * phi.cos() * u + sin_theta * phi.sin() * v + cos_theta * axis)
+    }
+
+    fn direction_pdf(&self, origin: DVec3, direction: DVec3) -> f64 {
+        let to_center = self.center - origin;
+        let distance_squared = to_center.length_squared();
+        if distance_squared <= self.radius * self.radius {
+            return 0.0;
+        }
+        let cos_max = (1.0 - self.radius * self.radius / distance_squared).sqrt();
+        if direction.dot(to_center) < cos_max * direction.length() * distance_squared.sqrt() {
+            return 0.0;
+        }
+        1.0 / (f64::TAU * (1.0 - cos_max))
+    }
 }
 
 /// Encapsulates a hittable in a moving reference frame with the given velocity.
@@ -340,12 +502,23 @@ impl<Mat: Material> Hittable for Rect<Mat> {
             Plane::ZX => DVec3::Y,
         };
         let (normal, face) = compute_face_normal(ray, outward_normal);
+        let (u_axis, v_axis) = match self.plane {
+            Plane::XY => (DVec3::X, DVec3::Y),
+            Plane::YZ => (DVec3::Y, DVec3::Z),
+            Plane::ZX => (DVec3::Z, DVec3::X),
+        };
+        let extent = self.max - self.min;
         Some(HitRecord {
             t,
             point,
             normal,
+            object_point: point,
+            object_rotation: DQuat::IDENTITY,
             uv,
+            dpdu: extent.x * u_axis,
+            dpdv: extent.y * v_axis,
             face,
+            color: DVec3::ONE,
             material: &self.material,
         })
     }
@@ -362,6 +535,32 @@ impl<Mat: Material> Hittable for Rect<Mat> {
             maximum: swizzle(self.max.extend(self.k + epsilon)),
         })
     }
+
+    fn sample_direction(&self, origin: DVec3) -> Option<DVec3> {
+        let mut rng = rand::thread_rng();
+        let xy = self.min + DVec2::new(rng.gen(), rng.gen()) * (self.max - self.min);
+        let point = match self.plane {
+            Plane::XY => xy.extend(self.k),
+            Plane::YZ => DVec3::new(self.k, xy.x, xy.y),
+            Plane::ZX => DVec3::new(xy.y, self.k, xy.x),
+        };
+        Some(point - origin)
+    }
+
+    fn direction_pdf(&self, origin: DVec3, direction: DVec3) -> f64 {
+        let extent = self.max - self.min;
+        area_to_solid_angle_pdf(self, extent.x * extent.y, origin, direction)
+    }
+}
+
+/// How uv coordinates are laid out on the sides of a [`Cuboid`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CuboidUv {
+    /// Each side covers the whole unit square.
+    PerFace,
+    /// The sides are unfolded into a horizontal cross, four sides wide and
+    /// three high, with +Y on top of +Z and -X, +Z, +X, -Z in the middle row.
+    CubeCross,
 }
 
 // haha can't name anything box
@@ -369,16 +568,25 @@ pub struct Cuboid<Mat> {
     min: DVec3,
     max: DVec3,
     sides: [Rect<Mat>; 6],
+    uv_layout: CuboidUv,
 }
 
 impl<Mat> Cuboid<Mat> {
     pub fn new(min: DVec3, max: DVec3, material: Mat) -> Self
+    where
+        Mat: Clone,
+    {
+        Self::with_uv_layout(min, max, material, CuboidUv::PerFace)
+    }
+
+    pub fn with_uv_layout(min: DVec3, max: DVec3, material: Mat, uv_layout: CuboidUv) -> Self
     where
         Mat: Clone,
     {
         Self {
             min,
             max,
+            uv_layout,
             sides: [
                 Rect {
                     plane: Plane::XY,
@@ -420,13 +628,72 @@ impl<Mat> Cuboid<Mat> {
                     min: min.zx(),
                     max: max.zx(),
                     k: max.y,
-                    material: material,
+                    material,
                 },
             ],
         }
     }
 }
 
+impl<Mat: Material> Cuboid<Mat> {
+    /// Closest hit among the sides, along with the index of the side. The
+    /// sides all face towards +axis, so the face is flipped for the ones at
+    /// `min` to tell entering and leaving the cuboid apart.
+    fn side_hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<(usize, HitRecord<'a>)> {
+        let mut closest = None;
+        let mut t_max = t_max;
+        for (index, side) in self.sides.iter().enumerate() {
+            if let Some(hit) = side.hit(ray, t_min, t_max) {
+                t_max = hit.t;
+                closest = Some((index, hit));
+            }
+        }
+        let (index, mut hit) = closest?;
+        if index % 2 == 0 {
+            hit.face = match hit.face {
+                Face::Front => Face::Back,
+                Face::Back => Face::Front,
+            };
+        }
+        Some((index, hit))
+    }
+
+    fn cube_cross_hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        let (index, hit) = self.side_hit(ray, t_min, t_max)?;
+
+        // Position of the side's cell in the cross, and the axes along which
+        // the cell's s and t (pointing right and down in the image) run.
+        let (cell, s_axis, t_axis) = match index {
+            0 => ((3.0, 1.0), -DVec3::X, -DVec3::Y),
+            1 => ((1.0, 1.0), DVec3::X, -DVec3::Y),
+            2 => ((0.0, 1.0), DVec3::Z, -DVec3::Y),
+            3 => ((2.0, 1.0), -DVec3::Z, -DVec3::Y),
+            4 => ((1.0, 2.0), DVec3::X, -DVec3::Z),
+            _ => ((1.0, 0.0), DVec3::X, DVec3::Z),
+        };
+        let extent = self.max - self.min;
+        let local = (hit.point - self.min) / extent;
+        let along = |axis: DVec3| {
+            let x = local.dot(axis);
+            if x < 0.0 {
+                1.0 + x
+            } else {
+                x
+            }
+        };
+        let uv = DVec2::new(
+            (cell.0 + along(s_axis)) / 4.0,
+            (cell.1 + along(t_axis)) / 3.0,
+        );
+        Some(HitRecord {
+            uv,
+            dpdu: 4.0 * s_axis * extent,
+            dpdv: 3.0 * t_axis * extent,
+            ..hit
+        })
+    }
+}
+
 impl<Mat: Material> Hittable for Cuboid<Mat> {
     fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
         if self
@@ -434,7 +701,10 @@ impl<Mat: Material> Hittable for Cuboid<Mat> {
             .map(|bb| bb.is_hit_by(ray, t_min, t_max))
             .unwrap_or(true)
         {
-            self.sides.hit(ray, t_min, t_max)
+            match self.uv_layout {
+                CuboidUv::PerFace => self.side_hit(ray, t_min, t_max).map(|(_, hit)| hit),
+                CuboidUv::CubeCross => self.cube_cross_hit(ray, t_min, t_max),
+            }
         } else {
             None
         }
@@ -448,6 +718,344 @@ impl<Mat: Material> Hittable for Cuboid<Mat> {
     }
 }
 
+/// A parallelogram spanned by the edges `u` and `v` from `corner`. The uv
+/// coordinates run from 0 to 1 along the edges.
+#[derive(Debug, Clone)]
+pub struct Quad<Mat> {
+    pub corner: DVec3,
+    pub u: DVec3,
+    pub v: DVec3,
+    pub material: Mat,
+}
+
+impl<Mat: Material> Hittable for Quad<Mat> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        let n = self.u.cross(self.v);
+        let denominator = n.dot(ray.direction);
+        if denominator == 0.0 {
+            return None;
+        }
+        let t = n.dot(self.corner - ray.origin) / denominator;
+        if t < t_min || t > t_max {
+            return None;
+        }
+        let point = ray.at(t);
+        let d = point - self.corner;
+        let w = n / n.length_squared();
+        let uv = DVec2::new(w.dot(d.cross(self.v)), w.dot(self.u.cross(d)));
+        if uv.cmplt(DVec2::ZERO).any() || uv.cmpgt(DVec2::ONE).any() {
+            return None;
+        }
+        let (normal, face) = compute_face_normal(ray, n.normalize());
+        Some(HitRecord {
+            t,
+            point,
+            normal,
+            object_point: point,
+            object_rotation: DQuat::IDENTITY,
+            uv,
+            dpdu: self.u,
+            dpdv: self.v,
+            face,
+            color: DVec3::ONE,
+            material: &self.material,
+        })
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<Aabb> {
+        let epsilon = DVec3::splat(0.0001);
+        let corners = [
+            self.corner,
+            self.corner + self.u,
+            self.corner + self.v,
+            self.corner + self.u + self.v,
+        ];
+        let minimum = corners.iter().fold(corners[0], |acc, &c| acc.min(c));
+        let maximum = corners.iter().fold(corners[0], |acc, &c| acc.max(c));
+        Some(Aabb {
+            minimum: minimum - epsilon,
+            maximum: maximum + epsilon,
+        })
+    }
+
+    fn sample_direction(&self, origin: DVec3) -> Option<DVec3> {
+        let mut rng = rand::thread_rng();
+        let point = self.corner + rng.gen::<f64>() * self.u + rng.gen::<f64>() * self.v;
+        Some(point - origin)
+    }
+
+    fn direction_pdf(&self, origin: DVec3, direction: DVec3) -> f64 {
+        let area = self.u.cross(self.v).length();
+        area_to_solid_angle_pdf(self, area, origin, direction)
+    }
+}
+
+/// A flat disk facing `normal`, or an annulus if `inner_radius` is positive.
+/// u is the angle around the center and v runs from the outer to the inner
+/// edge.
+#[derive(Debug, Clone)]
+pub struct Disk<Mat> {
+    pub center: DVec3,
+    pub normal: DVec3,
+    pub radius: f64,
+    pub inner_radius: f64,
+    pub material: Mat,
+}
+
+impl<Mat> Disk<Mat> {
+    fn area(&self) -> f64 {
+        f64::PI * (self.radius * self.radius - self.inner_radius * self.inner_radius)
+    }
+}
+
+impl<Mat: Material> Hittable for Disk<Mat> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        let frame = Frame::from_normal(self.normal.normalize());
+        let denominator = frame.normal.dot(ray.direction);
+        if denominator == 0.0 {
+            return None;
+        }
+        let t = frame.normal.dot(self.center - ray.origin) / denominator;
+        if t < t_min || t > t_max {
+            return None;
+        }
+        let point = ray.at(t);
+        let local = frame.to_local(point - self.center);
+        let r = local.x.hypot(local.y);
+        if r > self.radius || r < self.inner_radius {
+            return None;
+        }
+        let phi = local.y.atan2(local.x).rem_euclid(f64::TAU);
+        let width = self.radius - self.inner_radius;
+        let uv = DVec2::new(phi / f64::TAU, (self.radius - r) / width);
+        let radial = if r > 0.0 {
+            frame.to_world(DVec3::new(local.x / r, local.y / r, 0.0))
+        } else {
+            frame.tangent
+        };
+        let (normal, face) = compute_face_normal(ray, frame.normal);
+        Some(HitRecord {
+            t,
+            point,
+            normal,
+            object_point: point,
+            object_rotation: DQuat::IDENTITY,
+            uv,
+            dpdu: f64::TAU * frame.to_world(DVec3::new(-local.y, local.x, 0.0)),
+            dpdv: -width * radial,
+            face,
+            color: DVec3::ONE,
+            material: &self.material,
+        })
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<Aabb> {
+        let n = self.normal.normalize();
+        let extent = self.radius * (DVec3::ONE - n * n).max(DVec3::ZERO).powf(0.5);
+        let extent = extent + DVec3::splat(0.0001);
+        Some(Aabb {
+            minimum: self.center - extent,
+            maximum: self.center + extent,
+        })
+    }
+
+    fn sample_direction(&self, origin: DVec3) -> Option<DVec3> {
+        let mut rng = rand::thread_rng();
+        let inner2 = self.inner_radius * self.inner_radius;
+        let r = (inner2 + rng.gen::<f64>() * (self.radius * self.radius - inner2)).sqrt();
+        let phi = f64::TAU * rng.gen::<f64>();
+        let frame = Frame::from_normal(self.normal.normalize());
+        let point = self.center + frame.to_world(DVec3::new(r * phi.cos(), r * phi.sin(), 0.0));
+        Some(point - origin)
+    }
+
+    fn direction_pdf(&self, origin: DVec3, direction: DVec3) -> f64 {
+        area_to_solid_angle_pdf(self, self.area(), origin, direction)
+    }
+}
+
+/// A single triangle, with optional per-vertex normals that are interpolated
+/// for shading.
+#[derive(Debug, Clone)]
+pub struct Triangle<Mat> {
+    pub vertices: [DVec3; 3],
+    pub normals: Option<[DVec3; 3]>,
+    /// Per-vertex tangents in xyz, with the sign of the bitangent
+    /// `w * normal × tangent` in w, as in glTF. When given, they replace the
+    /// directions of `dpdu` and `dpdv` for normal mapping.
+    pub tangents: Option<[DVec4; 3]>,
+    pub uvs: [DVec2; 3],
+    /// Per-vertex colors, interpolated into [`HitRecord::color`].
+    pub colors: Option<[DVec3; 3]>,
+    pub material: Mat,
+}
+
+impl<Mat> Triangle<Mat> {
+    /// A flat shaded triangle with uv coordinates (0, 0), (1, 0) and (0, 1)
+    /// at its vertices.
+    pub fn new(vertices: [DVec3; 3], material: Mat) -> Self {
+        Self {
+            vertices,
+            normals: None,
+            tangents: None,
+            uvs: [DVec2::ZERO, DVec2::X, DVec2::Y],
+            colors: None,
+            material,
+        }
+    }
+}
+
+/// Per-vertex attributes interpolated across a triangle.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct VertexAttributes {
+    pub normals: Option<[DVec3; 3]>,
+    pub tangents: Option<[DVec4; 3]>,
+    pub uvs: [DVec2; 3],
+    pub colors: Option<[DVec3; 3]>,
+}
+
+/// Intersects the triangle with the given vertex attributes, shared by
+/// [`Triangle`] and other triangulated surfaces.
+pub(crate) fn hit_triangle<'a>(
+    vertices: [DVec3; 3],
+    attributes: &VertexAttributes,
+    material: &'a dyn Material,
+    ray: &Ray,
+    t_min: f64,
+    t_max: f64,
+) -> Option<HitRecord<'a>> {
+    // Möller-Trumbore intersection.
+    let [p0, p1, p2] = vertices;
+    let e1 = p1 - p0;
+    let e2 = p2 - p0;
+    let pvec = ray.direction.cross(e2);
+    let det = e1.dot(pvec);
+    if det == 0.0 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    let tvec = ray.origin - p0;
+    let b1 = tvec.dot(pvec) * inv_det;
+    if !(0.0..=1.0).contains(&b1) {
+        return None;
+    }
+    let qvec = tvec.cross(e1);
+    let b2 = ray.direction.dot(qvec) * inv_det;
+    if b2 < 0.0 || b1 + b2 > 1.0 {
+        return None;
+    }
+    let t = e2.dot(qvec) * inv_det;
+    if t < t_min || t > t_max {
+        return None;
+    }
+    let b0 = 1.0 - b1 - b2;
+
+    let geometric_normal = e1.cross(e2).normalize();
+    let outward_normal = match attributes.normals {
+        Some([n0, n1, n2]) => (b0 * n0 + b1 * n1 + b2 * n2)
+            .try_normalize()
+            .unwrap_or(geometric_normal),
+        None => geometric_normal,
+    };
+    let (geometric_normal, face) = compute_face_normal(ray, geometric_normal);
+    let normal = if outward_normal.dot(geometric_normal) < 0.0 {
+        -outward_normal
+    } else {
+        outward_normal
+    };
+
+    let [uv0, uv1, uv2] = attributes.uvs;
+    let uv = b0 * uv0 + b1 * uv1 + b2 * uv2;
+    let duv1 = uv1 - uv0;
+    let duv2 = uv2 - uv0;
+    let uv_det = duv1.x * duv2.y - duv1.y * duv2.x;
+    let (dpdu, dpdv) = if uv_det.abs() > 1.0e-12 {
+        (
+            (duv2.y * e1 - duv1.y * e2) / uv_det,
+            (duv1.x * e2 - duv2.x * e1) / uv_det,
+        )
+    } else {
+        (e1, e2)
+    };
+    let (dpdu, dpdv) = match attributes.tangents {
+        Some([t0, t1, t2]) => {
+            let tangent = b0 * t0 + b1 * t1 + b2 * t2;
+            let n = outward_normal;
+            match (tangent.truncate() - n * n.dot(tangent.truncate())).try_normalize() {
+                Some(t) => (
+                    t * dpdu.length(),
+                    tangent.w.signum() * n.cross(t) * dpdv.length(),
+                ),
+                None => (dpdu, dpdv),
+            }
+        }
+        None => (dpdu, dpdv),
+    };
+
+    let color = match attributes.colors {
+        Some([c0, c1, c2]) => b0 * c0 + b1 * c1 + b2 * c2,
+        None => DVec3::ONE,
+    };
+
+    let point = b0 * p0 + b1 * p1 + b2 * p2;
+    Some(HitRecord {
+        t,
+        point,
+        normal,
+        object_point: point,
+        object_rotation: DQuat::IDENTITY,
+        uv,
+        dpdu,
+        dpdv,
+        face,
+        color,
+        material,
+    })
+}
+
+impl<Mat: Material> Hittable for Triangle<Mat> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        let attributes = VertexAttributes {
+            normals: self.normals,
+            tangents: self.tangents,
+            uvs: self.uvs,
+            colors: self.colors,
+        };
+        hit_triangle(
+            self.vertices,
+            &attributes,
+            &self.material,
+            ray,
+            t_min,
+            t_max,
+        )
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<Aabb> {
+        let [p0, p1, p2] = self.vertices;
+        let epsilon = DVec3::splat(0.0001);
+        Some(Aabb {
+            minimum: p0.min(p1).min(p2) - epsilon,
+            maximum: p0.max(p1).max(p2) + epsilon,
+        })
+    }
+
+    fn sample_direction(&self, origin: DVec3) -> Option<DVec3> {
+        let mut rng = rand::thread_rng();
+        let su = rng.gen::<f64>().sqrt();
+        let b0 = 1.0 - su;
+        let b1 = rng.gen::<f64>() * su;
+        let [p0, p1, p2] = self.vertices;
+        Some(b0 * p0 + b1 * p1 + (1.0 - b0 - b1) * p2 - origin)
+    }
+
+    fn direction_pdf(&self, origin: DVec3, direction: DVec3) -> f64 {
+        let [p0, p1, p2] = self.vertices;
+        let area = 0.5 * (p1 - p0).cross(p2 - p0).length();
+        area_to_solid_angle_pdf(self, area, origin, direction)
+    }
+}
+
 pub struct Translate<T> {
     pub offset: DVec3,
     pub inner: T,
@@ -472,6 +1080,14 @@ impl<T: Hittable> Hittable for Translate<T> {
             .bounding_box(start_time, end_time)
             .map(|bb| bb.offset(self.offset))
     }
+
+    fn sample_direction(&self, origin: DVec3) -> Option<DVec3> {
+        self.inner.sample_direction(origin - self.offset)
+    }
+
+    fn direction_pdf(&self, origin: DVec3, direction: DVec3) -> f64 {
+        self.inner.direction_pdf(origin - self.offset, direction)
+    }
 }
 
 pub struct RotateY<T> {
@@ -508,6 +1124,9 @@ impl<T: Hittable> Hittable for RotateY<T> {
         Some(HitRecord {
             point: self.rotate_out(hit.point),
             normal: self.rotate_out(hit.normal),
+            dpdu: self.rotate_out(hit.dpdu),
+            dpdv: self.rotate_out(hit.dpdv),
+            object_rotation: DQuat::from_rotation_y(self.radians) * hit.object_rotation,
             ..hit
         })
     }
@@ -529,4 +1148,127 @@ impl<T: Hittable> Hittable for RotateY<T> {
 
         Some(Aabb { minimum, maximum })
     }
+
+    fn sample_direction(&self, origin: DVec3) -> Option<DVec3> {
+        let direction = self.inner.sample_direction(self.rotate_in(origin))?;
+        Some(self.rotate_out(direction))
+    }
+
+    fn direction_pdf(&self, origin: DVec3, direction: DVec3) -> f64 {
+        self.inner
+            .direction_pdf(self.rotate_in(origin), self.rotate_in(direction))
+    }
+}
+
+/// Applies an affine transformation, e.g. from the node hierarchy of a
+/// scene file, to `inner`.
+pub struct Transform<T> {
+    inner: T,
+    matrix: DAffine3,
+    inverse: DAffine3,
+    /// Inverse transpose of the linear part, which transforms normals.
+    normal_matrix: DMat3,
+    rotation: DQuat,
+}
+
+impl<T> Transform<T> {
+    pub fn new(matrix: DAffine3, inner: T) -> Self {
+        let inverse = matrix.inverse();
+        let (_, rotation, _) = matrix.to_scale_rotation_translation();
+        Self {
+            inner,
+            matrix,
+            inverse,
+            normal_matrix: inverse.matrix3.transpose(),
+            rotation,
+        }
+    }
+}
+
+impl<T: Hittable> Hittable for Transform<T> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        // Distances along the ray are unchanged, as the direction is
+        // transformed without normalizing it.
+        let local_ray = Ray {
+            origin: self.inverse.transform_point3(ray.origin),
+            direction: self.inverse.transform_vector3(ray.direction),
+            ..*ray
+        };
+        let hit = self.inner.hit(&local_ray, t_min, t_max)?;
+        Some(HitRecord {
+            point: self.matrix.transform_point3(hit.point),
+            normal: (self.normal_matrix * hit.normal).normalize(),
+            dpdu: self.matrix.transform_vector3(hit.dpdu),
+            dpdv: self.matrix.transform_vector3(hit.dpdv),
+            object_rotation: self.rotation * hit.object_rotation,
+            ..hit
+        })
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<Aabb> {
+        let bb = self.inner.bounding_box(start_time, end_time)?;
+
+        let mut minimum = DVec3::splat(f64::INFINITY);
+        let mut maximum = DVec3::splat(-f64::INFINITY);
+        for x in [bb.minimum.x, bb.maximum.x] {
+            for y in [bb.minimum.y, bb.maximum.y] {
+                for z in [bb.minimum.z, bb.maximum.z] {
+                    let point = self.matrix.transform_point3(DVec3::new(x, y, z));
+                    minimum = minimum.min(point);
+                    maximum = maximum.max(point);
+                }
+            }
+        }
+
+        Some(Aabb { minimum, maximum })
+    }
+
+    fn sample_direction(&self, origin: DVec3) -> Option<DVec3> {
+        let direction = self
+            .inner
+            .sample_direction(self.inverse.transform_point3(origin))?;
+        Some(self.matrix.transform_vector3(direction))
+    }
+
+    fn direction_pdf(&self, origin: DVec3, direction: DVec3) -> f64 {
+        let local = self.inverse.transform_vector3(direction);
+        let length = local.length();
+        if length == 0.0 {
+            return 0.0;
+        }
+        // Solid angles are stretched by the linear part of the inverse.
+        let jacobian = self.inverse.matrix3.determinant().abs() / length.powi(3);
+        self.inner
+            .direction_pdf(self.inverse.transform_point3(origin), local / length)
+            * jacobian
+    }
+}
+
+/// Swaps the front and back faces of a hittable, e.g. to choose which side of
+/// a one-sided emitter is lit.
+pub struct FlipFace<T> {
+    pub inner: T,
+}
+
+impl<T: Hittable> Hittable for FlipFace<T> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        let hit = self.inner.hit(ray, t_min, t_max)?;
+        let face = match hit.face {
+            Face::Front => Face::Back,
+            Face::Back => Face::Front,
+        };
+        Some(HitRecord { face, ..hit })
+    }
+
+    fn bounding_box(&self, start_time: f64, end_time: f64) -> Option<Aabb> {
+        self.inner.bounding_box(start_time, end_time)
+    }
+
+    fn sample_direction(&self, origin: DVec3) -> Option<DVec3> {
+        self.inner.sample_direction(origin)
+    }
+
+    fn direction_pdf(&self, origin: DVec3, direction: DVec3) -> f64 {
+        self.inner.direction_pdf(origin, direction)
+    }
 }
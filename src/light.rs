@@ -0,0 +1,281 @@
+use std::f64::consts as f64;
+
+use glam::DVec3;
+use rand::Rng;
+
+use crate::{hittable::Hittable, ray::Ray};
+
+/// A direction towards a light, chosen by [`Light::sample`].
+#[derive(Debug, Clone)]
+pub struct LightSample {
+    /// Normalized direction from the shaded point towards the light.
+    pub direction: DVec3,
+    /// Distance to the light along `direction`; shadow rays are tested up to
+    /// this distance.
+    pub distance: f64,
+    /// Radiance arriving along `direction`. For delta lights, this is the
+    /// irradiance at the shaded point instead.
+    pub radiance: DVec3,
+    /// Solid angle density of sampling `direction`, or `None` for delta
+    /// lights which cannot be hit by scattered rays.
+    pub pdf: Option<f64>,
+}
+
+/// A light source that is sampled explicitly by the integrator, using shadow
+/// rays.
+pub trait Light: Send + Sync {
+    fn sample(&self, point: DVec3) -> Option<LightSample>;
+
+    /// Solid angle density with which [`Light::sample`] would choose
+    /// `direction` from `origin`.
+    fn pdf(&self, origin: DVec3, direction: DVec3) -> f64 {
+        let _ = (origin, direction);
+        0.0
+    }
+
+    /// Radiance seen by a ray that escapes the scene along the normalized
+    /// `direction`.
+    fn emitted(&self, direction: DVec3) -> DVec3 {
+        let _ = direction;
+        DVec3::ZERO
+    }
+
+    /// Solid angle density with which [`Light::sample`] would choose the
+    /// surface point at `distance` along the normalized `direction` from
+    /// `origin`. Only lights backed by scene geometry return nonzero values.
+    fn surface_pdf(&self, origin: DVec3, direction: DVec3, distance: f64) -> f64 {
+        let _ = (origin, direction, distance);
+        0.0
+    }
+}
+
+impl<L: Light + ?Sized> Light for Box<L> {
+    fn sample(&self, point: DVec3) -> Option<LightSample> {
+        L::sample(self, point)
+    }
+
+    fn pdf(&self, origin: DVec3, direction: DVec3) -> f64 {
+        L::pdf(self, origin, direction)
+    }
+
+    fn emitted(&self, direction: DVec3) -> DVec3 {
+        L::emitted(self, direction)
+    }
+
+    fn surface_pdf(&self, origin: DVec3, direction: DVec3, distance: f64) -> f64 {
+        L::surface_pdf(self, origin, direction, distance)
+    }
+}
+
+/// A distant disk-shaped light, such as the sun.
+#[derive(Debug, Clone)]
+pub struct Sun {
+    /// Direction towards the sun.
+    pub direction: DVec3,
+    /// Apparent angular diameter in radians.
+    pub angular_diameter: f64,
+    /// Irradiance on a surface facing the sun.
+    pub irradiance: DVec3,
+}
+
+impl Sun {
+    fn cos_max(&self) -> f64 {
+        (self.angular_diameter / 2.0).cos()
+    }
+
+    fn solid_angle(&self) -> f64 {
+        f64::TAU * (1.0 - self.cos_max())
+    }
+}
+
+impl Light for Sun {
+    fn sample(&self, _point: DVec3) -> Option<LightSample> {
+        let axis = self.direction.normalize();
+        let solid_angle = self.solid_angle();
+        if solid_angle <= 0.0 {
+            return Some(LightSample {
+                direction: axis,
+                distance: f64::INFINITY,
+                radiance: self.irradiance,
+                pdf: None,
+            });
+        }
+
+        let mut rng = rand::thread_rng();
+        let cos_theta = 1.0 - rng.gen::<f64>() * (1.0 - self.cos_max());
+        let sin_theta = (1.0 - cos_theta * cos_theta).sqrt();
+        let phi = f64::TAU * rng.gen::<f64>();
+        let (u, v) = axis.any_orthonormal_pair();
+        let direction = sin_theta * phi.cos() * u + sin_theta * phi.sin() * v + cos_theta * axis;
+
+        Some(LightSample {
+            direction,
+            distance: f64::INFINITY,
+            radiance: self.irradiance / solid_angle,
+            pdf: Some(1.0 / solid_angle),
+        })
+    }
+
+    fn pdf(&self, _origin: DVec3, direction: DVec3) -> f64 {
+        let solid_angle = self.solid_angle();
+        if solid_angle > 0.0 && direction.dot(self.direction.normalize()) >= self.cos_max() {
+            1.0 / solid_angle
+        } else {
+            0.0
+        }
+    }
+
+    fn emitted(&self, direction: DVec3) -> DVec3 {
+        let solid_angle = self.solid_angle();
+        if solid_angle > 0.0 && direction.dot(self.direction.normalize()) >= self.cos_max() {
+            self.irradiance / solid_angle
+        } else {
+            DVec3::ZERO
+        }
+    }
+}
+
+/// An infinitely small light emitting equally in all directions.
+#[derive(Debug, Clone)]
+pub struct Point {
+    pub position: DVec3,
+    /// Radiant intensity, i.e. power per unit solid angle.
+    pub intensity: DVec3,
+}
+
+impl Light for Point {
+    fn sample(&self, point: DVec3) -> Option<LightSample> {
+        let to_light = self.position - point;
+        let distance = to_light.length();
+        if distance == 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            direction: to_light / distance,
+            distance,
+            radiance: self.intensity / (distance * distance),
+            pdf: None,
+        })
+    }
+}
+
+/// A point light restricted to a cone, with a smooth falloff towards its edge.
+#[derive(Debug, Clone)]
+pub struct Spot {
+    pub position: DVec3,
+    /// Direction in which the spot is pointing.
+    pub direction: DVec3,
+    /// Radiant intensity along the axis of the cone.
+    pub intensity: DVec3,
+    /// Angle between the axis and the edge of the cone, in radians.
+    pub cone_angle: f64,
+    /// Angle from the axis at which the intensity starts falling off, in
+    /// radians.
+    pub falloff_start: f64,
+}
+
+impl Spot {
+    fn falloff(&self, cos_theta: f64) -> f64 {
+        let cos_edge = self.cone_angle.cos();
+        let cos_start = self.falloff_start.min(self.cone_angle).cos();
+        if cos_theta >= cos_start {
+            1.0
+        } else if cos_theta <= cos_edge {
+            0.0
+        } else {
+            let x = (cos_theta - cos_edge) / (cos_start - cos_edge);
+            x * x * (3.0 - 2.0 * x)
+        }
+    }
+}
+
+impl Light for Spot {
+    fn sample(&self, point: DVec3) -> Option<LightSample> {
+        let to_light = self.position - point;
+        let distance = to_light.length();
+        if distance == 0.0 {
+            return None;
+        }
+        let direction = to_light / distance;
+        let falloff = self.falloff(-direction.dot(self.direction.normalize()));
+        if falloff == 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            direction,
+            distance,
+            radiance: falloff * self.intensity / (distance * distance),
+            pdf: None,
+        })
+    }
+}
+
+/// A light infinitely far away, arriving from a single direction.
+#[derive(Debug, Clone)]
+pub struct Directional {
+    /// Direction towards the light.
+    pub direction: DVec3,
+    /// Irradiance on a surface facing the light.
+    pub irradiance: DVec3,
+}
+
+impl Light for Directional {
+    fn sample(&self, _point: DVec3) -> Option<LightSample> {
+        Some(LightSample {
+            direction: self.direction.normalize(),
+            distance: f64::INFINITY,
+            radiance: self.irradiance,
+            pdf: None,
+        })
+    }
+}
+
+/// Samples the emission of the material on `shape`, by choosing points on its
+/// surface. The shape should also be added to the world, typically by sharing
+/// it through an `Arc`.
+#[derive(Debug, Clone)]
+pub struct Area<H> {
+    pub shape: H,
+}
+
+impl<H: Hittable> Area<H> {
+    fn ray(origin: DVec3, direction: DVec3) -> Ray {
+        Ray {
+            origin,
+            direction,
+            time: 0.0,
+            width: 0.0,
+            spread: 0.0,
+        }
+    }
+}
+
+impl<H: Hittable> Light for Area<H> {
+    fn sample(&self, point: DVec3) -> Option<LightSample> {
+        let direction = self.shape.sample_direction(point)?.try_normalize()?;
+        let ray = Self::ray(point, direction);
+        let hit = self.shape.hit(&ray, 0.001, f64::INFINITY)?;
+        let radiance = hit.material.emitted(&ray, &hit);
+        let pdf = self.shape.direction_pdf(point, direction);
+        if radiance == DVec3::ZERO || pdf <= 0.0 {
+            return None;
+        }
+        Some(LightSample {
+            direction,
+            // Stop shadow rays just short of the light itself.
+            distance: hit.t - 0.001,
+            radiance,
+            pdf: Some(pdf),
+        })
+    }
+
+    fn surface_pdf(&self, origin: DVec3, direction: DVec3, distance: f64) -> f64 {
+        let ray = Self::ray(origin, direction);
+        match self.shape.hit(&ray, 0.001, f64::INFINITY) {
+            Some(hit) if (hit.t - distance).abs() <= 1.0e-6 * distance.max(1.0) => {
+                self.shape.direction_pdf(origin, direction)
+            }
+            _ => 0.0,
+        }
+    }
+}
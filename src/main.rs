@@ -1,43 +1,73 @@
 pub mod camera;
+pub mod csg;
+pub mod curve;
+pub mod gltf_import;
+pub mod heightfield;
 pub mod hittable;
 pub mod image;
+pub mod light;
 pub mod material;
+pub mod mesh;
+pub mod microfacet;
+pub mod mipmap;
+pub mod particles;
+pub mod pbrt_import;
 pub mod perlin;
+pub mod ply;
+pub mod projection;
+pub mod quadric;
 pub mod ray;
 pub mod scene;
+pub mod sdf;
+pub mod sky;
 pub mod texture;
+pub mod texture_manager;
 
+use anyhow::bail;
 use glam::DVec3;
 use rand::Rng;
 
 use std::{
+    env,
     f64::consts as f64,
     fs::File,
     io::{self, Write},
+    path::PathBuf,
 };
 
 use crate::{
-    hittable::{Hittable, World},
+    hittable::Hittable,
     image::{Image, Pixel},
     ray::Ray,
-    scene::Scene,
+    scene::{RenderSettings, Scene},
 };
 
+/// Renders the `.pbrt` file given as the first argument, or the default
+/// scene without one.
 fn main() -> anyhow::Result<()> {
-    let image_aspect = 1.0;
-    let image_height = 600;
-    let image_width = ((image_height as f64) * image_aspect) as usize;
-    let samples_per_pixel = 400;
-    let max_depth = 50;
+    let (settings, scene) = match env::args_os().nth(1).map(PathBuf::from) {
+        Some(path) if path.extension().is_some_and(|ext| ext == "pbrt") => {
+            let pbrt = pbrt_import::load(&path)?;
+            for warning in &pbrt.warnings {
+                eprintln!("warning: {warning}");
+            }
+            (pbrt.settings, pbrt.scene)
+        }
+        Some(path) => bail!("unsupported scene file {}, expected .pbrt", path.display()),
+        None => {
+            let settings = RenderSettings::default();
+            let scene = scene::cornell_box::build(settings.aspect_ratio());
+            (settings, scene)
+        }
+    };
+    let image_height = settings.height;
+    let image_width = settings.width;
+    let samples_per_pixel = settings.samples_per_pixel;
+    let max_depth = settings.max_depth;
 
     let mut image = Image::new(image_width, image_height, Pixel::BLACK);
 
-    let Scene {
-        world,
-        camera,
-        background,
-    } = scene::cornell_box::build(image_aspect);
-
+    let spread = scene.camera.pixel_spread(image_height);
     let mut rng = rand::thread_rng();
 
     for y in 0..image_height {
@@ -53,8 +83,11 @@ fn main() -> anyhow::Result<()> {
 
                 let u = (x as f64 + du) / (image_width as f64);
                 let v = (up_y as f64 + dv) / (image_height as f64);
-                let ray = camera.get_ray(u, v);
-                sum += ray_color(&ray, background, &world, max_depth);
+                let ray = Ray {
+                    spread,
+                    ..scene.camera.get_ray(u, v)
+                };
+                sum += ray_color(&ray, &scene, max_depth);
             }
             *image.pixel_mut(x, y) = (sum / (samples_per_pixel as f64)).powf(0.5).into();
         }
@@ -65,25 +98,102 @@ fn main() -> anyhow::Result<()> {
     Ok(())
 }
 
-fn ray_color(ray: &Ray, background: DVec3, world: &World, depth: u32) -> DVec3 {
+/// Multiple importance sampling weight for a sample drawn with density `pdf`,
+/// where `other_pdf` is the density of the competing strategy.
+fn power_heuristic(pdf: f64, other_pdf: f64) -> f64 {
+    let sum = pdf * pdf + other_pdf * other_pdf;
+    if sum > 0.0 {
+        pdf * pdf / sum
+    } else {
+        0.0
+    }
+}
+
+fn ray_color(ray: &Ray, scene: &Scene, depth: u32) -> DVec3 {
     let mut ray = ray.clone();
     let mut total = DVec3::ZERO;
     let mut atten = DVec3::ONE;
+    // Density of the scattering event that produced `ray`, or `None` for
+    // camera rays and discrete scattering, which light sampling can't reach.
+    let mut scatter_pdf: Option<f64> = None;
     for _ in 0..depth {
-        let hit = match world.hit(&ray, 0.001, f64::INFINITY) {
+        let hit = match scene.world.hit(&ray, 0.001, f64::INFINITY) {
             Some(x) => x,
             None => {
-                total += atten * background;
+                let direction = ray.direction.normalize();
+                total += atten * scene.background.value(direction);
+                for light in &scene.lights {
+                    let emitted = light.emitted(direction);
+                    let weight = match scatter_pdf {
+                        Some(pdf) => power_heuristic(pdf, light.pdf(ray.origin, direction)),
+                        None => 1.0,
+                    };
+                    total += atten * weight * emitted;
+                }
                 break;
             }
         };
 
-        total += atten * hit.material.emitted(hit.uv, hit.point);
+        let emitted = hit.material.emitted(&ray, &hit);
+        if emitted != DVec3::ZERO {
+            // Area lights may have found this emission with light sampling
+            // already.
+            let weight = match scatter_pdf {
+                Some(pdf) => {
+                    let direction = ray.direction.normalize();
+                    let distance = hit.t * ray.direction.length();
+                    let light_pdf: f64 = scene
+                        .lights
+                        .iter()
+                        .map(|light| light.surface_pdf(ray.origin, direction, distance))
+                        .sum();
+                    power_heuristic(pdf, light_pdf)
+                }
+                None => 1.0,
+            };
+            total += atten * weight * emitted;
+        }
+
+        for light in &scene.lights {
+            let sample = match light.sample(hit.point) {
+                Some(x) => x,
+                None => continue,
+            };
+            let f = hit.material.eval(&ray, &hit, sample.direction);
+            if f == DVec3::ZERO {
+                continue;
+            }
+            let shadow_ray = Ray {
+                origin: hit.point,
+                direction: sample.direction,
+                ..ray
+            };
+            if scene
+                .world
+                .hit(&shadow_ray, 0.001, sample.distance)
+                .is_some()
+            {
+                continue;
+            }
+            let contribution = match sample.pdf {
+                Some(pdf) => {
+                    let weight =
+                        power_heuristic(pdf, hit.material.pdf(&ray, &hit, sample.direction));
+                    weight * sample.radiance / pdf
+                }
+                None => sample.radiance,
+            };
+            total += atten * f * contribution;
+        }
 
         match hit.material.scatter(&ray, &hit) {
             Some(scatter) => {
                 atten *= scatter.attenuation;
-                ray = scatter.ray;
+                scatter_pdf = scatter.pdf;
+                ray = Ray {
+                    width: ray.width_at(hit.t),
+                    ..scatter.ray
+                };
             }
             None => {
                 break;
@@ -4,12 +4,13 @@ use rand::{
     Rng,
 };
 
-use std::{fmt::Debug, sync::Arc};
+use std::{f64::consts as f64, fmt::Debug, sync::Arc};
 
 use crate::{
     hittable::{Face, HitRecord},
+    microfacet::{self, Frame, Ggx},
     ray::Ray,
-    texture::Texture,
+    texture::{Solid, Texture, TextureContext},
 };
 
 #[allow(dead_code)]
@@ -52,29 +53,46 @@ fn reflect(incident: DVec3, normal: DVec3) -> DVec3 {
     incident - 2.0 * incident.dot(normal) * normal
 }
 
-fn refract(incident: DVec3, normal: DVec3, ir_ratio: f64) -> DVec3 {
-    let cos = normal.dot(-incident).min(1.0);
-    let r_perp = ir_ratio * (incident + cos * normal);
-    let r_par = -(1.0 - r_perp.length_squared()).abs().sqrt() * normal;
-    r_perp + r_par
+pub fn luminance(color: DVec3) -> f64 {
+    color.dot(DVec3::new(0.2126, 0.7152, 0.0722))
 }
 
-fn reflectance(cos: f64, ir_ratio: f64) -> f64 {
-    let r0 = (1.0 - ir_ratio) / (1.0 + ir_ratio);
-    let r0 = r0 * r0;
-    r0 + (1.0 - r0) * (1.0 - cos).powi(5)
-}
+/// Representative wavelengths (in micrometers) of the red, green and blue
+/// channels, used for dispersion.
+const RGB_WAVELENGTHS: [f64; 3] = [0.650, 0.550, 0.450];
 
 pub struct Scatter {
     pub ray: Ray,
     pub attenuation: DVec3,
+    /// Solid angle density with which the scattered direction was chosen, or
+    /// `None` if the material scatters in a discrete direction.
+    pub pdf: Option<f64>,
 }
 
-pub trait Material {
+pub trait Material: Send + Sync {
     fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<Scatter>;
 
-    fn emitted(&self, uv: DVec2, point: DVec3) -> DVec3 {
-        let _ = (uv, point);
+    /// Evaluates the BSDF times the cosine of the angle to the normal, for
+    /// light arriving from the normalized `direction`.
+    ///
+    /// Materials that only scatter in discrete directions return zero and
+    /// are not sampled explicitly.
+    fn eval(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> DVec3 {
+        let _ = (ray, hit, direction);
+        DVec3::ZERO
+    }
+
+    /// Solid angle density with which [`Material::scatter`] would choose the
+    /// normalized `direction`.
+    fn pdf(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> f64 {
+        let _ = (ray, hit, direction);
+        0.0
+    }
+
+    /// Radiance emitted from the surface at `hit`, towards the origin of
+    /// `ray`.
+    fn emitted(&self, ray: &Ray, hit: &HitRecord) -> DVec3 {
+        let _ = (ray, hit);
         DVec3::ZERO
     }
 }
@@ -84,18 +102,34 @@ impl<M: Material> Material for &M {
         M::scatter(*self, ray, hit)
     }
 
-    fn emitted(&self, uv: DVec2, point: DVec3) -> DVec3 {
-        M::emitted(*self, uv, point)
+    fn eval(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> DVec3 {
+        M::eval(*self, ray, hit, direction)
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> f64 {
+        M::pdf(*self, ray, hit, direction)
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &HitRecord) -> DVec3 {
+        M::emitted(*self, ray, hit)
     }
 }
 
-impl<M: Material> Material for Arc<M> {
+impl<M: Material + ?Sized> Material for Arc<M> {
     fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<Scatter> {
-        M::scatter(&*self, ray, hit)
+        M::scatter(self, ray, hit)
     }
 
-    fn emitted(&self, uv: DVec2, point: DVec3) -> DVec3 {
-        M::emitted(&*self, uv, point)
+    fn eval(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> DVec3 {
+        M::eval(self, ray, hit, direction)
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> f64 {
+        M::pdf(self, ray, hit, direction)
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &HitRecord) -> DVec3 {
+        M::emitted(self, ray, hit)
     }
 }
 
@@ -117,9 +151,19 @@ impl<Albedo: Texture> Material for Lambertian<Albedo> {
                 direction,
                 ..*ray
             },
-            attenuation: self.albedo.value(hit.uv, hit.point),
+            attenuation: self.albedo.value(&TextureContext::new(ray, hit)),
+            pdf: Some(self.pdf(ray, hit, direction.normalize())),
         })
     }
+
+    fn eval(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> DVec3 {
+        let albedo = self.albedo.value(&TextureContext::new(ray, hit));
+        albedo * hit.normal.dot(direction).max(0.0) / f64::PI
+    }
+
+    fn pdf(&self, _ray: &Ray, hit: &HitRecord, direction: DVec3) -> f64 {
+        hit.normal.dot(direction).max(0.0) / f64::PI
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -140,6 +184,7 @@ impl Material for Metal {
                     ..*ray
                 },
                 attenuation: self.albedo,
+                pdf: None,
             })
         } else {
             None
@@ -147,42 +192,856 @@ impl Material for Metal {
     }
 }
 
+/// A metal with a GGX microfacet surface and measured complex index of
+/// refraction.
+#[derive(Debug, Clone)]
+pub struct Conductor {
+    /// Real part of the index of refraction, per RGB channel.
+    pub eta: DVec3,
+    /// Extinction coefficient, per RGB channel.
+    pub k: DVec3,
+    /// Perceptual roughness in `0..=1`.
+    pub roughness: f64,
+}
+
+impl Conductor {
+    pub fn gold(roughness: f64) -> Self {
+        Self {
+            eta: DVec3::new(0.143, 0.374, 1.442),
+            k: DVec3::new(3.983, 2.385, 1.603),
+            roughness,
+        }
+    }
+
+    pub fn copper(roughness: f64) -> Self {
+        Self {
+            eta: DVec3::new(0.200, 0.924, 1.102),
+            k: DVec3::new(3.912, 2.452, 2.142),
+            roughness,
+        }
+    }
+
+    pub fn aluminium(roughness: f64) -> Self {
+        Self {
+            eta: DVec3::new(1.657, 0.880, 0.521),
+            k: DVec3::new(9.224, 6.270, 4.837),
+            roughness,
+        }
+    }
+
+    pub fn silver(roughness: f64) -> Self {
+        Self {
+            eta: DVec3::new(0.155, 0.117, 0.138),
+            k: DVec3::new(4.828, 3.122, 2.147),
+            roughness,
+        }
+    }
+
+    fn distribution(&self) -> Ggx {
+        Ggx {
+            alpha: microfacet::roughness_to_alpha(self.roughness),
+        }
+    }
+}
+
+impl Material for Conductor {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<Scatter> {
+        let frame = Frame::from_normal(hit.normal);
+        let wo = frame.to_local(-ray.direction.normalize());
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let ggx = self.distribution();
+
+        if ggx.is_smooth() {
+            return Some(Scatter {
+                ray: Ray {
+                    origin: hit.point,
+                    direction: frame.to_world(microfacet::reflect(wo, DVec3::Z)),
+                    ..*ray
+                },
+                attenuation: microfacet::fresnel_conductor(wo.z, self.eta, self.k),
+                pdf: None,
+            });
+        }
+
+        let mut rng = rand::thread_rng();
+        let m = ggx.sample_visible_normal(wo, rng.gen(), rng.gen());
+        let wi = microfacet::reflect(wo, m);
+        if wi.z <= 0.0 {
+            return None;
+        }
+        let fresnel = microfacet::fresnel_conductor(wo.dot(m), self.eta, self.k);
+        let direction = frame.to_world(wi);
+        Some(Scatter {
+            ray: Ray {
+                origin: hit.point,
+                direction,
+                ..*ray
+            },
+            attenuation: fresnel * ggx.g2(wo, wi) / ggx.g1(wo),
+            pdf: Some(self.pdf(ray, hit, direction)),
+        })
+    }
+
+    fn eval(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> DVec3 {
+        let ggx = self.distribution();
+        let frame = Frame::from_normal(hit.normal);
+        let wo = frame.to_local(-ray.direction.normalize());
+        let wi = frame.to_local(direction);
+        if ggx.is_smooth() || wo.z <= 0.0 || wi.z <= 0.0 {
+            return DVec3::ZERO;
+        }
+        let m = (wo + wi).normalize();
+        let fresnel = microfacet::fresnel_conductor(wo.dot(m), self.eta, self.k);
+        fresnel * ggx.d(m) * ggx.g2(wo, wi) / (4.0 * wo.z)
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> f64 {
+        let ggx = self.distribution();
+        let frame = Frame::from_normal(hit.normal);
+        let wo = frame.to_local(-ray.direction.normalize());
+        let wi = frame.to_local(direction);
+        if ggx.is_smooth() || wo.z <= 0.0 || wi.z <= 0.0 {
+            return 0.0;
+        }
+        let m = (wo + wi).normalize();
+        ggx.visible_normal_pdf(wo, m) / (4.0 * wo.dot(m))
+    }
+}
+
+/// Glass-like material with a smooth or rough interface.
 #[derive(Debug, Clone)]
 pub struct Dielectric {
+    /// Index of refraction, at the sodium d-line for dispersive materials.
     pub ir: f64,
+    /// Perceptual roughness of the interface in `0..=1`.
+    pub roughness: f64,
+    /// Fraction of light transmitted after travelling `tint_distance` inside
+    /// the medium, per RGB channel.
+    pub tint: DVec3,
+    pub tint_distance: f64,
+    /// Models a thin sheet, like a window pane, instead of a solid volume.
+    /// Light passes through without changing direction, and `tint` is applied
+    /// once per sheet. Roughness is ignored.
+    pub thin: bool,
+    /// Abbe number describing the dispersion of the material, or `None` if
+    /// the index of refraction is the same for all wavelengths.
+    pub abbe_number: Option<f64>,
+}
+
+impl Default for Dielectric {
+    fn default() -> Self {
+        Self {
+            ir: 1.5,
+            roughness: 0.0,
+            tint: DVec3::ONE,
+            tint_distance: 1.0,
+            thin: false,
+            abbe_number: None,
+        }
+    }
+}
+
+impl Dielectric {
+    fn distribution(&self) -> Ggx {
+        Ggx {
+            alpha: microfacet::roughness_to_alpha(self.roughness),
+        }
+    }
+
+    /// Whether the BSDF can be evaluated for arbitrary directions.
+    fn is_glossy(&self) -> bool {
+        !self.thin && self.abbe_number.is_none() && !self.distribution().is_smooth()
+    }
+
+    /// Picks the index of refraction for this scattering event. With
+    /// dispersion, a single RGB channel is traced and the returned weight
+    /// masks out the others.
+    fn sample_ir<R: Rng>(&self, rng: &mut R) -> (f64, DVec3) {
+        let abbe_number = match self.abbe_number {
+            Some(x) => x,
+            None => return (self.ir, DVec3::ONE),
+        };
+        // Cauchy's equation n = a + b / λ², fitted through the d-line index
+        // and the Abbe number, which is defined with the F and C lines.
+        let (line_d, line_f, line_c) = (0.5876_f64, 0.4861_f64, 0.6563_f64);
+        let b = (self.ir - 1.0) / (abbe_number * (line_f.powi(-2) - line_c.powi(-2)));
+        let a = self.ir - b / (line_d * line_d);
+
+        let channel = rng.gen_range(0..3);
+        let lambda = RGB_WAVELENGTHS[channel];
+        let mut weight = DVec3::ZERO;
+        weight[channel] = 3.0;
+        (a + b / (lambda * lambda), weight)
+    }
+
+    /// Beer-Lambert attenuation along `ray` up to `hit`, if the ray travelled
+    /// inside the medium.
+    fn absorption(&self, ray: &Ray, hit: &HitRecord) -> DVec3 {
+        if self.thin || hit.face == Face::Front || self.tint == DVec3::ONE {
+            return DVec3::ONE;
+        }
+        let distance = hit.t * ray.direction.length();
+        // Equivalent to exp(-sigma * distance), with sigma = -ln(tint) / tint_distance.
+        self.tint.powf(distance / self.tint_distance)
+    }
+
+    /// Ratio of the index of refraction on the far side of the interface to
+    /// the one on the side of the incoming ray.
+    fn eta(ir: f64, hit: &HitRecord) -> f64 {
+        match hit.face {
+            Face::Front => ir,
+            Face::Back => 1.0 / ir,
+        }
+    }
+
+    /// Evaluates the rough BSDF times cosine and its sampling density, in the
+    /// local shading frame.
+    fn eval_local(&self, wo: DVec3, wi: DVec3, eta: f64) -> (f64, f64) {
+        let ggx = self.distribution();
+        if wo.z <= 0.0 || wi.z == 0.0 {
+            return (0.0, 0.0);
+        }
+        if wi.z > 0.0 {
+            let m = (wo + wi).normalize();
+            let fresnel = microfacet::fresnel_dielectric(wo.dot(m), eta);
+            let value = fresnel * ggx.d(m) * ggx.g2(wo, wi) / (4.0 * wo.z);
+            let pdf = fresnel * ggx.visible_normal_pdf(wo, m) / (4.0 * wo.dot(m));
+            (value, pdf)
+        } else {
+            let mut m = (wo + eta * wi).normalize();
+            if m.z < 0.0 {
+                m = -m;
+            }
+            let (cos_o, cos_i) = (wo.dot(m), wi.dot(m));
+            if cos_o <= 0.0 || cos_i >= 0.0 {
+                return (0.0, 0.0);
+            }
+            let fresnel = microfacet::fresnel_dielectric(cos_o, eta);
+            let denom = (cos_i + cos_o / eta).powi(2);
+            let value = (1.0 - fresnel) * ggx.d(m) * ggx.g2(wo, wi) * (cos_i * cos_o).abs()
+                / (wo.z * denom);
+            let pdf = (1.0 - fresnel) * ggx.visible_normal_pdf(wo, m) * cos_i.abs() / denom;
+            (value, pdf)
+        }
+    }
 }
 
 impl Material for Dielectric {
     fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<Scatter> {
-        let ir_ratio = match hit.face {
-            Face::Front => 1.0 / self.ir,
-            Face::Back => self.ir,
+        let mut rng = rand::thread_rng();
+        let (ir, mut attenuation) = self.sample_ir(&mut rng);
+        attenuation *= self.absorption(ray, hit);
+
+        let eta = Self::eta(ir, hit);
+        let frame = Frame::from_normal(hit.normal);
+        let wo = frame.to_local(-ray.direction.normalize());
+        let ggx = self.distribution();
+
+        let (wi, pdf) = if self.thin {
+            // Sum of all internal reflections between the two faces.
+            let r = microfacet::fresnel_dielectric(wo.z, ir);
+            let r = 2.0 * r / (1.0 + r);
+            if rng.gen_bool(r) {
+                (microfacet::reflect(wo, DVec3::Z), None)
+            } else {
+                attenuation *= self.tint;
+                (-wo, None)
+            }
+        } else if ggx.is_smooth() {
+            let fresnel = microfacet::fresnel_dielectric(wo.z, eta);
+            match microfacet::refract(wo, DVec3::Z, eta) {
+                Some(wt) if !rng.gen_bool(fresnel) => (wt, None),
+                _ => (microfacet::reflect(wo, DVec3::Z), None),
+            }
+        } else {
+            let m = ggx.sample_visible_normal(wo, rng.gen(), rng.gen());
+            let fresnel = microfacet::fresnel_dielectric(wo.dot(m), eta);
+            let wi = match microfacet::refract(wo, m, eta) {
+                Some(wt) if !rng.gen_bool(fresnel) => {
+                    if wt.z >= 0.0 {
+                        return None;
+                    }
+                    wt
+                }
+                _ => {
+                    let wr = microfacet::reflect(wo, m);
+                    if wr.z <= 0.0 {
+                        return None;
+                    }
+                    wr
+                }
+            };
+            attenuation *= ggx.g2(wo, wi) / ggx.g1(wo);
+            let pdf = if self.is_glossy() {
+                Some(self.eval_local(wo, wi, eta).1)
+            } else {
+                None
+            };
+            (wi, pdf)
         };
 
-        let unit_direction = ray.direction.normalize();
-        let cos = hit.normal.dot(-unit_direction).min(1.0);
-        let sin = (1.0 - cos * cos).sqrt();
+        Some(Scatter {
+            ray: Ray {
+                origin: hit.point,
+                direction: frame.to_world(wi),
+                ..*ray
+            },
+            attenuation,
+            pdf,
+        })
+    }
+
+    fn eval(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> DVec3 {
+        if !self.is_glossy() {
+            return DVec3::ZERO;
+        }
+        let frame = Frame::from_normal(hit.normal);
+        let wo = frame.to_local(-ray.direction.normalize());
+        let wi = frame.to_local(direction);
+        let (value, _) = self.eval_local(wo, wi, Self::eta(self.ir, hit));
+        value * self.absorption(ray, hit)
+    }
 
-        let mut rng = rand::thread_rng();
+    fn pdf(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> f64 {
+        if !self.is_glossy() {
+            return 0.0;
+        }
+        let frame = Frame::from_normal(hit.normal);
+        let wo = frame.to_local(-ray.direction.normalize());
+        let wi = frame.to_local(direction);
+        self.eval_local(wo, wi, Self::eta(self.ir, hit)).1
+    }
+}
+
+/// A material combining the layers of the Disney and glTF metallic-roughness
+/// models. Scalar parameters are read from the red channel of their textures.
+#[derive(Clone)]
+pub struct Principled {
+    pub base_color: Arc<dyn Texture>,
+    /// Blends between a dielectric (0) and a metal (1) using `base_color` as
+    /// the reflectance.
+    pub metallic: Arc<dyn Texture>,
+    /// Perceptual roughness of the specular and transmission layers.
+    pub roughness: Arc<dyn Texture>,
+    /// Specular reflectance of the dielectric part; 0.5 corresponds to a
+    /// reflectance of 4% at normal incidence.
+    pub specular: Arc<dyn Texture>,
+    /// Strength of an additional colorless glossy layer, like varnish.
+    pub clearcoat: Arc<dyn Texture>,
+    pub clearcoat_roughness: Arc<dyn Texture>,
+    /// Color of the retro-reflective layer seen on cloth at grazing angles.
+    pub sheen: Arc<dyn Texture>,
+    /// Fraction of the dielectric part that refracts instead of scattering
+    /// diffusely.
+    pub transmission: Arc<dyn Texture>,
+    /// Radiance emitted from the front of the surface.
+    pub emission: Arc<dyn Texture>,
+    /// Index of refraction of the transmissive part.
+    pub ior: f64,
+}
 
-        let direction = if sin * ir_ratio > 1.0 || rng.gen_bool(reflectance(cos, ir_ratio)) {
-            reflect(ray.direction, hit.normal)
+impl Default for Principled {
+    fn default() -> Self {
+        let solid = |value: f64| -> Arc<dyn Texture> {
+            Arc::new(Solid {
+                color: DVec3::splat(value),
+            })
+        };
+        Self {
+            base_color: solid(0.8),
+            metallic: solid(0.0),
+            roughness: solid(0.5),
+            specular: solid(0.5),
+            clearcoat: solid(0.0),
+            clearcoat_roughness: solid(0.03),
+            sheen: solid(0.0),
+            transmission: solid(0.0),
+            emission: solid(0.0),
+            ior: 1.5,
+        }
+    }
+}
+
+/// Texture values of a [`Principled`] material at a hit.
+struct PrincipledParams {
+    base_color: DVec3,
+    metallic: f64,
+    roughness: f64,
+    specular: f64,
+    clearcoat: f64,
+    clearcoat_roughness: f64,
+    sheen: DVec3,
+    transmission: f64,
+}
+
+impl PrincipledParams {
+    fn diffuse_weight(&self) -> f64 {
+        (1.0 - self.metallic) * (1.0 - self.transmission)
+    }
+
+    fn transmission_weight(&self) -> f64 {
+        (1.0 - self.metallic) * self.transmission
+    }
+
+    /// The transmission layer reflects by itself, so the specular layer
+    /// covers the remainder only.
+    fn specular_weight(&self) -> f64 {
+        1.0 - self.transmission_weight()
+    }
+
+    fn specular_f0(&self) -> DVec3 {
+        DVec3::splat(0.08 * self.specular).lerp(self.base_color, self.metallic)
+    }
+
+    fn specular_distribution(&self) -> Ggx {
+        Ggx {
+            alpha: microfacet::roughness_to_alpha(self.roughness).max(microfacet::MIN_ALPHA),
+        }
+    }
+
+    fn clearcoat_distribution(&self) -> Ggx {
+        Ggx {
+            alpha: microfacet::roughness_to_alpha(self.clearcoat_roughness)
+                .max(microfacet::MIN_ALPHA),
+        }
+    }
+
+    /// Probabilities of sampling the diffuse, specular, clearcoat and
+    /// transmission layers, respectively.
+    fn lobe_probabilities(&self, cos_o: f64) -> [f64; 4] {
+        let weights = [
+            self.diffuse_weight() * (luminance(self.base_color) + luminance(self.sheen)),
+            self.specular_weight()
+                * luminance(microfacet::fresnel_schlick(cos_o, self.specular_f0())),
+            0.25 * self.clearcoat * microfacet::fresnel_schlick(cos_o, DVec3::splat(0.04)).x,
+            self.transmission_weight(),
+        ];
+        let sum: f64 = weights.iter().sum();
+        if sum > 0.0 {
+            weights.map(|w| w / sum)
         } else {
-            refract(unit_direction, hit.normal, ir_ratio)
+            [1.0, 0.0, 0.0, 0.0]
+        }
+    }
+}
+
+impl Principled {
+    fn params(&self, ray: &Ray, hit: &HitRecord) -> PrincipledParams {
+        let ctx = TextureContext::new(ray, hit);
+        let value = |texture: &Arc<dyn Texture>| texture.value(&ctx);
+        let scalar = |texture: &Arc<dyn Texture>| value(texture).x;
+        PrincipledParams {
+            base_color: value(&self.base_color),
+            metallic: scalar(&self.metallic).clamp(0.0, 1.0),
+            roughness: scalar(&self.roughness),
+            specular: scalar(&self.specular).max(0.0),
+            clearcoat: scalar(&self.clearcoat).max(0.0),
+            clearcoat_roughness: scalar(&self.clearcoat_roughness),
+            sheen: value(&self.sheen),
+            transmission: scalar(&self.transmission).clamp(0.0, 1.0),
+        }
+    }
+
+    fn glass(&self, params: &PrincipledParams) -> Dielectric {
+        Dielectric {
+            ir: self.ior,
+            roughness: params.roughness,
+            ..Default::default()
+        }
+    }
+
+    /// Tints light refracted into the surface with the base color.
+    fn transmission_tint(params: &PrincipledParams, hit: &HitRecord, direction: DVec3) -> DVec3 {
+        if hit.face == Face::Front && direction.dot(hit.normal) < 0.0 {
+            params.base_color
+        } else {
+            DVec3::ONE
+        }
+    }
+
+    /// Evaluates the BSDF times cosine of all non-discrete layers, and the
+    /// combined density of sampling them.
+    fn eval_and_pdf(
+        &self,
+        params: &PrincipledParams,
+        ray: &Ray,
+        hit: &HitRecord,
+        direction: DVec3,
+    ) -> (DVec3, f64) {
+        let frame = Frame::from_normal(hit.normal);
+        let wo = frame.to_local(-ray.direction.normalize());
+        let wi = frame.to_local(direction);
+        if wo.z <= 0.0 {
+            return (DVec3::ZERO, 0.0);
+        }
+        let [p_diffuse, p_specular, p_clearcoat, p_transmission] = params.lobe_probabilities(wo.z);
+
+        let mut value = DVec3::ZERO;
+        let mut pdf = 0.0;
+
+        if wi.z > 0.0 {
+            let m = (wo + wi).normalize();
+            let cos_d = wi.dot(m);
+
+            let sheen = params.sheen * (1.0 - cos_d).powi(5);
+            value += params.diffuse_weight() * (params.base_color / f64::PI + sheen) * wi.z;
+            pdf += p_diffuse * wi.z / f64::PI;
+
+            let ggx = params.specular_distribution();
+            let fresnel = microfacet::fresnel_schlick(cos_d, params.specular_f0());
+            value += params.specular_weight() * fresnel * ggx.d(m) * ggx.g2(wo, wi) / (4.0 * wo.z);
+            pdf += p_specular * ggx.visible_normal_pdf(wo, m) / (4.0 * wo.dot(m));
+
+            let ggx = params.clearcoat_distribution();
+            let fresnel = microfacet::fresnel_schlick(cos_d, DVec3::splat(0.04));
+            value += 0.25 * params.clearcoat * fresnel * ggx.d(m) * ggx.g2(wo, wi) / (4.0 * wo.z);
+            pdf += p_clearcoat * ggx.visible_normal_pdf(wo, m) / (4.0 * wo.dot(m));
+        }
+
+        let glass = self.glass(params);
+        if p_transmission > 0.0 && glass.is_glossy() {
+            let tint = Self::transmission_tint(params, hit, direction);
+            value += params.transmission_weight() * tint * glass.eval(ray, hit, direction);
+            pdf += p_transmission * glass.pdf(ray, hit, direction);
+        }
+
+        (value, pdf)
+    }
+}
+
+impl Material for Principled {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<Scatter> {
+        let params = self.params(ray, hit);
+        let glass = self.glass(&params);
+        if hit.face == Face::Back && params.transmission > 0.0 {
+            // Inside the medium, only the transmission layer applies.
+            return glass.scatter(ray, hit);
+        }
+
+        let frame = Frame::from_normal(hit.normal);
+        let wo = frame.to_local(-ray.direction.normalize());
+        if wo.z <= 0.0 {
+            return None;
+        }
+        let probabilities = params.lobe_probabilities(wo.z);
+
+        let mut rng = rand::thread_rng();
+        let u: f64 = rng.gen();
+        let direction = if u < probabilities[0] {
+            let mut direction = hit.normal + random_on_unit_sphere(&mut rng);
+            if is_near_zero(direction) {
+                direction = hit.normal;
+            }
+            direction.normalize()
+        } else if u < probabilities[0] + probabilities[1] + probabilities[2] {
+            let ggx = if u < probabilities[0] + probabilities[1] {
+                params.specular_distribution()
+            } else {
+                params.clearcoat_distribution()
+            };
+            let m = ggx.sample_visible_normal(wo, rng.gen(), rng.gen());
+            let wi = microfacet::reflect(wo, m);
+            if wi.z <= 0.0 {
+                return None;
+            }
+            frame.to_world(wi)
+        } else {
+            let scatter = glass.scatter(ray, hit)?;
+            if scatter.pdf.is_none() {
+                let tint = Self::transmission_tint(&params, hit, scatter.ray.direction);
+                return Some(Scatter {
+                    attenuation: scatter.attenuation * tint * params.transmission_weight()
+                        / probabilities[3],
+                    ..scatter
+                });
+            }
+            scatter.ray.direction.normalize()
         };
+
+        let (value, pdf) = self.eval_and_pdf(&params, ray, hit, direction);
+        if pdf <= 0.0 {
+            return None;
+        }
         Some(Scatter {
             ray: Ray {
                 origin: hit.point,
                 direction,
                 ..*ray
             },
-            attenuation: DVec3::new(1.0, 1.0, 1.0),
+            attenuation: value / pdf,
+            pdf: Some(pdf),
         })
     }
+
+    fn eval(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> DVec3 {
+        let params = self.params(ray, hit);
+        if hit.face == Face::Back && params.transmission > 0.0 {
+            return self.glass(&params).eval(ray, hit, direction);
+        }
+        self.eval_and_pdf(&params, ray, hit, direction).0
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> f64 {
+        let params = self.params(ray, hit);
+        if hit.face == Face::Back && params.transmission > 0.0 {
+            return self.glass(&params).pdf(ray, hit, direction);
+        }
+        self.eval_and_pdf(&params, ray, hit, direction).1
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &HitRecord) -> DVec3 {
+        if hit.face == Face::Front {
+            self.emission.value(&TextureContext::new(ray, hit))
+        } else {
+            DVec3::ZERO
+        }
+    }
 }
 
+/// Hair and fur fibers, with the reflection (R), transmission (TT) and
+/// internal reflection (TRT) lobes of Marschner et al., "Light Scattering
+/// from Human Hair Fibers" (2003), simplified to Gaussian longitudinal and
+/// uniform or logistic azimuthal distributions.
+///
+/// Expects the fiber direction in `dpdu` and the offset across the fiber in
+/// `uv.y`, as given by a [`Curve`](crate::curve::Curve).
+#[derive(Debug, Clone)]
+pub struct Hair<Color> {
+    /// Fraction of light transmitted on one pass through the fiber.
+    pub color: Color,
+    pub ir: f64,
+    /// Standard deviation of the longitudinal lobes, in radians.
+    pub longitudinal_width: f64,
+    /// Scale of the azimuthal distribution of light transmitted through the
+    /// fiber.
+    pub azimuthal_width: f64,
+    /// Angle by which the cuticle scales tilt the highlights, in radians.
+    pub tilt: f64,
+}
+
+impl<Color> Hair<Color> {
+    pub fn new(color: Color) -> Self {
+        Self {
+            color,
+            ir: 1.55,
+            longitudinal_width: 0.12,
+            azimuthal_width: 0.3,
+            tilt: 0.05,
+        }
+    }
+}
+
+/// One of the scattering lobes of [`Hair`].
+struct HairLobe {
+    weight: DVec3,
+    /// Mean and standard deviation of the longitudinal angle.
+    mean: f64,
+    deviation: f64,
+    /// Whether light continues through the fiber, centering the azimuth on
+    /// the opposite side.
+    transmitted: bool,
+}
+
+fn gaussian(x: f64, mean: f64, deviation: f64) -> f64 {
+    let z = (x - mean) / deviation;
+    (-0.5 * z * z).exp() / (deviation * f64::TAU.sqrt())
+}
+
+fn logistic(x: f64, s: f64) -> f64 {
+    let e = (-x.abs() / s).exp();
+    e / (s * (1.0 + e) * (1.0 + e))
+}
+
+fn logistic_cdf(x: f64, s: f64) -> f64 {
+    1.0 / (1.0 + (-x / s).exp())
+}
+
+/// Logistic distribution restricted to `-π..=π`.
+fn trimmed_logistic(x: f64, s: f64) -> f64 {
+    logistic(x, s) / (logistic_cdf(f64::PI, s) - logistic_cdf(-f64::PI, s))
+}
+
+fn sample_trimmed_logistic(u: f64, s: f64) -> f64 {
+    let (low, high) = (logistic_cdf(-f64::PI, s), logistic_cdf(f64::PI, s));
+    let x = -s * (1.0 / (u * (high - low) + low) - 1.0).ln();
+    x.clamp(-f64::PI, f64::PI)
+}
+
+impl<Color: Texture> Hair<Color> {
+    /// Frame with the fiber along +Z and the normal along +X.
+    fn frame(hit: &HitRecord) -> Frame {
+        let fallback = Frame::from_normal(hit.normal);
+        let tangent = hit.dpdu.try_normalize().unwrap_or(fallback.tangent);
+        let normal = (hit.normal - tangent * hit.normal.dot(tangent))
+            .try_normalize()
+            .unwrap_or(fallback.bitangent);
+        Frame {
+            tangent: normal,
+            bitangent: tangent.cross(normal),
+            normal: tangent,
+        }
+    }
+
+    fn lobes(&self, ray: &Ray, hit: &HitRecord, wo: DVec3) -> [HairLobe; 3] {
+        let transmittance = self.color.value(&TextureContext::new(ray, hit));
+        let theta_o = wo.z.clamp(-1.0, 1.0).asin();
+        let h = (2.0 * hit.uv.y - 1.0).clamp(-1.0, 1.0);
+        let f = microfacet::fresnel_dielectric(theta_o.cos() * (1.0 - h * h).sqrt(), self.ir);
+        let tt = (1.0 - f) * (1.0 - f) * transmittance;
+        let trt = tt * f * transmittance / (DVec3::ONE - f * transmittance);
+
+        // The longitudinal angle of the half vector is spread around the
+        // tilt of each lobe, so the incoming angle is spread twice as much.
+        let beta = self.longitudinal_width;
+        let alpha = self.tilt;
+        [
+            HairLobe {
+                weight: DVec3::splat(f),
+                mean: -theta_o - 2.0 * alpha,
+                deviation: 2.0 * beta,
+                transmitted: false,
+            },
+            HairLobe {
+                weight: tt,
+                mean: -theta_o + alpha,
+                deviation: beta,
+                transmitted: true,
+            },
+            HairLobe {
+                weight: trt,
+                mean: -theta_o + 3.0 * alpha,
+                deviation: 4.0 * beta,
+                transmitted: false,
+            },
+        ]
+    }
+
+    /// Solid angle density of `wi` in a lobe, with both directions in the
+    /// fiber frame.
+    fn lobe_pdf(&self, lobe: &HairLobe, wo: DVec3, wi: DVec3) -> f64 {
+        let theta_i = wi.z.clamp(-1.0, 1.0).asin();
+        let cos_theta_i = theta_i.cos().max(1.0e-4);
+        let longitudinal = gaussian(theta_i, lobe.mean, lobe.deviation);
+        let azimuthal = if lobe.transmitted {
+            let phi = wi.y.atan2(wi.x) - wo.y.atan2(wo.x) - f64::PI;
+            let phi = (phi + f64::PI).rem_euclid(f64::TAU) - f64::PI;
+            trimmed_logistic(phi, self.azimuthal_width)
+        } else {
+            1.0 / f64::TAU
+        };
+        longitudinal * azimuthal / cos_theta_i
+    }
+
+    fn selection_weights(lobes: &[HairLobe; 3]) -> [f64; 3] {
+        let weights = lobes.each_ref().map(|lobe| luminance(lobe.weight).max(0.0));
+        let total: f64 = weights.iter().sum();
+        if total > 0.0 {
+            weights.map(|w| w / total)
+        } else {
+            [1.0, 0.0, 0.0]
+        }
+    }
+
+    /// The BSDF times the cosine factor, which by construction is a sum of
+    /// the lobe densities, and the density of sampling `wi`.
+    fn eval_and_pdf(&self, ray: &Ray, hit: &HitRecord, wo: DVec3, wi: DVec3) -> (DVec3, f64) {
+        let lobes = self.lobes(ray, hit, wo);
+        let selection = Self::selection_weights(&lobes);
+        let mut value = DVec3::ZERO;
+        let mut pdf = 0.0;
+        for (lobe, probability) in lobes.iter().zip(selection) {
+            let lobe_pdf = self.lobe_pdf(lobe, wo, wi);
+            value += lobe.weight * lobe_pdf;
+            pdf += probability * lobe_pdf;
+        }
+        (value, pdf)
+    }
+}
+
+impl<Color: Texture> Material for Hair<Color> {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<Scatter> {
+        let mut rng = rand::thread_rng();
+        let frame = Self::frame(hit);
+        let wo = frame.to_local(-ray.direction.normalize());
+        let lobes = self.lobes(ray, hit, wo);
+        let selection = Self::selection_weights(&lobes);
+
+        let mut choice = rng.gen::<f64>();
+        let mut index = 0;
+        while index < 2 && choice >= selection[index] {
+            choice -= selection[index];
+            index += 1;
+        }
+        let lobe = &lobes[index];
+
+        // Box-Muller transform for the longitudinal angle.
+        let radius = (-2.0 * (1.0 - rng.gen::<f64>()).ln()).sqrt();
+        let theta_i = lobe.mean + lobe.deviation * radius * (f64::TAU * rng.gen::<f64>()).cos();
+        if theta_i.abs() >= f64::FRAC_PI_2 {
+            return None;
+        }
+        let phi_o = wo.y.atan2(wo.x);
+        let phi_i = if lobe.transmitted {
+            phi_o + f64::PI + sample_trimmed_logistic(rng.gen(), self.azimuthal_width)
+        } else {
+            f64::TAU * rng.gen::<f64>()
+        };
+        let wi = DVec3::new(
+            theta_i.cos() * phi_i.cos(),
+            theta_i.cos() * phi_i.sin(),
+            theta_i.sin(),
+        );
+
+        let (value, pdf) = self.eval_and_pdf(ray, hit, wo, wi);
+        if pdf <= 0.0 {
+            return None;
+        }
+        Some(Scatter {
+            ray: Ray {
+                origin: hit.point,
+                direction: frame.to_world(wi),
+                ..*ray
+            },
+            attenuation: value / pdf,
+            pdf: Some(pdf),
+        })
+    }
+
+    fn eval(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> DVec3 {
+        let frame = Self::frame(hit);
+        let wo = frame.to_local(-ray.direction.normalize());
+        self.eval_and_pdf(ray, hit, wo, frame.to_local(direction)).0
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> f64 {
+        let frame = Self::frame(hit);
+        let wo = frame.to_local(-ray.direction.normalize());
+        self.eval_and_pdf(ray, hit, wo, frame.to_local(direction)).1
+    }
+}
+
+/// A Lambertian emitter, with the same radiance in all directions.
 pub struct DiffuseLight<E> {
     pub emit: E,
+    /// Multiplier applied to `emit`, such as a radiance in W/(sr·m²).
+    pub intensity: f64,
+    /// Whether light is emitted from the back face as well as the front face.
+    pub two_sided: bool,
+}
+
+impl<E> DiffuseLight<E> {
+    /// Creates an emitter with the given total emitted power (in W) spread
+    /// over a surface of the given area.
+    pub fn from_power(emit: E, power: f64, area: f64, two_sided: bool) -> Self {
+        let sides = if two_sided { 2.0 } else { 1.0 };
+        Self {
+            emit,
+            intensity: power / (sides * f64::PI * area),
+            two_sided,
+        }
+    }
 }
 
 impl<E: Texture> Material for DiffuseLight<E> {
@@ -190,7 +1049,172 @@ impl<E: Texture> Material for DiffuseLight<E> {
         None
     }
 
-    fn emitted(&self, uv: DVec2, point: DVec3) -> DVec3 {
-        self.emit.value(uv, point)
+    fn emitted(&self, ray: &Ray, hit: &HitRecord) -> DVec3 {
+        if hit.face == Face::Back && !self.two_sided {
+            return DVec3::ZERO;
+        }
+        self.intensity * self.emit.value(&TextureContext::new(ray, hit))
+    }
+}
+
+/// A one-sided emitter whose radiance varies with the angle from the normal,
+/// following a measured profile like the ones found in IES files.
+pub struct ProfiledLight<E> {
+    pub emit: E,
+    /// Multiplier applied to `emit`, such as a radiance in W/(sr·m²).
+    pub intensity: f64,
+    /// Relative radiance at evenly spaced angles from the normal, starting at
+    /// 0 and ending at 90 degrees. Values in between are linearly interpolated.
+    pub profile: Vec<f64>,
+}
+
+impl<E> ProfiledLight<E> {
+    fn profile_value(&self, cos_theta: f64) -> f64 {
+        match self.profile.as_slice() {
+            [] => 1.0,
+            [only] => *only,
+            profile => {
+                let x =
+                    cos_theta.clamp(0.0, 1.0).acos() / f64::FRAC_PI_2 * (profile.len() - 1) as f64;
+                let i = (x.floor() as usize).min(profile.len() - 2);
+                let frac = x - i as f64;
+                profile[i] * (1.0 - frac) + profile[i + 1] * frac
+            }
+        }
+    }
+}
+
+impl<E: Texture> Material for ProfiledLight<E> {
+    fn scatter(&self, _ray: &Ray, _hit: &HitRecord) -> Option<Scatter> {
+        None
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &HitRecord) -> DVec3 {
+        if hit.face == Face::Back {
+            return DVec3::ZERO;
+        }
+        let cos_theta = -ray.direction.normalize().dot(hit.normal);
+        let emit = self.emit.value(&TextureContext::new(ray, hit));
+        self.intensity * self.profile_value(cos_theta) * emit
+    }
+}
+
+/// Perturbs the shading normal of `inner` as if the surface was displaced
+/// along its normal by the red channel of `height`.
+pub struct BumpMap<M, H> {
+    pub inner: M,
+    pub height: H,
+    /// Multiplier applied to `height`, in scene units.
+    pub scale: f64,
+}
+
+impl<M, H: Texture> BumpMap<M, H> {
+    fn shading_hit<'a>(&self, ray: &Ray, hit: &HitRecord<'a>) -> HitRecord<'a> {
+        // Offsets in uv for the finite differences. The point is offset as
+        // well, so that solid textures like `Noise` can be used as height.
+        let delta = 0.0005;
+        let ctx = TextureContext::new(ray, hit);
+        let height = |du: f64, dv: f64| {
+            let offset = du * hit.dpdu + dv * hit.dpdv;
+            let object_offset = hit.object_rotation.inverse() * offset;
+            let ctx = TextureContext {
+                uv: ctx.uv + DVec2::new(du, dv),
+                point: ctx.point + offset,
+                object_point: ctx.object_point + object_offset,
+                ..ctx
+            };
+            self.scale * self.height.value(&ctx).x
+        };
+        let base = height(0.0, 0.0);
+        let dhdu = (height(delta, 0.0) - base) / delta;
+        let dhdv = (height(0.0, delta) - base) / delta;
+
+        let dpdu = hit.dpdu + dhdu * hit.normal;
+        let dpdv = hit.dpdv + dhdv * hit.normal;
+        let normal = match dpdu.cross(dpdv).try_normalize() {
+            Some(n) if n.dot(hit.normal) < 0.0 => -n,
+            Some(n) => n,
+            None => hit.normal,
+        };
+        HitRecord {
+            normal,
+            dpdu,
+            dpdv,
+            ..hit.clone()
+        }
+    }
+}
+
+impl<M: Material, H: Texture> Material for BumpMap<M, H> {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<Scatter> {
+        self.inner.scatter(ray, &self.shading_hit(ray, hit))
+    }
+
+    fn eval(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> DVec3 {
+        self.inner.eval(ray, &self.shading_hit(ray, hit), direction)
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> f64 {
+        self.inner.pdf(ray, &self.shading_hit(ray, hit), direction)
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &HitRecord) -> DVec3 {
+        self.inner.emitted(ray, &self.shading_hit(ray, hit))
+    }
+}
+
+/// Replaces the shading normal of `inner` with a tangent-space normal read
+/// from `normals`, where the red and green channels follow `dpdu` and `dpdv`.
+pub struct NormalMap<M, N> {
+    pub inner: M,
+    pub normals: N,
+    /// Scales the deviation from the geometric normal.
+    pub strength: f64,
+}
+
+impl<M, N: Texture> NormalMap<M, N> {
+    fn shading_hit<'a>(&self, ray: &Ray, hit: &HitRecord<'a>) -> HitRecord<'a> {
+        let n = hit.normal;
+        let tangent = match (hit.dpdu - n * n.dot(hit.dpdu)).try_normalize() {
+            Some(t) => t,
+            None => return hit.clone(),
+        };
+        // Back face hits flip the normal but not `dpdv`, so the bitangent
+        // flips too, keeping the frame right-handed and the bumps the same
+        // from both sides. `n × tangent` follows the normal already.
+        let bitangent = match (hit.dpdv - n * n.dot(hit.dpdv) - tangent * tangent.dot(hit.dpdv))
+            .try_normalize()
+        {
+            Some(b) if hit.face == Face::Back => -b,
+            Some(b) => b,
+            None => n.cross(tangent),
+        };
+
+        let local = 2.0 * self.normals.value(&TextureContext::new(ray, hit)) - DVec3::ONE;
+        let normal = (self.strength * (local.x * tangent + local.y * bitangent) + local.z * n)
+            .try_normalize()
+            .unwrap_or(n);
+        HitRecord {
+            normal,
+            ..hit.clone()
+        }
+    }
+}
+
+impl<M: Material, N: Texture> Material for NormalMap<M, N> {
+    fn scatter(&self, ray: &Ray, hit: &HitRecord) -> Option<Scatter> {
+        self.inner.scatter(ray, &self.shading_hit(ray, hit))
+    }
+
+    fn eval(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> DVec3 {
+        self.inner.eval(ray, &self.shading_hit(ray, hit), direction)
+    }
+
+    fn pdf(&self, ray: &Ray, hit: &HitRecord, direction: DVec3) -> f64 {
+        self.inner.pdf(ray, &self.shading_hit(ray, hit), direction)
+    }
+
+    fn emitted(&self, ray: &Ray, hit: &HitRecord) -> DVec3 {
+        self.inner.emitted(ray, &self.shading_hit(ray, hit))
     }
 }
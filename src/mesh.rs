@@ -0,0 +1,585 @@
+use std::{
+    collections::HashMap,
+    fs::File,
+    ops::{Add, Div},
+    path::Path,
+    sync::Arc,
+};
+
+use anyhow::{bail, Context};
+use glam::{DVec2, DVec3, DVec4};
+
+use crate::{
+    hittable::{BvhNode, Hittable, Triangle},
+    material::{luminance, Material},
+    ply::Ply,
+    texture::{Texture, TextureContext},
+};
+
+/// Crease angle in radians (30 degrees) for meshes loaded without normals,
+/// a common choice that smooths curved surfaces but keeps the edges of boxes
+/// and similar shapes sharp.
+pub const DEFAULT_CREASE_ANGLE: f64 = std::f64::consts::PI / 6.0;
+
+/// A polygon mesh with per-vertex attributes. `normals`, `tangents`, `uvs`
+/// and `colors` are either empty or have one entry per position.
+#[derive(Debug, Clone, Default)]
+pub struct Mesh {
+    pub positions: Vec<DVec3>,
+    pub normals: Vec<DVec3>,
+    /// See [`Triangle::tangents`].
+    pub tangents: Vec<DVec4>,
+    pub uvs: Vec<DVec2>,
+    /// Available to textures through
+    /// [`VertexColor`](crate::texture::VertexColor).
+    pub colors: Vec<DVec3>,
+    /// Vertex indices of each face, counter-clockwise when seen from the
+    /// front.
+    pub faces: Vec<Vec<usize>>,
+}
+
+fn edge_key(a: usize, b: usize) -> (usize, usize) {
+    (a.min(b), a.max(b))
+}
+
+/// Pairs of consecutive vertices around a face.
+fn face_edges(face: &[usize]) -> impl Iterator<Item = (usize, usize)> + '_ {
+    (0..face.len()).map(move |i| (face[i], face[(i + 1) % face.len()]))
+}
+
+/// Extends per-vertex `values` with the average over each of `edges` and
+/// then each of `faces`, in the order subdivision adds vertices. Attributes
+/// without a value per vertex are dropped.
+fn refine<T>(
+    values: &[T],
+    vertex_count: usize,
+    edges: &[(usize, usize)],
+    faces: &[Vec<usize>],
+) -> Vec<T>
+where
+    T: Copy + Default + Add<Output = T> + Div<f64, Output = T>,
+{
+    if values.len() != vertex_count {
+        return Vec::new();
+    }
+    let average = |indices: &[usize]| {
+        indices.iter().fold(T::default(), |s, &i| s + values[i]) / indices.len() as f64
+    };
+    let mut refined = values.to_vec();
+    refined.extend(edges.iter().map(|&(a, b)| average(&[a, b])));
+    refined.extend(faces.iter().map(|face| average(face)));
+    refined
+}
+
+impl Mesh {
+    pub fn new(positions: Vec<DVec3>, faces: Vec<Vec<usize>>) -> Self {
+        Self {
+            positions,
+            faces,
+            ..Default::default()
+        }
+    }
+
+    /// Reads the `vertex` and `face` elements of a PLY file. Vertices need
+    /// `x`, `y` and `z`, and may have `nx`, `ny` and `nz`, `u` and `v` (or
+    /// `s` and `t`) and `red`, `green` and `blue`. Faces need
+    /// `vertex_indices` (or `vertex_index`). Without normals in the file,
+    /// they are computed with a `crease_angle` in radians, usually
+    /// [`DEFAULT_CREASE_ANGLE`], or left out for flat shading with `None`.
+    pub fn from_ply(path: impl AsRef<Path>, crease_angle: Option<f64>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let ply = Ply::open(path)?;
+        let mut mesh = Self::from_ply_data(&ply)
+            .with_context(|| format!("failed to load mesh {}", path.display()))?;
+        if let (true, Some(crease_angle)) = (mesh.normals.is_empty(), crease_angle) {
+            mesh.compute_normals_with_crease_angle(crease_angle);
+        }
+        Ok(mesh)
+    }
+
+    fn from_ply_data(ply: &Ply) -> anyhow::Result<Self> {
+        let vertex = ply.element("vertex").context("no vertex element")?;
+        let vectors = |names: [&str; 3]| match names.map(|name| vertex.scalar(name)) {
+            [Some(x), Some(y), Some(z)] => (0..vertex.count)
+                .map(|i| DVec3::new(x[i], y[i], z[i]))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let positions = vectors(["x", "y", "z"]);
+        if positions.is_empty() && vertex.count > 0 {
+            bail!("no x, y and z properties");
+        }
+        let normals = vectors(["nx", "ny", "nz"]);
+        // Texture coordinates have their origin at the bottom left, images
+        // at the top left.
+        let uvs = match (
+            vertex.any_scalar(&["u", "s", "texture_u"]),
+            vertex.any_scalar(&["v", "t", "texture_v"]),
+        ) {
+            (Some(u), Some(v)) => (0..vertex.count)
+                .map(|i| DVec2::new(u[i], 1.0 - v[i]))
+                .collect(),
+            _ => Vec::new(),
+        };
+        let colors = match ["red", "green", "blue"].map(|name| vertex.normalized(name)) {
+            [Some(r), Some(g), Some(b)] => (0..vertex.count)
+                .map(|i| DVec3::new(r[i], g[i], b[i]))
+                .collect(),
+            _ => Vec::new(),
+        };
+
+        let face = ply.element("face").context("no face element")?;
+        let indices = face
+            .list("vertex_indices")
+            .or_else(|| face.list("vertex_index"))
+            .context("no vertex_indices property")?;
+        let faces = indices
+            .iter()
+            .map(|face| {
+                face.iter()
+                    .map(|&index| {
+                        if index < 0.0 || index as usize >= vertex.count {
+                            bail!("vertex index {index} out of range");
+                        }
+                        Ok(index as usize)
+                    })
+                    .collect()
+            })
+            .collect::<anyhow::Result<_>>()?;
+
+        Ok(Self {
+            positions,
+            normals,
+            tangents: Vec::new(),
+            uvs,
+            colors,
+            faces,
+        })
+    }
+
+    /// Reads an ASCII or binary STL file, merging vertices at the same
+    /// position. STL only stores flat facet normals, so vertex normals are
+    /// computed with a `crease_angle` in radians, usually
+    /// [`DEFAULT_CREASE_ANGLE`].
+    pub fn from_stl(path: impl AsRef<Path>, crease_angle: f64) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let mut mesh = File::open(path)
+            .and_then(|mut file| stl_io::read_stl(&mut file))
+            .map(|stl| {
+                Self::new(
+                    stl.vertices
+                        .iter()
+                        .map(|v| DVec3::from(v.0.map(f64::from)))
+                        .collect(),
+                    stl.faces
+                        .iter()
+                        .map(|face| face.vertices.to_vec())
+                        .collect(),
+                )
+            })
+            .with_context(|| format!("failed to load mesh {}", path.display()))?;
+        mesh.compute_normals_with_crease_angle(crease_angle);
+        Ok(mesh)
+    }
+
+    fn has_uvs(&self) -> bool {
+        self.uvs.len() == self.positions.len()
+    }
+
+    /// Splits every face into a fan of triangles.
+    pub fn triangulate(&self) -> Self {
+        let faces = self
+            .faces
+            .iter()
+            .flat_map(|face| {
+                (1..face.len().saturating_sub(1)).map(|i| vec![face[0], face[i], face[i + 1]])
+            })
+            .collect();
+        Self {
+            faces,
+            ..self.clone()
+        }
+    }
+
+    /// Area weighted average of the normals of the faces around each vertex.
+    pub fn compute_normals(&mut self) {
+        let mut normals = vec![DVec3::ZERO; self.positions.len()];
+        for face in &self.faces {
+            let normal = self.face_area_normal(face);
+            for &index in face {
+                normals[index] += normal;
+            }
+        }
+        self.normals = normals
+            .into_iter()
+            .map(|n| n.try_normalize().unwrap_or(DVec3::Y))
+            .collect();
+    }
+
+    /// Like [`Mesh::compute_normals`], but only averages faces whose normals
+    /// differ by at most `crease_angle` radians, so that sharper edges stay
+    /// sharp. Vertices on such edges are split, one per side.
+    pub fn compute_normals_with_crease_angle(&mut self, crease_angle: f64) {
+        let face_normals: Vec<DVec3> = self
+            .faces
+            .iter()
+            .map(|face| self.face_area_normal(face))
+            .collect();
+        let mut vertex_faces = vec![Vec::new(); self.positions.len()];
+        for (f, face) in self.faces.iter().enumerate() {
+            for &v in face {
+                vertex_faces[v].push(f);
+            }
+        }
+
+        let cos_crease = crease_angle.cos();
+        let mut mesh = Self::default();
+        let mut split: HashMap<(usize, [u64; 3]), usize> = HashMap::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            let direction = face_normals[f].normalize_or_zero();
+            let face = face
+                .iter()
+                .map(|&v| {
+                    let normal = vertex_faces[v]
+                        .iter()
+                        .map(|&g| face_normals[g])
+                        .filter(|n| n.normalize_or_zero().dot(direction) >= cos_crease)
+                        .fold(DVec3::ZERO, |sum, n| sum + n);
+                    let normal = normal.try_normalize().unwrap_or(DVec3::Y);
+                    *split
+                        .entry((v, normal.to_array().map(f64::to_bits)))
+                        .or_insert_with(|| {
+                            mesh.positions.push(self.positions[v]);
+                            mesh.normals.push(normal);
+                            mesh.tangents.extend(self.tangents.get(v));
+                            mesh.uvs.extend(self.uvs.get(v));
+                            mesh.colors.extend(self.colors.get(v));
+                            mesh.positions.len() - 1
+                        })
+                })
+                .collect();
+            mesh.faces.push(face);
+        }
+        *self = mesh;
+    }
+
+    /// Normal of a face with a length of twice its area.
+    fn face_area_normal(&self, face: &[usize]) -> DVec3 {
+        let origin = self.positions[face[0]];
+        (1..face.len().saturating_sub(1))
+            .map(|i| (self.positions[face[i]] - origin).cross(self.positions[face[i + 1]] - origin))
+            .fold(DVec3::ZERO, |sum, n| sum + n)
+    }
+
+    /// Applies `levels` steps of Loop subdivision, after triangulating.
+    /// Normals are recomputed and uvs and colors interpolated linearly.
+    pub fn subdivide_loop(&self, levels: usize) -> Self {
+        let mut mesh = self.triangulate();
+        for _ in 0..levels {
+            mesh = mesh.loop_step();
+        }
+        mesh.compute_normals();
+        mesh
+    }
+
+    fn loop_step(&self) -> Self {
+        let vertex_count = self.positions.len();
+
+        // Vertices opposite each edge, one for boundary edges.
+        let mut opposite: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        for face in &self.faces {
+            for (i, (a, b)) in face_edges(face).enumerate() {
+                opposite
+                    .entry(edge_key(a, b))
+                    .or_default()
+                    .push(face[(i + 2) % 3]);
+            }
+        }
+        let mut edges: Vec<_> = opposite.keys().copied().collect();
+        edges.sort_unstable();
+        let edge_index: HashMap<_, _> = edges
+            .iter()
+            .enumerate()
+            .map(|(i, &edge)| (edge, vertex_count + i))
+            .collect();
+
+        let mut neighbors = vec![Vec::new(); vertex_count];
+        let mut boundary_neighbors = vec![Vec::new(); vertex_count];
+        for &(a, b) in &edges {
+            neighbors[a].push(b);
+            neighbors[b].push(a);
+            if opposite[&(a, b)].len() == 1 {
+                boundary_neighbors[a].push(b);
+                boundary_neighbors[b].push(a);
+            }
+        }
+
+        let p = &self.positions;
+        let mut positions: Vec<DVec3> = (0..vertex_count)
+            .map(|v| {
+                if boundary_neighbors[v].len() == 2 {
+                    let [a, b] = [boundary_neighbors[v][0], boundary_neighbors[v][1]];
+                    0.75 * p[v] + 0.125 * (p[a] + p[b])
+                } else if !boundary_neighbors[v].is_empty() || neighbors[v].is_empty() {
+                    p[v]
+                } else {
+                    let n = neighbors[v].len() as f64;
+                    let beta = if neighbors[v].len() == 3 {
+                        3.0 / 16.0
+                    } else {
+                        3.0 / (8.0 * n)
+                    };
+                    let sum = neighbors[v].iter().fold(DVec3::ZERO, |s, &u| s + p[u]);
+                    (1.0 - n * beta) * p[v] + beta * sum
+                }
+            })
+            .collect();
+        positions.extend(edges.iter().map(|&(a, b)| match opposite[&(a, b)][..] {
+            [c, d] => 0.375 * (p[a] + p[b]) + 0.125 * (p[c] + p[d]),
+            _ => 0.5 * (p[a] + p[b]),
+        }));
+
+        let uvs = refine(&self.uvs, vertex_count, &edges, &[]);
+        let colors = refine(&self.colors, vertex_count, &edges, &[]);
+
+        let mut faces = Vec::with_capacity(4 * self.faces.len());
+        for face in &self.faces {
+            let [a, b, c] = [face[0], face[1], face[2]];
+            let ab = edge_index[&edge_key(a, b)];
+            let bc = edge_index[&edge_key(b, c)];
+            let ca = edge_index[&edge_key(c, a)];
+            faces.push(vec![a, ab, ca]);
+            faces.push(vec![ab, b, bc]);
+            faces.push(vec![ca, bc, c]);
+            faces.push(vec![ab, bc, ca]);
+        }
+
+        Self {
+            positions,
+            normals: Vec::new(),
+            tangents: Vec::new(),
+            uvs,
+            colors,
+            faces,
+        }
+    }
+
+    /// Applies `levels` steps of Catmull-Clark subdivision, which turns all
+    /// faces into quads. Normals are recomputed and uvs and colors
+    /// interpolated linearly.
+    pub fn subdivide_catmull_clark(&self, levels: usize) -> Self {
+        let mut mesh = self.clone();
+        for _ in 0..levels {
+            mesh = mesh.catmull_clark_step();
+        }
+        mesh.compute_normals();
+        mesh
+    }
+
+    fn catmull_clark_step(&self) -> Self {
+        let vertex_count = self.positions.len();
+        let p = &self.positions;
+        let average = |indices: &[usize], values: &dyn Fn(usize) -> DVec3| {
+            indices.iter().fold(DVec3::ZERO, |s, &i| s + values(i)) / indices.len() as f64
+        };
+
+        let face_points: Vec<DVec3> = self
+            .faces
+            .iter()
+            .map(|face| average(face, &|i| p[i]))
+            .collect();
+
+        // Faces on either side of each edge, one for boundary edges.
+        let mut edge_faces: HashMap<(usize, usize), Vec<usize>> = HashMap::new();
+        let mut vertex_faces = vec![Vec::new(); vertex_count];
+        for (f, face) in self.faces.iter().enumerate() {
+            for (a, b) in face_edges(face) {
+                edge_faces.entry(edge_key(a, b)).or_default().push(f);
+            }
+            for &v in face {
+                vertex_faces[v].push(f);
+            }
+        }
+        let mut edges: Vec<_> = edge_faces.keys().copied().collect();
+        edges.sort_unstable();
+        let edge_index: HashMap<_, _> = edges
+            .iter()
+            .enumerate()
+            .map(|(i, &edge)| (edge, vertex_count + i))
+            .collect();
+        let face_offset = vertex_count + edges.len();
+
+        let mut vertex_edges = vec![Vec::new(); vertex_count];
+        for &(a, b) in &edges {
+            vertex_edges[a].push((a, b));
+            vertex_edges[b].push((a, b));
+        }
+
+        let mut positions: Vec<DVec3> = (0..vertex_count)
+            .map(|v| {
+                let boundary: Vec<usize> = vertex_edges[v]
+                    .iter()
+                    .filter(|edge| edge_faces[edge].len() == 1)
+                    .map(|&(a, b)| if a == v { b } else { a })
+                    .collect();
+                if let [a, b] = boundary[..] {
+                    (p[a] + 6.0 * p[v] + p[b]) / 8.0
+                } else if !boundary.is_empty() || vertex_faces[v].is_empty() {
+                    p[v]
+                } else {
+                    let n = vertex_faces[v].len() as f64;
+                    let faces = average(&vertex_faces[v], &|f| face_points[f]);
+                    let midpoints = vertex_edges[v]
+                        .iter()
+                        .fold(DVec3::ZERO, |s, &(a, b)| s + 0.5 * (p[a] + p[b]))
+                        / vertex_edges[v].len() as f64;
+                    (faces + 2.0 * midpoints + (n - 3.0) * p[v]) / n
+                }
+            })
+            .collect();
+        positions.extend(edges.iter().map(|&(a, b)| match edge_faces[&(a, b)][..] {
+            [f, g] => 0.25 * (p[a] + p[b] + face_points[f] + face_points[g]),
+            _ => 0.5 * (p[a] + p[b]),
+        }));
+        positions.extend(face_points);
+
+        let uvs = refine(&self.uvs, vertex_count, &edges, &self.faces);
+        let colors = refine(&self.colors, vertex_count, &edges, &self.faces);
+
+        let mut faces = Vec::new();
+        for (f, face) in self.faces.iter().enumerate() {
+            let k = face.len();
+            for i in 0..k {
+                let previous = face[(i + k - 1) % k];
+                let next = face[(i + 1) % k];
+                faces.push(vec![
+                    face[i],
+                    edge_index[&edge_key(face[i], next)],
+                    face_offset + f,
+                    edge_index[&edge_key(previous, face[i])],
+                ]);
+            }
+        }
+
+        Self {
+            positions,
+            normals: Vec::new(),
+            tangents: Vec::new(),
+            uvs,
+            colors,
+            faces,
+        }
+    }
+
+    /// Derives tangents from the uvs, pointing along increasing u with the
+    /// bitangent along increasing v. Needs normals and uvs.
+    pub fn compute_tangents(&mut self) {
+        if !self.has_uvs() || self.normals.len() != self.positions.len() {
+            return;
+        }
+        let mut dpdus = vec![DVec3::ZERO; self.positions.len()];
+        let mut dpdvs = vec![DVec3::ZERO; self.positions.len()];
+        for face in &self.triangulate().faces {
+            let [a, b, c] = [face[0], face[1], face[2]];
+            let e1 = self.positions[b] - self.positions[a];
+            let e2 = self.positions[c] - self.positions[a];
+            let duv1 = self.uvs[b] - self.uvs[a];
+            let duv2 = self.uvs[c] - self.uvs[a];
+            let det = duv1.x * duv2.y - duv1.y * duv2.x;
+            if det.abs() <= 1.0e-12 {
+                continue;
+            }
+            let dpdu = (duv2.y * e1 - duv1.y * e2) / det;
+            let dpdv = (duv1.x * e2 - duv2.x * e1) / det;
+            for index in [a, b, c] {
+                dpdus[index] += dpdu;
+                dpdvs[index] += dpdv;
+            }
+        }
+        self.tangents = (0..self.positions.len())
+            .map(|i| {
+                let n = self.normals[i];
+                let tangent = (dpdus[i] - n * n.dot(dpdus[i]))
+                    .try_normalize()
+                    .unwrap_or_else(|| n.any_orthonormal_vector());
+                let sign = if n.cross(tangent).dot(dpdvs[i]) < 0.0 {
+                    -1.0
+                } else {
+                    1.0
+                };
+                tangent.extend(sign)
+            })
+            .collect();
+    }
+
+    /// Moves each vertex along its normal by `scale` times the luminance of
+    /// `height` at the vertex, then recomputes the normals. Subdivide first
+    /// so that there are enough vertices to show the detail.
+    pub fn displace<T: Texture>(&self, height: &T, scale: f64) -> Self {
+        let mut mesh = self.clone();
+        if mesh.normals.len() != mesh.positions.len() {
+            mesh.compute_normals();
+        }
+        for (index, position) in mesh.positions.iter_mut().enumerate() {
+            let normal = mesh.normals[index];
+            let ctx = TextureContext {
+                uv: mesh.uvs.get(index).copied().unwrap_or(DVec2::ZERO),
+                uv_footprint: DVec2::ZERO,
+                point: *position,
+                normal,
+                object_point: *position,
+                object_normal: normal,
+                direction: -normal,
+                time: 0.0,
+                width: 0.0,
+                color: DVec3::ONE,
+            };
+            *position += scale * luminance(height.value(&ctx)) * normal;
+        }
+        mesh.compute_normals();
+        mesh
+    }
+
+    /// Triangulates the mesh into triangles sharing `material`, with the
+    /// vertex attributes of the mesh.
+    pub fn triangles<Mat: Clone>(&self, material: Mat) -> Vec<Triangle<Mat>> {
+        let mesh = self.triangulate();
+        let has_normals = mesh.normals.len() == mesh.positions.len();
+        mesh.faces
+            .iter()
+            .map(|face| {
+                let [a, b, c] = [face[0], face[1], face[2]];
+                let mut triangle = Triangle::new(
+                    [mesh.positions[a], mesh.positions[b], mesh.positions[c]],
+                    material.clone(),
+                );
+                if has_normals {
+                    triangle.normals = Some([mesh.normals[a], mesh.normals[b], mesh.normals[c]]);
+                }
+                if mesh.tangents.len() == mesh.positions.len() {
+                    triangle.tangents =
+                        Some([mesh.tangents[a], mesh.tangents[b], mesh.tangents[c]]);
+                }
+                if mesh.has_uvs() {
+                    triangle.uvs = [mesh.uvs[a], mesh.uvs[b], mesh.uvs[c]];
+                }
+                if mesh.colors.len() == mesh.positions.len() {
+                    triangle.colors = Some([mesh.colors[a], mesh.colors[b], mesh.colors[c]]);
+                }
+                triangle
+            })
+            .collect()
+    }
+
+    /// Triangulates the mesh into a bounding volume hierarchy of triangles
+    /// sharing `material`. Panics if the mesh has no faces.
+    pub fn to_bvh<Mat: Material + 'static>(&self, material: Mat) -> BvhNode {
+        let mut triangles: Vec<Arc<dyn Hittable>> = self
+            .triangles(Arc::new(material))
+            .into_iter()
+            .map(|triangle| Arc::new(triangle) as Arc<dyn Hittable>)
+            .collect();
+        assert!(!triangles.is_empty(), "mesh has no faces");
+        BvhNode::new(&mut triangles, 0.0, 1.0)
+    }
+}
@@ -0,0 +1,179 @@
+use std::f64::consts as f64;
+
+use glam::DVec3;
+
+/// Orthonormal basis around a shading normal. Microfacet functions work in
+/// this local frame, where the normal is +Z.
+#[derive(Debug, Clone, Copy)]
+pub struct Frame {
+    pub tangent: DVec3,
+    pub bitangent: DVec3,
+    pub normal: DVec3,
+}
+
+impl Frame {
+    pub fn from_normal(normal: DVec3) -> Self {
+        let (tangent, bitangent) = normal.any_orthonormal_pair();
+        Self {
+            tangent,
+            bitangent,
+            normal,
+        }
+    }
+
+    pub fn to_local(&self, v: DVec3) -> DVec3 {
+        DVec3::new(
+            v.dot(self.tangent),
+            v.dot(self.bitangent),
+            v.dot(self.normal),
+        )
+    }
+
+    pub fn to_world(&self, v: DVec3) -> DVec3 {
+        v.x * self.tangent + v.y * self.bitangent + v.z * self.normal
+    }
+}
+
+/// Below this `alpha`, surfaces are treated as perfectly smooth.
+pub const MIN_ALPHA: f64 = 1.0e-3;
+
+/// Converts a perceptual roughness in `0..=1` to the GGX `alpha` parameter.
+pub fn roughness_to_alpha(roughness: f64) -> f64 {
+    let roughness = roughness.clamp(0.0, 1.0);
+    roughness * roughness
+}
+
+/// Isotropic GGX (Trowbridge-Reitz) microfacet distribution with
+/// height-correlated Smith masking.
+#[derive(Debug, Clone, Copy)]
+pub struct Ggx {
+    pub alpha: f64,
+}
+
+impl Ggx {
+    pub fn is_smooth(&self) -> bool {
+        self.alpha < MIN_ALPHA
+    }
+
+    /// Density of microfacet normals `m`.
+    pub fn d(&self, m: DVec3) -> f64 {
+        if m.z <= 0.0 {
+            return 0.0;
+        }
+        let a2 = self.alpha * self.alpha;
+        let x = m.z * m.z * (a2 - 1.0) + 1.0;
+        a2 / (f64::PI * x * x)
+    }
+
+    fn lambda(&self, w: DVec3) -> f64 {
+        let cos2 = w.z * w.z;
+        if cos2 == 0.0 {
+            return f64::INFINITY;
+        }
+        let tan2 = (1.0 - cos2).max(0.0) / cos2;
+        ((1.0 + self.alpha * self.alpha * tan2).sqrt() - 1.0) / 2.0
+    }
+
+    /// Fraction of microfacets visible from `w`.
+    pub fn g1(&self, w: DVec3) -> f64 {
+        1.0 / (1.0 + self.lambda(w))
+    }
+
+    /// Fraction of microfacets visible from both `wo` and `wi`.
+    pub fn g2(&self, wo: DVec3, wi: DVec3) -> f64 {
+        1.0 / (1.0 + self.lambda(wo) + self.lambda(wi))
+    }
+
+    /// Samples a microfacet normal visible from `wo`, following Heitz,
+    /// "Sampling the GGX Distribution of Visible Normals" (2018).
+    pub fn sample_visible_normal(&self, wo: DVec3, u1: f64, u2: f64) -> DVec3 {
+        let wo = wo * wo.z.signum();
+        let vh = DVec3::new(self.alpha * wo.x, self.alpha * wo.y, wo.z).normalize();
+        let len_sq = vh.x * vh.x + vh.y * vh.y;
+        let t1 = if len_sq > 0.0 {
+            DVec3::new(-vh.y, vh.x, 0.0) / len_sq.sqrt()
+        } else {
+            DVec3::X
+        };
+        let t2 = vh.cross(t1);
+
+        let r = u1.sqrt();
+        let phi = f64::TAU * u2;
+        let p1 = r * phi.cos();
+        let s = 0.5 * (1.0 + vh.z);
+        let p2 = (1.0 - s) * (1.0 - p1 * p1).sqrt() + s * r * phi.sin();
+        let nh = p1 * t1 + p2 * t2 + (1.0 - p1 * p1 - p2 * p2).max(0.0).sqrt() * vh;
+
+        DVec3::new(self.alpha * nh.x, self.alpha * nh.y, nh.z.max(0.0)).normalize()
+    }
+
+    /// Density of sampling `m` with [`Ggx::sample_visible_normal`].
+    pub fn visible_normal_pdf(&self, wo: DVec3, m: DVec3) -> f64 {
+        if wo.z == 0.0 {
+            return 0.0;
+        }
+        self.g1(wo) * wo.dot(m).abs() * self.d(m) / wo.z.abs()
+    }
+}
+
+/// Mirrors `w` about `m`; both point away from the surface.
+pub fn reflect(w: DVec3, m: DVec3) -> DVec3 {
+    2.0 * w.dot(m) * m - w
+}
+
+/// Fresnel reflectance of a conductor with complex index of refraction
+/// `eta + i k`, for light arriving at `cos_theta` to the normal.
+pub fn fresnel_conductor(cos_theta: f64, eta: DVec3, k: DVec3) -> DVec3 {
+    let cos2 = cos_theta.clamp(0.0, 1.0).powi(2);
+    let sin2 = 1.0 - cos2;
+    let eta2 = eta * eta;
+    let k2 = k * k;
+
+    let t0 = eta2 - k2 - DVec3::splat(sin2);
+    let a2_plus_b2 = (t0 * t0 + 4.0 * eta2 * k2).powf(0.5);
+    let t1 = a2_plus_b2 + DVec3::splat(cos2);
+    let a = (0.5 * (a2_plus_b2 + t0)).max(DVec3::ZERO).powf(0.5);
+    let t2 = 2.0 * cos_theta * a;
+    let rs = (t1 - t2) / (t1 + t2);
+
+    let t3 = cos2 * a2_plus_b2 + DVec3::splat(sin2 * sin2);
+    let t4 = t2 * sin2;
+    let rp = rs * (t3 - t4) / (t3 + t4);
+
+    0.5 * (rp + rs)
+}
+
+/// Schlick's approximation of the Fresnel reflectance, with reflectance `f0`
+/// at normal incidence.
+pub fn fresnel_schlick(cos_theta: f64, f0: DVec3) -> DVec3 {
+    f0 + (DVec3::ONE - f0) * (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5)
+}
+
+/// Refracts `w` through a surface with normal `m`, where `eta` is the ratio
+/// of the index of refraction below the surface to the one above. `w` points
+/// away from the surface on the side of `m`. Returns `None` on total internal
+/// reflection.
+pub fn refract(w: DVec3, m: DVec3, eta: f64) -> Option<DVec3> {
+    let cos_i = w.dot(m);
+    let sin2_t = (1.0 - cos_i * cos_i).max(0.0) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return None;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    Some(-w / eta + (cos_i / eta - cos_t) * m)
+}
+
+/// Exact Fresnel reflectance of an interface between two dielectrics, where
+/// `eta` is the ratio of the index of refraction on the transmitted side to
+/// the one on the incident side.
+pub fn fresnel_dielectric(cos_theta: f64, eta: f64) -> f64 {
+    let cos_i = cos_theta.clamp(0.0, 1.0);
+    let sin2_t = (1.0 - cos_i * cos_i) / (eta * eta);
+    if sin2_t >= 1.0 {
+        return 1.0;
+    }
+    let cos_t = (1.0 - sin2_t).sqrt();
+    let r_parallel = (eta * cos_i - cos_t) / (eta * cos_i + cos_t);
+    let r_perpendicular = (cos_i - eta * cos_t) / (cos_i + eta * cos_t);
+    (r_parallel * r_parallel + r_perpendicular * r_perpendicular) / 2.0
+}
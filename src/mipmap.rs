@@ -0,0 +1,249 @@
+use ::image::{ColorType, DynamicImage, Rgb};
+use glam::{DVec2, DVec3, IVec2};
+use half::f16;
+
+/// Encoding of the values stored in an image file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ColorSpace {
+    /// Values are proportional to light intensity, or are non-color data
+    /// such as roughness or normals.
+    Linear,
+    /// The sRGB transfer function, used by most 8-bit color images.
+    Srgb,
+    /// A pure power law with the given exponent.
+    Gamma(f64),
+}
+
+impl ColorSpace {
+    /// Converts an encoded value in `0..=1` to a linear one.
+    pub fn to_linear(self, value: f64) -> f64 {
+        match self {
+            ColorSpace::Linear => value,
+            ColorSpace::Srgb => {
+                if value <= 0.04045 {
+                    value / 12.92
+                } else {
+                    ((value + 0.055) / 1.055).powf(2.4)
+                }
+            }
+            ColorSpace::Gamma(gamma) => value.max(0.0).powf(gamma),
+        }
+    }
+}
+
+/// How texel coordinates outside of the image are mapped back into it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Wrap {
+    Repeat,
+    Mirror,
+    Clamp,
+}
+
+impl Wrap {
+    fn apply(self, coord: i32, size: i32) -> i32 {
+        match self {
+            Wrap::Repeat => coord.rem_euclid(size),
+            Wrap::Mirror => {
+                let period = coord.rem_euclid(2 * size);
+                if period < size {
+                    period
+                } else {
+                    2 * size - 1 - period
+                }
+            }
+            Wrap::Clamp => coord.clamp(0, size - 1),
+        }
+    }
+}
+
+/// How texels are combined when looking up a texture.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Filter {
+    /// The nearest texel of the full-resolution image.
+    Nearest,
+    /// Interpolates the four nearest texels of the mipmap level closest to the
+    /// footprint.
+    Bilinear,
+    /// Interpolates bilinear lookups in the two mipmap levels around the
+    /// footprint.
+    Trilinear,
+}
+
+/// Precision in which texels are kept in memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum TexelFormat {
+    /// 64-bit floats, 24 bytes per texel.
+    Double,
+    /// 32-bit floats, 12 bytes per texel.
+    Float,
+    /// 16-bit floats, 6 bytes per texel.
+    Half,
+}
+
+#[derive(Debug, Clone)]
+enum Texels {
+    Double(Vec<DVec3>),
+    Float(Vec<[f32; 3]>),
+    Half(Vec<[f16; 3]>),
+}
+
+impl Texels {
+    fn new(format: TexelFormat, texels: impl Iterator<Item = DVec3>) -> Self {
+        match format {
+            TexelFormat::Double => Texels::Double(texels.collect()),
+            TexelFormat::Float => Texels::Float(texels.map(|t| t.as_vec3().to_array()).collect()),
+            TexelFormat::Half => {
+                Texels::Half(texels.map(|t| t.to_array().map(f16::from_f64)).collect())
+            }
+        }
+    }
+
+    fn format(&self) -> TexelFormat {
+        match self {
+            Texels::Double(_) => TexelFormat::Double,
+            Texels::Float(_) => TexelFormat::Float,
+            Texels::Half(_) => TexelFormat::Half,
+        }
+    }
+
+    fn get(&self, index: usize) -> DVec3 {
+        match self {
+            Texels::Double(texels) => texels[index],
+            Texels::Float(texels) => DVec3::from(texels[index].map(f64::from)),
+            Texels::Half(texels) => DVec3::from(texels[index].map(f64::from)),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Level {
+    width: usize,
+    height: usize,
+    texels: Texels,
+}
+
+impl Level {
+    fn texel(&self, coord: IVec2, wrap: Wrap) -> DVec3 {
+        let x = wrap.apply(coord.x, self.width as i32) as usize;
+        let y = wrap.apply(coord.y, self.height as i32) as usize;
+        self.texels.get(y * self.width + x)
+    }
+
+    fn downsample(&self) -> Self {
+        let width = (self.width / 2).max(1);
+        let height = (self.height / 2).max(1);
+        let texels = (0..height).flat_map(|y| {
+            (0..width).map(move |x| {
+                let mut sum = DVec3::ZERO;
+                for (dx, dy) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+                    let coord = IVec2::new((2 * x + dx) as i32, (2 * y + dy) as i32);
+                    sum += self.texel(coord, Wrap::Clamp);
+                }
+                sum / 4.0
+            })
+        });
+        Self {
+            width,
+            height,
+            texels: Texels::new(self.texels.format(), texels),
+        }
+    }
+
+    fn nearest(&self, uv: DVec2, wrap: Wrap) -> DVec3 {
+        let size = DVec2::new(self.width as f64, self.height as f64);
+        self.texel((uv * size).floor().as_ivec2(), wrap)
+    }
+
+    fn bilinear(&self, uv: DVec2, wrap: Wrap) -> DVec3 {
+        let size = DVec2::new(self.width as f64, self.height as f64);
+        let st = uv * size - 0.5;
+        let base = st.floor();
+        let frac = st - base;
+        let base = base.as_ivec2();
+        let top = self
+            .texel(base, wrap)
+            .lerp(self.texel(base + IVec2::X, wrap), frac.x);
+        let bottom = self
+            .texel(base + IVec2::Y, wrap)
+            .lerp(self.texel(base + IVec2::ONE, wrap), frac.x);
+        top.lerp(bottom, frac.y)
+    }
+}
+
+/// An image decoded to linear RGB, along with successively halved copies of
+/// it for filtering.
+#[derive(Debug, Clone)]
+pub struct MipMap {
+    levels: Vec<Level>,
+}
+
+impl MipMap {
+    /// Decodes `image` to linear RGB. Integer images are interpreted in
+    /// `color_space`, while floating point (HDR) images are always assumed to
+    /// be linear already.
+    pub fn new(image: &DynamicImage, color_space: ColorSpace) -> Self {
+        Self::with_format(image, color_space, TexelFormat::Double)
+    }
+
+    /// Like [`MipMap::new`], storing texels in the given format.
+    pub fn with_format(image: &DynamicImage, color_space: ColorSpace, format: TexelFormat) -> Self {
+        let color_space = match image.color() {
+            ColorType::Rgb32F | ColorType::Rgba32F => ColorSpace::Linear,
+            _ => color_space,
+        };
+        let decoded = image.to_rgb32f();
+        let texels = decoded.pixels().map(|&Rgb([r, g, b])| {
+            let decode = |v: f32| color_space.to_linear(v as f64);
+            DVec3::new(decode(r), decode(g), decode(b))
+        });
+        let texels = Texels::new(format, texels);
+
+        let mut levels = vec![Level {
+            width: decoded.width() as usize,
+            height: decoded.height() as usize,
+            texels,
+        }];
+        loop {
+            let last = levels.last().unwrap();
+            if last.width == 1 && last.height == 1 {
+                break;
+            }
+            let next = last.downsample();
+            levels.push(next);
+        }
+        Self { levels }
+    }
+
+    pub fn width(&self) -> usize {
+        self.levels[0].width
+    }
+
+    pub fn height(&self) -> usize {
+        self.levels[0].height
+    }
+
+    /// Looks up the texture at `uv`, where (0, 0) is the top left corner of
+    /// the image. `footprint` is the extent of the area to filter over, in
+    /// the same units as `uv`.
+    pub fn lookup(&self, uv: DVec2, footprint: DVec2, filter: Filter, wrap: Wrap) -> DVec3 {
+        let texels = footprint * DVec2::new(self.width() as f64, self.height() as f64);
+        let max_level = (self.levels.len() - 1) as f64;
+        let level = texels
+            .max_element()
+            .max(1.0e-8)
+            .log2()
+            .clamp(0.0, max_level);
+
+        match filter {
+            Filter::Nearest => self.levels[0].nearest(uv, wrap),
+            Filter::Bilinear => self.levels[level.round() as usize].bilinear(uv, wrap),
+            Filter::Trilinear => {
+                let lower = level.floor();
+                let upper = level.ceil();
+                let a = self.levels[lower as usize].bilinear(uv, wrap);
+                let b = self.levels[upper as usize].bilinear(uv, wrap);
+                a.lerp(b, level - lower)
+            }
+        }
+    }
+}
@@ -0,0 +1,436 @@
+use std::{f64::consts as f64, fs, path::Path};
+
+use anyhow::{bail, Context};
+use glam::{DQuat, DVec2, DVec3, Vec3};
+
+use crate::{
+    hittable::{compute_face_normal, Aabb, HitRecord, Hittable},
+    material::Material,
+    ply::Ply,
+    ray::Ray,
+};
+
+/// Particles stored in flat arrays, in single precision to save memory.
+/// `radii` and `colors` are either empty or have one entry per center.
+#[derive(Debug, Clone, Default)]
+pub struct Particles {
+    pub centers: Vec<Vec3>,
+    pub radii: Vec<f32>,
+    pub colors: Vec<Vec3>,
+}
+
+impl Particles {
+    /// Reads the `vertex` element of a PLY file, with `x`, `y` and `z`, and
+    /// optionally `radius` (or `pscale` or `scale`) and `red`, `green` and
+    /// `blue`.
+    pub fn from_ply(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        let ply = Ply::open(path)?;
+        Self::from_ply_data(&ply)
+            .with_context(|| format!("failed to load particles {}", path.display()))
+    }
+
+    fn from_ply_data(ply: &Ply) -> anyhow::Result<Self> {
+        let vertex = ply.element("vertex").context("no vertex element")?;
+        let column = |name| {
+            vertex
+                .scalar(name)
+                .with_context(|| format!("no {name} property"))
+        };
+        let (x, y, z) = (column("x")?, column("y")?, column("z")?);
+        let centers = (0..vertex.count)
+            .map(|i| Vec3::new(x[i] as f32, y[i] as f32, z[i] as f32))
+            .collect();
+        let radii = vertex
+            .any_scalar(&["radius", "pscale", "scale"])
+            .map(|r| r.iter().map(|&r| r as f32).collect())
+            .unwrap_or_default();
+        let colors = match (
+            vertex.normalized("red"),
+            vertex.normalized("green"),
+            vertex.normalized("blue"),
+        ) {
+            (Some(r), Some(g), Some(b)) => (0..vertex.count)
+                .map(|i| Vec3::new(r[i] as f32, g[i] as f32, b[i] as f32))
+                .collect(),
+            _ => Vec::new(),
+        };
+        Ok(Self {
+            centers,
+            radii,
+            colors,
+        })
+    }
+
+    /// Reads comma separated values with a header line naming the columns
+    /// `x`, `y` and `z`, and optionally `radius` and `red`, `green` and
+    /// `blue` in `0..=1`. Lines starting with `#` are ignored.
+    pub fn from_csv(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        fs::read_to_string(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|text| Self::parse_csv(&text))
+            .with_context(|| format!("failed to load particles {}", path.display()))
+    }
+
+    fn parse_csv(text: &str) -> anyhow::Result<Self> {
+        let mut lines = text
+            .lines()
+            .enumerate()
+            .filter(|(_, line)| !line.trim().is_empty() && !line.starts_with('#'));
+        let (_, header) = lines.next().context("missing header line")?;
+        let columns: Vec<&str> = header.split(',').map(str::trim).collect();
+        let column = |name| columns.iter().position(|&c| c == name);
+        let required = |name| column(name).with_context(|| format!("no {name} column"));
+        let [x, y, z] = [required("x")?, required("y")?, required("z")?];
+        let radius = column("radius");
+        let color = match (column("red"), column("green"), column("blue")) {
+            (Some(r), Some(g), Some(b)) => Some([r, g, b]),
+            _ => None,
+        };
+
+        let mut particles = Self::default();
+        for (number, line) in lines {
+            let values = line
+                .split(',')
+                .map(|value| value.trim().parse::<f32>())
+                .collect::<Result<Vec<_>, _>>()
+                .with_context(|| format!("invalid number on line {}", number + 1))?;
+            if values.len() != columns.len() {
+                bail!(
+                    "expected {} values on line {}, found {}",
+                    columns.len(),
+                    number + 1,
+                    values.len()
+                );
+            }
+            particles
+                .centers
+                .push(Vec3::new(values[x], values[y], values[z]));
+            if let Some(radius) = radius {
+                particles.radii.push(values[radius]);
+            }
+            if let Some([r, g, b]) = color {
+                particles
+                    .colors
+                    .push(Vec3::new(values[r], values[g], values[b]));
+            }
+        }
+        Ok(particles)
+    }
+}
+
+/// A node of the flattened hierarchy. Leaves hold `count` particles from
+/// `first`, inner nodes have their left child right after them and their
+/// right child at `first`.
+#[derive(Debug, Clone, Copy)]
+struct Node {
+    bounds: Aabb,
+    first: u32,
+    count: u32,
+}
+
+const LEAF_SIZE: usize = 4;
+
+fn reorder<T: Copy>(values: &mut Vec<T>, order: &[u32]) {
+    if !values.is_empty() {
+        *values = order.iter().map(|&i| values[i as usize]).collect();
+    }
+}
+
+/// Many spheres sharing one material, with their own bounding volume
+/// hierarchy. Particle colors are available to textures through
+/// [`VertexColor`](crate::texture::VertexColor).
+///
+/// This needs far less memory than a [`Sphere`](crate::hittable::Sphere)
+/// per particle in a [`BvhNode`](crate::hittable::BvhNode), which matters
+/// for millions of particles.
+#[derive(Debug)]
+pub struct SphereCloud<Mat> {
+    particles: Particles,
+    /// Radius of particles without their own.
+    radius: f32,
+    nodes: Vec<Node>,
+    material: Mat,
+}
+
+impl<Mat> SphereCloud<Mat> {
+    /// Builds the hierarchy over `particles`, reordering them. Particles
+    /// without radii all get `radius`. Fails if `radii` or `colors` are
+    /// neither empty nor have one entry per particle.
+    pub fn new(mut particles: Particles, radius: f32, material: Mat) -> anyhow::Result<Self> {
+        let count = particles.centers.len();
+        if !particles.radii.is_empty() && particles.radii.len() != count {
+            bail!(
+                "expected a radius for each of {count} particles, found {}",
+                particles.radii.len()
+            );
+        }
+        if !particles.colors.is_empty() && particles.colors.len() != count {
+            bail!(
+                "expected a color for each of {count} particles, found {}",
+                particles.colors.len()
+            );
+        }
+
+        let mut cloud = Self {
+            particles: Particles::default(),
+            radius,
+            nodes: Vec::with_capacity(2 * count / LEAF_SIZE + 1),
+            material,
+        };
+        if count > 0 {
+            let mut order: Vec<u32> = (0..count as u32).collect();
+            cloud.build(&particles, &mut order, 0);
+            reorder(&mut particles.centers, &order);
+            reorder(&mut particles.radii, &order);
+            reorder(&mut particles.colors, &order);
+        }
+        cloud.particles = particles;
+        Ok(cloud)
+    }
+
+    pub fn len(&self) -> usize {
+        self.particles.centers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.particles.centers.is_empty()
+    }
+
+    fn radius_of(particles: &Particles, index: usize, default: f32) -> f32 {
+        particles.radii.get(index).copied().unwrap_or(default)
+    }
+
+    /// Adds the nodes for the particles `order`, which starts at `offset`
+    /// in the final order, splitting at the median along the widest axis.
+    fn build(&mut self, particles: &Particles, order: &mut [u32], offset: usize) {
+        let mut bounds = Aabb {
+            minimum: DVec3::splat(f64::INFINITY),
+            maximum: DVec3::splat(-f64::INFINITY),
+        };
+        let mut centroids = bounds;
+        for &i in order.iter() {
+            let center = particles.centers[i as usize].as_dvec3();
+            let radius = Self::radius_of(particles, i as usize, self.radius) as f64;
+            bounds.minimum = bounds.minimum.min(center - radius);
+            bounds.maximum = bounds.maximum.max(center + radius);
+            centroids.minimum = centroids.minimum.min(center);
+            centroids.maximum = centroids.maximum.max(center);
+        }
+
+        let index = self.nodes.len();
+        self.nodes.push(Node {
+            bounds,
+            first: offset as u32,
+            count: order.len() as u32,
+        });
+        if order.len() <= LEAF_SIZE {
+            return;
+        }
+
+        let extent = centroids.maximum - centroids.minimum;
+        let axis = if extent.x > extent.y && extent.x > extent.z {
+            0
+        } else if extent.y > extent.z {
+            1
+        } else {
+            2
+        };
+        let mid = order.len() / 2;
+        order.select_nth_unstable_by(mid, |&a, &b| {
+            let a = particles.centers[a as usize][axis];
+            let b = particles.centers[b as usize][axis];
+            a.total_cmp(&b)
+        });
+        let (left, right) = order.split_at_mut(mid);
+        self.build(particles, left, offset);
+        let right_index = self.nodes.len();
+        self.build(particles, right, offset + mid);
+        self.nodes[index] = Node {
+            bounds,
+            first: right_index as u32,
+            count: 0,
+        };
+    }
+
+    /// Distance along the ray to particle `index`, if within range.
+    fn sphere_hit(&self, index: usize, ray: &Ray, t_min: f64, t_max: f64) -> Option<f64> {
+        let center = self.particles.centers[index].as_dvec3();
+        let radius = Self::radius_of(&self.particles, index, self.radius) as f64;
+        let center_to_origin = ray.origin - center;
+        let a = ray.direction.length_squared();
+        let half_b = ray.direction.dot(center_to_origin);
+        let c = center_to_origin.length_squared() - radius * radius;
+        let discriminant = half_b * half_b - a * c;
+        if discriminant < 0.0 {
+            return None;
+        }
+        let root = discriminant.sqrt();
+        [(-half_b - root) / a, (-half_b + root) / a]
+            .into_iter()
+            .find(|t| (t_min..=t_max).contains(t))
+    }
+}
+
+impl<Mat: Material> Hittable for SphereCloud<Mat> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        let mut closest = None;
+        let mut t_max = t_max;
+        let mut stack = Vec::with_capacity(64);
+        if !self.nodes.is_empty() {
+            stack.push(0);
+        }
+        while let Some(index) = stack.pop() {
+            let node = self.nodes[index];
+            if !node.bounds.is_hit_by(ray, t_min, t_max) {
+                continue;
+            }
+            if node.count == 0 {
+                stack.push(node.first as usize);
+                stack.push(index + 1);
+                continue;
+            }
+            let first = node.first as usize;
+            for particle in first..first + node.count as usize {
+                if let Some(t) = self.sphere_hit(particle, ray, t_min, t_max) {
+                    t_max = t;
+                    closest = Some(particle);
+                }
+            }
+        }
+
+        let index = closest?;
+        let center = self.particles.centers[index].as_dvec3();
+        let radius = Self::radius_of(&self.particles, index, self.radius) as f64;
+        let point = ray.at(t_max);
+        let outward_normal = (point - center) / radius;
+        let (normal, face) = compute_face_normal(ray, outward_normal);
+
+        // The same parameterization as a `Sphere`.
+        let n = outward_normal;
+        let uv = DVec2::new(
+            ((-n.z).atan2(n.x) + f64::PI) / f64::TAU,
+            n.y.clamp(-1.0, 1.0).acos() / f64::PI,
+        );
+        let sin_latitude = (1.0 - n.y * n.y).max(1.0e-8).sqrt();
+        let dpdu = f64::TAU * radius * DVec3::new(n.z, 0.0, -n.x);
+        let dpdv = f64::PI
+            * radius
+            * DVec3::new(
+                n.y * n.x / sin_latitude,
+                -sin_latitude,
+                n.y * n.z / sin_latitude,
+            );
+
+        Some(HitRecord {
+            t: t_max,
+            point,
+            normal,
+            object_point: point,
+            object_rotation: DQuat::IDENTITY,
+            uv,
+            dpdu,
+            dpdv,
+            face,
+            color: self
+                .particles
+                .colors
+                .get(index)
+                .map_or(DVec3::ONE, |c| c.as_dvec3()),
+            material: &self.material,
+        })
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<Aabb> {
+        // An empty cloud still needs a box to go into a hierarchy.
+        Some(self.nodes.first().map_or(
+            Aabb {
+                minimum: DVec3::ZERO,
+                maximum: DVec3::ZERO,
+            },
+            |node| node.bounds,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+    use crate::{
+        hittable::{BvhNode, Sphere},
+        material::Lambertian,
+        texture::Solid,
+    };
+
+    #[test]
+    fn parses_csv() {
+        let text = "# exported particles\n\
+            x, y, z, radius, red, green, blue\n\
+            0, 1, 2, 0.5, 1, 0, 0\n\
+            \n\
+            3, 4, 5, 0.25, 0, 0, 1\n";
+        let particles = Particles::parse_csv(text).unwrap();
+        assert_eq!(
+            particles.centers,
+            [Vec3::new(0.0, 1.0, 2.0), Vec3::new(3.0, 4.0, 5.0)]
+        );
+        assert_eq!(particles.radii, [0.5, 0.25]);
+        assert_eq!(particles.colors[1], Vec3::Z);
+    }
+
+    #[test]
+    fn rejects_missing_column() {
+        let error = Particles::parse_csv("x,y,radius\n0,1,2\n").unwrap_err();
+        assert_eq!(error.to_string(), "no z column");
+    }
+
+    #[test]
+    fn rejects_short_row() {
+        let error = Particles::parse_csv("x,y,z\n0,1,2\n3,4\n").unwrap_err();
+        assert_eq!(error.to_string(), "expected 3 values on line 3, found 2");
+    }
+
+    #[test]
+    fn allows_empty_cloud() {
+        let particles = Particles::parse_csv("x,y,z\n").unwrap();
+        let material = Lambertian {
+            albedo: Solid { color: DVec3::ONE },
+        };
+        let cloud = SphereCloud::new(particles, 1.0, material.clone()).unwrap();
+        assert!(cloud.is_empty());
+        let ray = Ray {
+            origin: DVec3::ZERO,
+            direction: DVec3::X,
+            time: 0.0,
+            width: 0.0,
+            spread: 0.0,
+        };
+        assert!(cloud.hit(&ray, 0.001, f64::INFINITY).is_none());
+
+        let sphere = Sphere {
+            center: DVec3::new(5.0, 0.0, 0.0),
+            radius: 1.0,
+            material,
+        };
+        let mut list: Vec<Arc<dyn Hittable>> = vec![Arc::new(cloud), Arc::new(sphere)];
+        let bvh = BvhNode::new(&mut list, 0.0, 1.0);
+        let hit = bvh.hit(&ray, 0.001, f64::INFINITY).unwrap();
+        assert!((hit.t - 4.0).abs() < 1.0e-9);
+    }
+
+    #[test]
+    fn rejects_mismatched_radii() {
+        let particles = Particles {
+            centers: vec![Vec3::ZERO, Vec3::X],
+            radii: vec![1.0],
+            colors: Vec::new(),
+        };
+        let material = Lambertian {
+            albedo: Solid { color: DVec3::ONE },
+        };
+        assert!(SphereCloud::new(particles, 1.0, material).is_err());
+    }
+}
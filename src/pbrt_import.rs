@@ -0,0 +1,1359 @@
+use std::{
+    collections::HashMap,
+    fs,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::{bail, Context};
+use glam::{DAffine3, DMat3, DMat4, DVec2, DVec3};
+
+use crate::{
+    camera::{Camera, CameraDescriptor},
+    hittable::{BvhNode, FlipFace, Hittable, Sphere, Transform, World},
+    light::{Area, Directional, Light, Point, Spot},
+    material::{Conductor, Dielectric, DiffuseLight, Lambertian, Material, Principled},
+    mesh::Mesh,
+    mipmap::{ColorSpace, Wrap},
+    scene::{RenderSettings, Scene},
+    texture::{Image, Multiply, Solid, Texture},
+    texture_manager::TextureManager,
+};
+
+/// A scene read by [`load`] or [`parse`].
+pub struct PbrtScene {
+    pub scene: Scene,
+    pub settings: RenderSettings,
+    /// Directives and parameters that were skipped or approximated, with
+    /// the file and line they appear on.
+    pub warnings: Vec<String>,
+}
+
+/// Loads a scene in a subset of the pbrt-v4 format, with the image size,
+/// samples per pixel and path depth of its `Film`, `Sampler` and
+/// `Integrator`.
+///
+/// Perspective cameras, `sphere`, `trianglemesh` and `plymesh` shapes,
+/// `diffuse`, `coateddiffuse`, `conductor`, `dielectric` and
+/// `thindielectric` materials, `imagemap` and `constant` textures, diffuse
+/// area lights and `infinite`, `point`, `spot` and `distant` lights are
+/// supported, along with transformations, attributes, named materials and
+/// coordinate systems, object instances and `Include`. Other directives are
+/// skipped, and unsupported parameters approximated, with a warning.
+///
+/// pbrt's cameras are left-handed, so the scene is mirrored where needed to
+/// render the same image as pbrt.
+pub fn load(path: impl AsRef<Path>) -> anyhow::Result<PbrtScene> {
+    let path = path.as_ref();
+    let mut importer = Importer::new(path.parent().unwrap_or(Path::new("")));
+    importer
+        .include(path)
+        .and_then(|()| importer.finish())
+        .with_context(|| format!("failed to load pbrt scene {}", path.display()))
+}
+
+/// Like [`load`], but reads the scene from `text`, resolving relative file
+/// names against `directory`.
+pub fn parse(text: &str, directory: impl AsRef<Path>) -> anyhow::Result<PbrtScene> {
+    let mut importer = Importer::new(directory.as_ref());
+    importer
+        .read(text, Path::new("<text>"))
+        .and_then(|()| importer.finish())
+        .context("failed to parse pbrt scene")
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    /// A directive, number or boolean.
+    Word(String),
+    Quoted(String),
+    Open,
+    Close,
+}
+
+/// Splits `text` into tokens, each with its line number.
+fn tokenize(text: &str) -> anyhow::Result<Vec<(Token, usize)>> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    let mut line = 1;
+    while let Some(c) = chars.next() {
+        match c {
+            '\n' => line += 1,
+            '#' => while chars.next_if(|&c| c != '\n').is_some() {},
+            '[' => tokens.push((Token::Open, line)),
+            ']' => tokens.push((Token::Close, line)),
+            '"' => {
+                let mut text = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some('\\') => text.extend(chars.next()),
+                        Some('\n') | None => bail!("unterminated string on line {line}"),
+                        Some(c) => text.push(c),
+                    }
+                }
+                tokens.push((Token::Quoted(text), line));
+            }
+            c if c.is_whitespace() => {}
+            c => {
+                let mut word = String::from(c);
+                while let Some(c) =
+                    chars.next_if(|&c| !c.is_whitespace() && !matches!(c, '"' | '#' | '[' | ']'))
+                {
+                    word.push(c);
+                }
+                tokens.push((Token::Word(word), line));
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+struct Tokens {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+}
+
+impl Tokens {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Option<(Token, usize)> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+        token
+    }
+
+    fn expect(&mut self, what: &str) -> anyhow::Result<Token> {
+        match self.next() {
+            Some((token, _)) => Ok(token),
+            None => bail!("unexpected end of file, expected {what}"),
+        }
+    }
+
+    fn number(&mut self) -> anyhow::Result<f64> {
+        match self.expect("a number")? {
+            Token::Word(word) => word
+                .parse()
+                .with_context(|| format!("expected a number, found {word}")),
+            token => bail!("expected a number, found {token:?}"),
+        }
+    }
+
+    /// `N` numbers, optionally in brackets.
+    fn numbers<const N: usize>(&mut self) -> anyhow::Result<[f64; N]> {
+        let bracketed = self.peek() == Some(&Token::Open);
+        if bracketed {
+            self.position += 1;
+        }
+        let mut numbers = [0.0; N];
+        for number in &mut numbers {
+            *number = self.number()?;
+        }
+        if bracketed && self.expect("]")? != Token::Close {
+            bail!("expected {N} numbers in brackets");
+        }
+        Ok(numbers)
+    }
+
+    fn string(&mut self) -> anyhow::Result<String> {
+        match self.expect("a string")? {
+            Token::Quoted(text) => Ok(text),
+            token => bail!("expected a string, found {token:?}"),
+        }
+    }
+
+    /// Parameters of the form `"type name" value` or `"type name" [values]`
+    /// up to the next directive.
+    fn params(&mut self) -> anyhow::Result<Params> {
+        let mut params = Vec::new();
+        while let Some(Token::Quoted(declaration)) = self.peek() {
+            let Some((ty, name)) = declaration
+                .split_once(char::is_whitespace)
+                .map(|(ty, name)| (ty.to_owned(), name.trim().to_owned()))
+            else {
+                bail!("invalid parameter declaration \"{declaration}\"");
+            };
+            self.position += 1;
+
+            let mut items = Vec::new();
+            match self.expect("a parameter value")? {
+                Token::Open => loop {
+                    match self.expect("]")? {
+                        Token::Close => break,
+                        Token::Open => bail!("nested brackets in parameter {name}"),
+                        item => items.push(item),
+                    }
+                },
+                Token::Close => bail!("unexpected ] in parameter {name}"),
+                item => items.push(item),
+            }
+
+            let text = |item: &Token| match item {
+                Token::Word(text) | Token::Quoted(text) => text.clone(),
+                _ => String::new(),
+            };
+            let value = if ty == "bool" {
+                let bools = items.iter().map(|item| match text(item).as_str() {
+                    "true" => Ok(true),
+                    "false" => Ok(false),
+                    other => bail!("invalid bool {other} in parameter {name}"),
+                });
+                Value::Bools(bools.collect::<anyhow::Result<_>>()?)
+            } else if !items.is_empty() && items.iter().all(|i| matches!(i, Token::Quoted(_))) {
+                Value::Strings(items.iter().map(text).collect())
+            } else {
+                let numbers = items.iter().map(|item| match item {
+                    Token::Word(word) => word
+                        .parse()
+                        .with_context(|| format!("invalid number {word} in parameter {name}")),
+                    _ => bail!("mixed values in parameter {name}"),
+                });
+                Value::Numbers(numbers.collect::<anyhow::Result<_>>()?)
+            };
+            params.push(Param { ty, name, value });
+        }
+        Ok(Params(params))
+    }
+
+    /// Skips the arguments of an unsupported directive.
+    fn skip_arguments(&mut self) {
+        while let Some(token) = self.peek() {
+            if matches!(token, Token::Word(word) if word.starts_with(char::is_uppercase)) {
+                break;
+            }
+            self.position += 1;
+        }
+    }
+}
+
+#[derive(Debug)]
+enum Value {
+    Numbers(Vec<f64>),
+    Strings(Vec<String>),
+    Bools(Vec<bool>),
+}
+
+#[derive(Debug)]
+struct Param {
+    ty: String,
+    name: String,
+    value: Value,
+}
+
+#[derive(Debug, Default)]
+struct Params(Vec<Param>);
+
+impl Params {
+    fn get(&self, name: &str) -> Option<&Param> {
+        self.0.iter().find(|param| param.name == name)
+    }
+
+    fn numbers(&self, name: &str) -> Option<&[f64]> {
+        match &self.get(name)?.value {
+            Value::Numbers(numbers) => Some(numbers),
+            _ => None,
+        }
+    }
+
+    fn float(&self, name: &str, default: f64) -> f64 {
+        self.numbers(name)
+            .and_then(|numbers| numbers.first().copied())
+            .unwrap_or(default)
+    }
+
+    /// A count that has to be at least 1, like an image size.
+    fn positive(&self, name: &str, default: f64) -> anyhow::Result<usize> {
+        let value = self.float(name, default);
+        if value < 1.0 {
+            bail!("{name} must be positive, found {value}");
+        }
+        Ok(value as usize)
+    }
+
+    fn point(&self, name: &str, default: DVec3) -> DVec3 {
+        match self.numbers(name) {
+            Some(&[x, y, z]) => DVec3::new(x, y, z),
+            _ => default,
+        }
+    }
+
+    fn string(&self, name: &str) -> Option<&str> {
+        match &self.get(name)?.value {
+            Value::Strings(strings) => strings.first().map(String::as_str),
+            _ => None,
+        }
+    }
+
+    fn bool(&self, name: &str, default: bool) -> bool {
+        match self.get(name).map(|param| &param.value) {
+            Some(Value::Bools(bools)) => bools.first().copied().unwrap_or(default),
+            _ => default,
+        }
+    }
+}
+
+fn solid(color: DVec3) -> Arc<dyn Texture> {
+    Arc::new(Solid { color })
+}
+
+/// The attributes saved by `AttributeBegin`.
+#[derive(Clone)]
+struct State {
+    /// pbrt's current transformation matrix, from object to world space.
+    transform: DAffine3,
+    /// `None` for `interface` materials, whose shapes are skipped.
+    material: Option<Arc<dyn Material>>,
+    /// Emitter replacing the material of shapes, set by `AreaLightSource`.
+    area_light: Option<Arc<dyn Material>>,
+    reverse_orientation: bool,
+}
+
+struct Importer {
+    /// Directory against which relative file names are resolved.
+    directory: PathBuf,
+    /// File and line of the current directive, for warnings.
+    file: PathBuf,
+    line: usize,
+    warnings: Vec<String>,
+    state: State,
+    stack: Vec<State>,
+    /// Mirrors pbrt's world space for grayt's right-handed camera.
+    render_from_world: DAffine3,
+    /// The camera and its field of view along the shorter image side.
+    camera: Option<(CameraDescriptor, f64)>,
+    settings: RenderSettings,
+    coordinate_systems: HashMap<String, DAffine3>,
+    materials: HashMap<String, Option<Arc<dyn Material>>>,
+    textures: HashMap<String, Arc<dyn Texture>>,
+    texture_manager: TextureManager,
+    objects: HashMap<String, Arc<BvhNode>>,
+    /// Name and shapes of the object being defined by `ObjectBegin`.
+    object: Option<(String, Vec<Arc<dyn Hittable>>)>,
+    shapes: Vec<Arc<dyn Hittable>>,
+    lights: Vec<Box<dyn Light>>,
+    background: DVec3,
+}
+
+impl Importer {
+    fn new(directory: &Path) -> Self {
+        Self {
+            directory: directory.to_owned(),
+            file: PathBuf::new(),
+            line: 0,
+            warnings: Vec::new(),
+            state: State {
+                transform: DAffine3::IDENTITY,
+                material: Some(Arc::new(Lambertian {
+                    albedo: Solid {
+                        color: DVec3::splat(0.5),
+                    },
+                })),
+                area_light: None,
+                reverse_orientation: false,
+            },
+            stack: Vec::new(),
+            render_from_world: DAffine3::IDENTITY,
+            camera: None,
+            // pbrt's defaults.
+            settings: RenderSettings {
+                width: 1280,
+                height: 720,
+                samples_per_pixel: 16,
+                max_depth: 5,
+            },
+            coordinate_systems: HashMap::new(),
+            materials: HashMap::new(),
+            textures: HashMap::new(),
+            texture_manager: TextureManager::default(),
+            objects: HashMap::new(),
+            object: None,
+            shapes: Vec::new(),
+            lights: Vec::new(),
+            background: DVec3::ZERO,
+        }
+    }
+
+    fn warn(&mut self, message: String) {
+        self.warnings
+            .push(format!("{}:{}: {message}", self.file.display(), self.line));
+    }
+
+    /// Applies `transform` before the current transformation.
+    fn concat(&mut self, transform: DAffine3) {
+        self.state.transform = self.state.transform * transform;
+    }
+
+    fn include(&mut self, path: &Path) -> anyhow::Result<()> {
+        let text = fs::read_to_string(path)
+            .with_context(|| format!("failed to read {}", path.display()))?;
+        self.read(&text, path)
+    }
+
+    /// Runs the directives in `text`, the contents of `file`.
+    fn read(&mut self, text: &str, file: &Path) -> anyhow::Result<()> {
+        let mut tokens = Tokens {
+            tokens: tokenize(text)?,
+            position: 0,
+        };
+        let parent = std::mem::replace(&mut self.file, file.to_owned());
+        let parent_line = self.line;
+        while let Some((token, line)) = tokens.next() {
+            let Token::Word(directive) = token else {
+                bail!("expected a directive on line {line}, found {token:?}");
+            };
+            self.line = line;
+            self.directive(&directive, &mut tokens)
+                .with_context(|| format!("in {directive} on line {line}"))?;
+        }
+        self.file = parent;
+        self.line = parent_line;
+        Ok(())
+    }
+
+    fn directive(&mut self, directive: &str, tokens: &mut Tokens) -> anyhow::Result<()> {
+        match directive {
+            "Identity" => self.state.transform = DAffine3::IDENTITY,
+            "Translate" => {
+                let [x, y, z] = tokens.numbers()?;
+                self.concat(DAffine3::from_translation(DVec3::new(x, y, z)));
+            }
+            "Scale" => {
+                let [x, y, z] = tokens.numbers()?;
+                self.concat(DAffine3::from_scale(DVec3::new(x, y, z)));
+            }
+            "Rotate" => {
+                let [angle, x, y, z] = tokens.numbers()?;
+                let axis = DVec3::new(x, y, z).normalize();
+                self.concat(DAffine3::from_axis_angle(axis, angle.to_radians()));
+            }
+            "LookAt" => {
+                let [ex, ey, ez, lx, ly, lz, ux, uy, uz] = tokens.numbers()?;
+                let eye = DVec3::new(ex, ey, ez);
+                let direction = (DVec3::new(lx, ly, lz) - eye).normalize();
+                let right = DVec3::new(ux, uy, uz)
+                    .normalize()
+                    .cross(direction)
+                    .normalize();
+                let up = direction.cross(right);
+                let world_from_camera = DAffine3::from_cols(right, up, direction, eye);
+                self.concat(world_from_camera.inverse());
+            }
+            "Transform" => {
+                let matrix = DMat4::from_cols_array(&tokens.numbers()?);
+                self.state.transform = DAffine3::from_mat4(matrix);
+            }
+            "ConcatTransform" => {
+                let matrix = DMat4::from_cols_array(&tokens.numbers()?);
+                self.concat(DAffine3::from_mat4(matrix));
+            }
+            "CoordinateSystem" => {
+                let name = tokens.string()?;
+                self.coordinate_systems.insert(name, self.state.transform);
+            }
+            "CoordSysTransform" => {
+                let name = tokens.string()?;
+                match self.coordinate_systems.get(&name) {
+                    Some(&transform) => self.state.transform = transform,
+                    None => self.warn(format!("unknown coordinate system {name}")),
+                }
+            }
+            "AttributeBegin" | "TransformBegin" => self.stack.push(self.state.clone()),
+            "AttributeEnd" | "TransformEnd" => match self.stack.pop() {
+                Some(state) if directive == "AttributeEnd" => self.state = state,
+                Some(state) => self.state.transform = state.transform,
+                None => self.warn(format!("unmatched {directive}")),
+            },
+            "ReverseOrientation" => {
+                self.state.reverse_orientation = !self.state.reverse_orientation;
+            }
+
+            "Camera" => {
+                let ty = tokens.string()?;
+                let params = tokens.params()?;
+                self.camera(&ty, &params);
+            }
+            "Film" => {
+                tokens.string()?;
+                let params = tokens.params()?;
+                self.settings.width = params.positive("xresolution", 1280.0)?;
+                self.settings.height = params.positive("yresolution", 720.0)?;
+            }
+            "Sampler" => {
+                tokens.string()?;
+                let params = tokens.params()?;
+                self.settings.samples_per_pixel = params.positive("pixelsamples", 16.0)?;
+            }
+            "Integrator" => {
+                let ty = tokens.string()?;
+                let params = tokens.params()?;
+                if !matches!(ty.as_str(), "path" | "volpath") {
+                    self.warn(format!("unsupported integrator {ty}, using a path tracer"));
+                }
+                self.settings.max_depth = params.float("maxdepth", 5.0) as u32;
+            }
+            "WorldBegin" => {
+                if self.camera.is_none() {
+                    self.camera("perspective", &Params::default());
+                }
+                self.state.transform = DAffine3::IDENTITY;
+                self.coordinate_systems
+                    .insert("world".to_owned(), DAffine3::IDENTITY);
+            }
+            "WorldEnd" => {}
+
+            "Material" => {
+                let ty = tokens.string()?;
+                let params = tokens.params()?;
+                self.state.material = self.material(&ty, &params);
+            }
+            "MakeNamedMaterial" => {
+                let name = tokens.string()?;
+                let params = tokens.params()?;
+                let ty = params.string("type").unwrap_or_default().to_owned();
+                let material = self.material(&ty, &params);
+                self.materials.insert(name, material);
+            }
+            "NamedMaterial" => {
+                let name = tokens.string()?;
+                match self.materials.get(&name) {
+                    Some(material) => self.state.material = material.clone(),
+                    None => self.warn(format!("unknown material {name}")),
+                }
+            }
+            "Texture" => {
+                let name = tokens.string()?;
+                let ty = tokens.string()?;
+                let class = tokens.string()?;
+                let params = tokens.params()?;
+                self.texture(name, ty == "float", &class, &params)?;
+            }
+
+            "Shape" => {
+                let ty = tokens.string()?;
+                let params = tokens.params()?;
+                self.shape(&ty, &params)?;
+            }
+            "AreaLightSource" => {
+                let ty = tokens.string()?;
+                let params = tokens.params()?;
+                self.area_light(&ty, &params);
+            }
+            "LightSource" => {
+                let ty = tokens.string()?;
+                let params = tokens.params()?;
+                self.light(&ty, &params);
+            }
+
+            "ObjectBegin" => {
+                let name = tokens.string()?;
+                self.stack.push(self.state.clone());
+                if self.object.is_some() {
+                    self.warn("nested ObjectBegin".to_owned());
+                }
+                self.object = Some((name, Vec::new()));
+            }
+            "ObjectEnd" => {
+                if let Some(state) = self.stack.pop() {
+                    self.state = state;
+                }
+                match self.object.take() {
+                    Some((name, mut shapes)) if !shapes.is_empty() => {
+                        let object = BvhNode::new(&mut shapes, 0.0, 1.0);
+                        self.objects.insert(name, Arc::new(object));
+                    }
+                    Some(_) => {}
+                    None => self.warn("unmatched ObjectEnd".to_owned()),
+                }
+            }
+            "ObjectInstance" => {
+                let name = tokens.string()?;
+                match self.objects.get(&name) {
+                    Some(object) => {
+                        // Shapes of the object are already in render space.
+                        let matrix = self.render_from_world
+                            * self.state.transform
+                            * self.render_from_world.inverse();
+                        let instance = Transform::new(matrix, Arc::clone(object));
+                        self.add(instance, false);
+                    }
+                    None => self.warn(format!("unknown object {name}")),
+                }
+            }
+
+            "Include" | "Import" => {
+                let path = self.directory.join(tokens.string()?);
+                self.include(&path)
+                    .with_context(|| format!("failed to include {}", path.display()))?;
+            }
+            "ActiveTransform" => {
+                tokens.expect("a transform time")?;
+                self.warn("unsupported directive ActiveTransform, motion is ignored".to_owned());
+            }
+            _ => {
+                tokens.skip_arguments();
+                self.warn(format!("unsupported directive {directive}"));
+            }
+        }
+        Ok(())
+    }
+
+    fn camera(&mut self, ty: &str, params: &Params) {
+        if ty != "perspective" {
+            self.warn(format!("unsupported camera {ty}, using a perspective one"));
+        }
+        if params.get("screenwindow").is_some() {
+            self.warn("unsupported camera parameter screenwindow".to_owned());
+        }
+        let world_from_camera = self.state.transform.inverse();
+        self.coordinate_systems
+            .insert("camera".to_owned(), world_from_camera);
+
+        let origin = world_from_camera.translation;
+        let forward = world_from_camera.transform_vector3(DVec3::Z);
+        let up = world_from_camera.transform_vector3(DVec3::Y);
+        let right = world_from_camera.transform_vector3(DVec3::X);
+        // The image is mirrored if pbrt's right isn't ours, unless the world
+        // is mirrored too, across the plane of the camera's up and forward
+        // directions.
+        self.render_from_world = if right.dot(forward.cross(up)) < 0.0 {
+            let n = right.normalize();
+            let reflection = DMat3::IDENTITY - 2.0 * DMat3::from_cols(n * n.x, n * n.y, n * n.z);
+            DAffine3::from_mat3_translation(reflection, 2.0 * n.dot(origin) * n)
+        } else {
+            DAffine3::IDENTITY
+        };
+
+        let lens_radius = params.float("lensradius", 0.0);
+        let camera_desc = CameraDescriptor {
+            origin,
+            look_at: origin + forward,
+            vup: up,
+            aperture: 2.0 * lens_radius,
+            focus_distance: (lens_radius > 0.0).then(|| params.float("focaldistance", 1.0e6)),
+            ..Default::default()
+        };
+        self.camera = Some((camera_desc, params.float("fov", 90.0)));
+    }
+
+    /// The color of parameter `name`, warning about unsupported spectra.
+    fn spectrum(&mut self, params: &Params, name: &str, default: DVec3) -> DVec3 {
+        let Some(param) = params.get(name) else {
+            return default;
+        };
+        match (param.ty.as_str(), &param.value) {
+            ("rgb" | "color", Value::Numbers(numbers)) if numbers.len() == 3 => {
+                DVec3::from_slice(numbers)
+            }
+            ("float", Value::Numbers(numbers)) if numbers.len() == 1 => DVec3::splat(numbers[0]),
+            (ty, _) => {
+                self.warn(format!(
+                    "unsupported {ty} parameter {name}, using {default}"
+                ));
+                default
+            }
+        }
+    }
+
+    /// The value of float parameter `name`, warning about textures and other
+    /// unsupported types.
+    fn float(&mut self, params: &Params, name: &str, default: f64) -> f64 {
+        let Some(param) = params.get(name) else {
+            return default;
+        };
+        match (param.ty.as_str(), &param.value) {
+            ("float", Value::Numbers(numbers)) if numbers.len() == 1 => numbers[0],
+            (ty, _) => {
+                self.warn(format!(
+                    "unsupported {ty} parameter {name}, using {default}"
+                ));
+                default
+            }
+        }
+    }
+
+    /// Converts pbrt's roughness parameters to perceptual roughness.
+    fn roughness(&mut self, params: &Params) -> f64 {
+        let roughness = self.float(params, "roughness", 0.0);
+        let u = self.float(params, "uroughness", roughness);
+        let v = self.float(params, "vroughness", roughness);
+        if u != v {
+            self.warn("unsupported anisotropic roughness, using the average".to_owned());
+        }
+        let roughness = 0.5 * (u + v);
+        let alpha = if params.bool("remaproughness", true) {
+            roughness.sqrt()
+        } else {
+            roughness
+        };
+        alpha.sqrt()
+    }
+
+    /// Like [`Importer::spectrum`], but also allowing named textures.
+    fn color(&mut self, params: &Params, name: &str, default: DVec3) -> Arc<dyn Texture> {
+        match params
+            .get(name)
+            .map(|param| (param.ty.as_str(), &param.value))
+        {
+            Some(("texture", Value::Strings(names))) => match self.textures.get(&names[0]) {
+                Some(texture) => texture.clone(),
+                None => {
+                    self.warn(format!("unknown texture {}", names[0]));
+                    solid(default)
+                }
+            },
+            _ => solid(self.spectrum(params, name, default)),
+        }
+    }
+
+    /// `None` for `interface` materials.
+    fn material(&mut self, ty: &str, params: &Params) -> Option<Arc<dyn Material>> {
+        let roughness = self.roughness(params);
+        Some(match ty {
+            "diffuse" => Arc::new(Lambertian {
+                albedo: self.color(params, "reflectance", DVec3::splat(0.5)),
+            }),
+            "coateddiffuse" => {
+                self.warn(
+                    "approximating coateddiffuse with a principled material, \
+                     ignoring its interface, albedo and thickness"
+                        .to_owned(),
+                );
+                Arc::new(Principled {
+                    base_color: self.color(params, "reflectance", DVec3::splat(0.5)),
+                    roughness: solid(DVec3::splat(roughness)),
+                    ..Default::default()
+                })
+            }
+            "conductor" => self.conductor(params, roughness),
+            "dielectric" | "thindielectric" => Arc::new(Dielectric {
+                ir: self.index_of_refraction(params),
+                roughness,
+                thin: ty == "thindielectric",
+                ..Default::default()
+            }),
+            "interface" => return None,
+            _ => {
+                self.warn(format!("unsupported material {ty}, using a diffuse one"));
+                Arc::new(Lambertian {
+                    albedo: Solid {
+                        color: DVec3::splat(0.5),
+                    },
+                })
+            }
+        })
+    }
+
+    fn conductor(&mut self, params: &Params, roughness: f64) -> Arc<dyn Material> {
+        if params.get("reflectance").is_some() {
+            return Arc::new(Principled {
+                base_color: self.color(params, "reflectance", DVec3::ONE),
+                metallic: solid(DVec3::ONE),
+                roughness: solid(DVec3::splat(roughness)),
+                ..Default::default()
+            });
+        }
+        if let (Some(eta), Some(k)) = (params.numbers("eta"), params.numbers("k")) {
+            if eta.len() == 3 && k.len() == 3 {
+                return Arc::new(Conductor {
+                    eta: DVec3::from_slice(eta),
+                    k: DVec3::from_slice(k),
+                    roughness,
+                });
+            }
+        }
+        Arc::new(match params.string("eta") {
+            Some("metal-Au-eta") => Conductor::gold(roughness),
+            Some("metal-Ag-eta") => Conductor::silver(roughness),
+            Some("metal-Al-eta") => Conductor::aluminium(roughness),
+            Some("metal-Cu-eta") => Conductor::copper(roughness),
+            Some(name) => {
+                self.warn(format!("unsupported spectrum {name}, using copper"));
+                Conductor::copper(roughness)
+            }
+            None => {
+                let (eta, k) = (params.get("eta"), params.get("k"));
+                if let Some(param) = [eta, k].into_iter().flatten().find(|p| p.ty != "rgb") {
+                    let message = format!(
+                        "unsupported {} parameter {}, using copper",
+                        param.ty, param.name
+                    );
+                    self.warn(message);
+                } else if eta.is_some() || k.is_some() {
+                    self.warn("conductor needs both rgb eta and k, using copper".to_owned());
+                }
+                Conductor::copper(roughness)
+            }
+        })
+    }
+
+    fn index_of_refraction(&mut self, params: &Params) -> f64 {
+        match params.string("eta") {
+            Some("glass-BK7") => 1.5168,
+            Some("glass-BAF10") => 1.67,
+            Some("glass-FK51A") => 1.4866,
+            Some("glass-LASF9") => 1.8503,
+            Some(name) => {
+                self.warn(format!("unsupported spectrum {name}, using 1.5"));
+                1.5
+            }
+            None => self.float(params, "eta", 1.5),
+        }
+    }
+
+    fn texture(
+        &mut self,
+        name: String,
+        is_float: bool,
+        class: &str,
+        params: &Params,
+    ) -> anyhow::Result<()> {
+        let texture: Arc<dyn Texture> = match class {
+            "constant" => self.color(params, "value", DVec3::ONE),
+            "imagemap" => {
+                let filename = params.string("filename").context("no filename")?;
+                let color_space = match params.string("encoding") {
+                    Some("linear") => ColorSpace::Linear,
+                    Some("sRGB") => ColorSpace::Srgb,
+                    None if is_float => ColorSpace::Linear,
+                    None => ColorSpace::Srgb,
+                    Some(encoding) => {
+                        self.warn(format!("unsupported encoding {encoding}, using sRGB"));
+                        ColorSpace::Srgb
+                    }
+                };
+                let wrap = match params.string("wrap") {
+                    Some("clamp") => Wrap::Clamp,
+                    Some("repeat") | None => Wrap::Repeat,
+                    Some(wrap) => {
+                        self.warn(format!("unsupported wrap mode {wrap}, using repeat"));
+                        Wrap::Repeat
+                    }
+                };
+                if params.string("mapping").unwrap_or("uv") != "uv" {
+                    self.warn("unsupported texture mapping, using uv".to_owned());
+                }
+                let path = self.directory.join(filename);
+                let mipmap = self.texture_manager.load(path, color_space)?;
+                // pbrt's t runs up the image, and uvs are flipped on import.
+                let scale = DVec2::new(params.float("uscale", 1.0), params.float("vscale", 1.0));
+                let delta = DVec2::new(params.float("udelta", 0.0), params.float("vdelta", 0.0));
+                let image = Image {
+                    wrap,
+                    uv_scale: scale,
+                    uv_offset: DVec2::new(delta.x, 1.0 - scale.y - delta.y),
+                    ..Image::new(mipmap)
+                };
+                Arc::new(Multiply {
+                    a: Solid {
+                        color: DVec3::splat(params.float("scale", 1.0)),
+                    },
+                    b: image,
+                })
+            }
+            _ => {
+                self.warn(format!("unsupported texture {class}"));
+                return Ok(());
+            }
+        };
+        self.textures.insert(name, texture);
+        Ok(())
+    }
+
+    fn area_light(&mut self, ty: &str, params: &Params) {
+        if ty != "diffuse" {
+            self.warn(format!("unsupported area light {ty}"));
+            return;
+        }
+        for name in ["filename", "power"] {
+            if params.get(name).is_some() {
+                self.warn(format!("unsupported area light parameter {name}"));
+            }
+        }
+        let radiance = self.spectrum(params, "L", DVec3::ONE);
+        self.state.area_light = Some(Arc::new(DiffuseLight {
+            emit: Solid { color: radiance },
+            intensity: params.float("scale", 1.0),
+            two_sided: params.bool("twosided", false),
+        }));
+    }
+
+    fn light(&mut self, ty: &str, params: &Params) {
+        for name in ["power", "illuminance"] {
+            if params.get(name).is_some() {
+                self.warn(format!("unsupported light parameter {name}"));
+            }
+        }
+        let scale = params.float("scale", 1.0);
+        let transform = self.render_from_world * self.state.transform;
+        let from = params.point("from", DVec3::ZERO);
+        let to = params.point("to", DVec3::Z);
+        let light: Box<dyn Light> = match ty {
+            "point" => Box::new(Point {
+                position: transform.transform_point3(from),
+                intensity: scale * self.spectrum(params, "I", DVec3::ONE),
+            }),
+            "spot" => {
+                let cone_angle = params.float("coneangle", 30.0);
+                let cone_delta = params.float("conedelta", 5.0);
+                Box::new(Spot {
+                    position: transform.transform_point3(from),
+                    direction: transform.transform_vector3(to - from),
+                    intensity: scale * self.spectrum(params, "I", DVec3::ONE),
+                    cone_angle: cone_angle.to_radians(),
+                    falloff_start: (cone_angle - cone_delta).to_radians(),
+                })
+            }
+            "distant" => Box::new(Directional {
+                direction: transform.transform_vector3(from - to),
+                irradiance: scale * self.spectrum(params, "L", DVec3::ONE),
+            }),
+            "infinite" => {
+                if params.get("filename").is_some() {
+                    self.warn("unsupported environment map, using uniform radiance".to_owned());
+                }
+                let radiance = self.spectrum(params, "L", DVec3::ONE);
+                self.background += scale * radiance;
+                return;
+            }
+            _ => {
+                self.warn(format!("unsupported light {ty}"));
+                return;
+            }
+        };
+        self.lights.push(light);
+    }
+
+    fn shape(&mut self, ty: &str, params: &Params) -> anyhow::Result<()> {
+        let Some(mut material) = self.state.material.clone() else {
+            return Ok(());
+        };
+        let mut emissive = false;
+        if let Some(emitter) = self.state.area_light.clone() {
+            if self.object.is_some() {
+                self.warn("unsupported area light in object definition".to_owned());
+            } else {
+                material = emitter;
+                emissive = true;
+            }
+        }
+        let transform = self.render_from_world * self.state.transform;
+
+        match ty {
+            "sphere" => {
+                if ["zmin", "zmax", "phimax"]
+                    .iter()
+                    .any(|name| params.get(name).is_some())
+                {
+                    self.warn("unsupported partial sphere, using a full one".to_owned());
+                }
+                let sphere = Sphere {
+                    center: DVec3::ZERO,
+                    radius: params.float("radius", 1.0),
+                    material,
+                };
+                let sphere = Transform::new(transform, sphere);
+                if self.state.reverse_orientation {
+                    self.add(FlipFace { inner: sphere }, emissive);
+                } else {
+                    self.add(sphere, emissive);
+                }
+            }
+            "trianglemesh" => {
+                let mesh = triangle_mesh(params)?;
+                self.add_mesh(mesh, transform, material, emissive);
+            }
+            "plymesh" => {
+                if params.get("displacement").is_some() {
+                    self.warn("unsupported displacement".to_owned());
+                }
+                let mesh = ply_mesh(&self.directory, params)?;
+                self.add_mesh(mesh, transform, material, emissive);
+            }
+            _ => self.warn(format!("unsupported shape {ty}")),
+        }
+        Ok(())
+    }
+
+    /// Adds `shape` to the world or the object being defined, and as an area
+    /// light if `emissive`.
+    fn add<H: Hittable + 'static>(&mut self, shape: H, emissive: bool) {
+        let shape = Arc::new(shape);
+        if emissive {
+            self.lights.push(Box::new(Area {
+                shape: Arc::clone(&shape),
+            }));
+        }
+        match &mut self.object {
+            Some((_, shapes)) => shapes.push(shape),
+            None => self.shapes.push(shape),
+        }
+    }
+
+    fn add_mesh(
+        &mut self,
+        mesh: Mesh,
+        transform: DAffine3,
+        material: Arc<dyn Material>,
+        emissive: bool,
+    ) {
+        // Faces with fewer than three vertices don't make any triangles.
+        let mut mesh = mesh.triangulate();
+        if mesh.faces.is_empty() {
+            self.warn("skipped mesh without triangles".to_owned());
+            return;
+        }
+        let normal_matrix = transform.matrix3.inverse().transpose();
+        for position in &mut mesh.positions {
+            *position = transform.transform_point3(*position);
+        }
+        for normal in &mut mesh.normals {
+            *normal = (normal_matrix * *normal).normalize_or_zero();
+        }
+        // pbrt's geometric normals follow the shading normals where there are
+        // any, and are otherwise flipped when the orientation is reversed or
+        // the transform swaps handedness.
+        if mesh.normals.len() == mesh.positions.len() {
+            for face in &mut mesh.faces {
+                let [a, b, c] = [face[0], face[1], face[2]].map(|i| mesh.positions[i]);
+                let normal: DVec3 = face.iter().map(|&i| &mesh.normals[i]).sum();
+                if (b - a).cross(c - a).dot(normal) < 0.0 {
+                    face.reverse();
+                }
+            }
+        } else if self.state.reverse_orientation != (transform.matrix3.determinant() < 0.0) {
+            for face in &mut mesh.faces {
+                face.reverse();
+            }
+        }
+
+        if emissive {
+            for triangle in mesh.triangles(material) {
+                self.add(triangle, true);
+            }
+        } else {
+            self.add(mesh.to_bvh(material), false);
+        }
+    }
+
+    fn finish(mut self) -> anyhow::Result<PbrtScene> {
+        if self.camera.is_none() {
+            self.camera("perspective", &Params::default());
+        }
+        let (camera_desc, fov) = self.camera.take().context("no camera")?;
+        let aspect_ratio = self.settings.aspect_ratio();
+        // pbrt's field of view spans the shorter side of the image.
+        let vfov = if aspect_ratio < 1.0 {
+            let half_width = (0.5 * fov.to_radians()).tan();
+            2.0 * (half_width / aspect_ratio).atan().to_degrees()
+        } else {
+            fov
+        };
+
+        let mut world = World::new();
+        if !self.shapes.is_empty() {
+            world.add(BvhNode::new(&mut self.shapes, 0.0, 1.0));
+        }
+        Ok(PbrtScene {
+            scene: Scene {
+                world,
+                camera: Camera::new(&CameraDescriptor {
+                    vfov,
+                    aspect_ratio,
+                    ..camera_desc
+                }),
+                background: Box::new(self.background),
+                lights: self.lights,
+            },
+            settings: self.settings,
+            warnings: self.warnings,
+        })
+    }
+}
+
+/// Reads the `P`, `indices`, `N` and `uv` parameters of a `trianglemesh`.
+fn triangle_mesh(params: &Params) -> anyhow::Result<Mesh> {
+    let positions = params.numbers("P").context("no P parameter")?;
+    if !positions.len().is_multiple_of(3) {
+        bail!(
+            "expected 3 values per point in P, found {}",
+            positions.len()
+        );
+    }
+    let positions: Vec<DVec3> = positions.chunks_exact(3).map(DVec3::from_slice).collect();
+    let indices: Vec<usize> = match params.numbers("indices") {
+        Some(indices) => indices
+            .iter()
+            .map(|&index| {
+                if index < 0.0 || index.fract() != 0.0 || index as usize >= positions.len() {
+                    bail!("vertex index {index} out of range");
+                }
+                Ok(index as usize)
+            })
+            .collect::<anyhow::Result<_>>()?,
+        None if positions.len() == 3 => vec![0, 1, 2],
+        None => bail!("no indices parameter"),
+    };
+    if !indices.len().is_multiple_of(3) {
+        bail!("expected 3 indices per triangle, found {}", indices.len());
+    }
+    let mut mesh = Mesh::new(
+        positions,
+        indices.chunks_exact(3).map(<[_]>::to_vec).collect(),
+    );
+    let vertex_count = mesh.positions.len();
+    if let Some(normals) = params.numbers("N") {
+        if normals.len() != 3 * vertex_count {
+            bail!(
+                "expected {} values in N for {vertex_count} points, found {}",
+                3 * vertex_count,
+                normals.len()
+            );
+        }
+        mesh.normals = normals.chunks_exact(3).map(DVec3::from_slice).collect();
+    }
+    // Flipped like those of [`Mesh::from_ply`].
+    if let Some(uvs) = params.numbers("uv").or_else(|| params.numbers("st")) {
+        if uvs.len() != 2 * vertex_count {
+            bail!(
+                "expected {} values in uv for {vertex_count} points, found {}",
+                2 * vertex_count,
+                uvs.len()
+            );
+        }
+        mesh.uvs = uvs
+            .chunks_exact(2)
+            .map(|uv| DVec2::new(uv[0], 1.0 - uv[1]))
+            .collect();
+    }
+    Ok(mesh)
+}
+
+/// Reads the file of a `plymesh`. Like pbrt, meshes without normals are
+/// shaded flat.
+fn ply_mesh(directory: &Path, params: &Params) -> anyhow::Result<Mesh> {
+    let filename = params.string("filename").context("no filename")?;
+    Mesh::from_ply(directory.join(filename), None)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tokens(text: &str) -> Tokens {
+        Tokens {
+            tokens: tokenize(text).unwrap(),
+            position: 0,
+        }
+    }
+
+    #[test]
+    fn tokenizes_strings_and_comments() {
+        let text = "Shape \"a \\\"quoted\\\" # name\" # comment [ 1\n[ 2 ]";
+        let tokens: Vec<_> = tokenize(text).unwrap();
+        assert_eq!(
+            tokens,
+            [
+                (Token::Word("Shape".to_owned()), 1),
+                (Token::Quoted("a \"quoted\" # name".to_owned()), 1),
+                (Token::Open, 2),
+                (Token::Word("2".to_owned()), 2),
+                (Token::Close, 2),
+            ]
+        );
+        assert!(tokenize("Shape \"open\nstring\"").is_err());
+    }
+
+    #[test]
+    fn reads_bracketed_and_bare_values() {
+        let mut tokens = tokens(
+            "\"float radius\" 2 \"rgb L\" [1 0.5 0] \"string filename\" \"a.ply\" \
+             \"string names\" [\"a\" \"b\"] Shape",
+        );
+        let params = tokens.params().unwrap();
+        assert_eq!(params.float("radius", 1.0), 2.0);
+        assert_eq!(params.numbers("L").unwrap(), &[1.0, 0.5, 0.0]);
+        assert_eq!(params.string("filename"), Some("a.ply"));
+        assert!(matches!(
+            &params.get("names").unwrap().value,
+            Value::Strings(names) if names == &["a", "b"]
+        ));
+        assert_eq!(tokens.peek(), Some(&Token::Word("Shape".to_owned())));
+    }
+
+    #[test]
+    fn reads_quoted_and_unquoted_bools() {
+        let params = tokens("\"bool a\" true \"bool b\" \"false\" \"bool c\" [\"true\"]")
+            .params()
+            .unwrap();
+        assert!(params.bool("a", false));
+        assert!(!params.bool("b", true));
+        assert!(params.bool("c", false));
+        assert!(params.bool("missing", true));
+        assert!(tokens("\"bool a\" yes").params().is_err());
+    }
+
+    #[test]
+    fn rejects_mixed_values() {
+        assert!(tokens("\"float a\" [1 \"b\"]").params().is_err());
+        assert!(tokens("\"float a\" [1 [2]]").params().is_err());
+        assert!(tokens("\"radius\" 1").params().is_err());
+    }
+
+    #[test]
+    fn skips_arguments_of_unknown_directives() {
+        let mut tokens = tokens(
+            "MakeNamedMedium \"fog\" \"string type\" \"homogeneous\" \"rgb sigma_a\" [1 1 1]\n\
+             Shape \"sphere\"",
+        );
+        tokens.next();
+        tokens.skip_arguments();
+        assert_eq!(tokens.next(), Some((Token::Word("Shape".to_owned()), 2)));
+        assert_eq!(tokens.string().unwrap(), "sphere");
+        assert_eq!(tokens.peek(), None);
+    }
+
+    #[test]
+    fn keeps_ply_meshes_without_normals_flat() {
+        let directory = std::env::temp_dir();
+        let filename = format!("grayt-flat-{}.ply", std::process::id());
+        let ply = "ply\n\
+            format ascii 1.0\n\
+            element vertex 4\n\
+            property float x\n\
+            property float y\n\
+            property float z\n\
+            element face 2\n\
+            property list uchar int vertex_indices\n\
+            end_header\n\
+            0 0 0\n\
+            1 0 0\n\
+            1 1 0.1\n\
+            0 1 0\n\
+            3 0 1 2\n\
+            3 0 2 3\n";
+        fs::write(directory.join(&filename), ply).unwrap();
+        let params = tokens(&format!("\"string filename\" \"{filename}\""))
+            .params()
+            .unwrap();
+        let mesh = ply_mesh(&directory, &params);
+        fs::remove_file(directory.join(&filename)).unwrap();
+        let triangles = mesh.unwrap().triangles(());
+        assert_eq!(triangles.len(), 2);
+        assert!(triangles.iter().all(|triangle| triangle.normals.is_none()));
+    }
+
+    #[test]
+    fn rejects_invalid_triangle_meshes() {
+        let mesh = |text: &str| triangle_mesh(&tokens(text).params().unwrap());
+        let points = "\"point3 P\" [0 0 0 1 0 0 0 1 0]";
+        assert!(mesh(points).is_ok());
+        assert!(mesh(&format!("{points} \"integer indices\" [0 1 -1]")).is_err());
+        assert!(mesh(&format!("{points} \"integer indices\" [0 1 1.5]")).is_err());
+        assert!(mesh(&format!("{points} \"integer indices\" [0 1 2 0]")).is_err());
+        assert!(mesh("\"point3 P\" [0 0 0 1 0 0 0 1]").is_err());
+        assert!(mesh(&format!("{points} \"normal N\" [0 0 1 0 0 1]")).is_err());
+        assert!(mesh(&format!("{points} \"point2 uv\" [0 0 1 0 0 1 1]")).is_err());
+        assert!(mesh(&format!("{points} \"point2 uv\" [0 0 1 0 0 1]")).is_ok());
+    }
+
+    fn assert_near(a: DVec3, b: DVec3) {
+        assert!(a.abs_diff_eq(b, 1.0e-6), "{a} != {b}");
+    }
+
+    /// Point where the ray through (`u`, `v`) on the image hits the world.
+    fn hit_point(scene: &Scene, u: f64, v: f64) -> Option<DVec3> {
+        let ray = scene.camera.get_ray(u, v);
+        let hit = scene.world.hit(&ray, 0.001, f64::INFINITY)?;
+        Some(hit.point)
+    }
+
+    const SPHERES: &str = "WorldBegin\n\
+        AttributeBegin\n\
+          Translate -1 0 0\n\
+          Shape \"sphere\" \"float radius\" 0.25\n\
+        AttributeEnd\n\
+        ObjectBegin \"ball\"\n\
+          Shape \"sphere\" \"float radius\" 0.25\n\
+        ObjectEnd\n\
+        AttributeBegin\n\
+          Transform [1 0 0 0  0 1 0 0  0 0 1 0  0 1 0 1]\n\
+          ObjectInstance \"ball\"\n\
+        AttributeEnd\n";
+
+    #[test]
+    fn places_camera_and_shapes_like_pbrt() {
+        let text = format!(
+            "Film \"rgb\" \"integer xresolution\" 200 \"integer yresolution\" 100\n\
+             LookAt 0 0 5  0 0 0  0 1 0\n\
+             Camera \"perspective\" \"float fov\" 40\n\
+             {SPHERES}"
+        );
+        let pbrt = parse(&text, "").unwrap();
+        assert!(pbrt.warnings.is_empty(), "{:?}", pbrt.warnings);
+        assert_eq!((pbrt.settings.width, pbrt.settings.height), (200, 100));
+        let scene = &pbrt.scene;
+        let ray = scene.camera.get_ray(0.5, 0.5);
+        assert_near(ray.origin, DVec3::new(0.0, 0.0, 5.0));
+        assert_near(ray.direction.normalize(), DVec3::NEG_Z);
+
+        // pbrt's camera is left-handed, so the sphere at x = -1 is on the
+        // right of the image, and mirrored to x = 1 for grayt's camera.
+        let half_width = 2.0 * 20.0_f64.to_radians().tan();
+        let u = 0.5 + 0.5 * 0.2 / half_width;
+        let point = hit_point(scene, u, 0.5).unwrap();
+        assert!(((point - DVec3::X).length() - 0.25).abs() < 1.0e-6);
+        assert!(hit_point(scene, 1.0 - u, 0.5).is_none());
+
+        // The instance is moved up by the last column of `Transform`.
+        let v = 0.5 + 0.5 * 0.2 / 20.0_f64.to_radians().tan();
+        let point = hit_point(scene, 0.5, v).unwrap();
+        assert!(((point - DVec3::Y).length() - 0.25).abs() < 1.0e-6);
+        assert!(hit_point(scene, 0.5, 1.0 - v).is_none());
+    }
+
+    #[test]
+    fn keeps_right_handed_cameras_unmirrored() {
+        let text = format!(
+            "Scale -1 1 1\n\
+             LookAt 0 0 5  0 0 0  0 1 0\n\
+             Camera \"perspective\" \"float fov\" 40\n\
+             {SPHERES}"
+        );
+        let scene = parse(&text, "").unwrap().scene;
+        let half_width = 1280.0 / 720.0 * 20.0_f64.to_radians().tan();
+        let u = 0.5 - 0.5 * 0.2 / half_width;
+        let point = hit_point(&scene, u, 0.5).unwrap();
+        assert!(((point - DVec3::NEG_X).length() - 0.25).abs() < 1.0e-6);
+        assert!(hit_point(&scene, 1.0 - u, 0.5).is_none());
+    }
+
+    #[test]
+    fn spans_fov_across_the_shorter_side() {
+        let text = "Film \"rgb\" \"integer xresolution\" 100 \"integer yresolution\" 200\n\
+             LookAt 0 0 0  0 0 -1  0 1 0\n\
+             Camera \"perspective\" \"float fov\" 90\n";
+        let camera = parse(text, "").unwrap().scene.camera;
+        let right = camera.get_ray(1.0, 0.5).direction.normalize();
+        assert!((right.angle_between(DVec3::NEG_Z).to_degrees() - 45.0).abs() < 1.0e-6);
+        let top = camera.get_ray(0.5, 1.0).direction.normalize();
+        assert!((top.angle_between(DVec3::NEG_Z) - 2.0_f64.atan()).abs() < 1.0e-6);
+    }
+
+    #[test]
+    fn rejects_empty_images() {
+        for (text, directive) in [
+            ("Film \"rgb\" \"integer xresolution\" 0", "Film"),
+            ("Film \"rgb\" \"integer yresolution\" -2", "Film"),
+            ("Sampler \"halton\" \"integer pixelsamples\" 0", "Sampler"),
+        ] {
+            let error = format!("{:#}", parse(text, "").err().unwrap());
+            assert!(
+                error.contains(&format!("in {directive} on line 1")),
+                "{error}"
+            );
+            assert!(error.contains("must be positive"), "{error}");
+        }
+    }
+}
@@ -1,90 +1,234 @@
-use std::iter::repeat_with;
+use glam::{DVec2, DVec3, DVec4, IVec2, IVec3, IVec4};
+use rand::{rngs::StdRng, seq::SliceRandom, SeedableRng};
 
-use glam::{DVec3, IVec3};
-use rand::{distributions::Uniform, prelude::Distribution, Rng};
+const TABLE_SIZE: usize = 256;
 
-const POINT_COUNT: usize = 256;
+/// Gradients towards the edge midpoints of a cube, with four of them repeated
+/// to fill a table of 16 (Perlin, "Improving Noise", 2002).
+const GRADIENTS_3: [DVec3; 16] = [
+    DVec3::new(1.0, 1.0, 0.0),
+    DVec3::new(-1.0, 1.0, 0.0),
+    DVec3::new(1.0, -1.0, 0.0),
+    DVec3::new(-1.0, -1.0, 0.0),
+    DVec3::new(1.0, 0.0, 1.0),
+    DVec3::new(-1.0, 0.0, 1.0),
+    DVec3::new(1.0, 0.0, -1.0),
+    DVec3::new(-1.0, 0.0, -1.0),
+    DVec3::new(0.0, 1.0, 1.0),
+    DVec3::new(0.0, -1.0, 1.0),
+    DVec3::new(0.0, 1.0, -1.0),
+    DVec3::new(0.0, -1.0, -1.0),
+    DVec3::new(1.0, 1.0, 0.0),
+    DVec3::new(-1.0, 1.0, 0.0),
+    DVec3::new(0.0, -1.0, 1.0),
+    DVec3::new(0.0, -1.0, -1.0),
+];
 
-#[derive(Debug)]
+const GRADIENTS_2: [DVec2; 8] = [
+    DVec2::new(1.0, 1.0),
+    DVec2::new(-1.0, 1.0),
+    DVec2::new(1.0, -1.0),
+    DVec2::new(-1.0, -1.0),
+    DVec2::new(1.0, 0.0),
+    DVec2::new(-1.0, 0.0),
+    DVec2::new(0.0, 1.0),
+    DVec2::new(0.0, -1.0),
+];
+
+/// Improved gradient noise in two, three and four dimensions, with values in
+/// about `-1..=1`.
+///
+/// The lattice is hashed with a single permutation table generated from a
+/// seed, so the same seed always gives the same noise.
+#[derive(Debug, Clone)]
 pub struct Perlin {
-    ranvec: Vec<DVec3>,
-    perm_x: Vec<usize>,
-    perm_y: Vec<usize>,
-    perm_z: Vec<usize>,
+    perm: Vec<u8>,
 }
 
 impl Perlin {
-    pub fn new<R: Rng>(rng: &mut R) -> Self {
-        let float_distr = Uniform::new_inclusive(-1.0, 1.0);
-        let random_vec = || {
-            DVec3::new(
-                float_distr.sample(rng),
-                float_distr.sample(rng),
-                float_distr.sample(rng),
-            )
-        };
-        let ranvec: Vec<DVec3> = repeat_with(random_vec).take(POINT_COUNT).collect();
-        let mut perm_x: Vec<usize> = (0..POINT_COUNT).collect();
-        let mut perm_y = perm_x.clone();
-        let mut perm_z = perm_x.clone();
-        shuffle(&mut perm_x, rng);
-        shuffle(&mut perm_y, rng);
-        shuffle(&mut perm_z, rng);
-
-        Self {
-            ranvec,
-            perm_x,
-            perm_y,
-            perm_z,
-        }
+    pub fn new(seed: u64) -> Self {
+        let mut perm: Vec<u8> = (0..TABLE_SIZE).map(|i| i as u8).collect();
+        perm.shuffle(&mut StdRng::seed_from_u64(seed));
+        Self { perm }
+    }
+
+    fn hash(&self, coords: &[i32]) -> usize {
+        coords.iter().fold(0, |h, &c| {
+            self.perm[(h as i32).wrapping_add(c) as usize & (TABLE_SIZE - 1)] as usize
+        })
     }
 
     pub fn noise(&self, point: DVec3) -> f64 {
-        let uvw = point.fract();
-        let uvw_smooth = uvw * uvw * (3.0 - 2.0 * uvw);
-        let (uu, vv, ww) = uvw_smooth.into();
+        self.noise_with_gradient(point).0
+    }
 
-        let ijk = point.floor().as_ivec3();
+    /// Noise along with its analytic gradient, e.g. for bump mapping.
+    pub fn noise_with_gradient(&self, point: DVec3) -> (f64, DVec3) {
+        self.noise_3(point, None)
+    }
 
-        let mut accum = 0.0;
-        for (di, ki) in (0..2).zip([1.0 - uu, uu]) {
-            for (dj, kj) in (0..2).zip([1.0 - vv, vv]) {
-                for (dk, kk) in (0..2).zip([1.0 - ww, ww]) {
-                    let dijk = IVec3::new(di, dj, dk);
-                    let weight = uvw - dijk.as_dvec3();
-                    let sample = self.int_noise(ijk + dijk);
-                    accum += ki * kj * kk * sample.dot(weight);
-                }
+    /// Noise that repeats every `period` lattice cells along each axis.
+    pub fn periodic_noise(&self, point: DVec3, period: IVec3) -> f64 {
+        self.noise_3(point, Some(period)).0
+    }
+
+    fn noise_3(&self, point: DVec3, period: Option<IVec3>) -> (f64, DVec3) {
+        let cell = point.floor();
+        let frac = point - cell;
+        let cell = cell.as_ivec3();
+        let s = frac.to_array().map(fade);
+        let ds = frac.to_array().map(fade_derivative);
+
+        let mut value = 0.0;
+        let mut gradient = DVec3::ZERO;
+        for corner in 0..8 {
+            let offset = IVec3::new(corner & 1, (corner >> 1) & 1, (corner >> 2) & 1);
+            let mut lattice = (cell + offset).to_array();
+            if let Some(period) = period {
+                wrap(&mut lattice, &period.to_array());
             }
+            let g = GRADIENTS_3[self.hash(&lattice) & 15];
+            let dot = g.dot(frac - offset.as_dvec3());
+
+            let [wx, wy, wz] = [0, 1, 2].map(|i| corner_weight(offset[i], s[i]));
+            let [dwx, dwy, dwz] = [0, 1, 2].map(|i| corner_weight_derivative(offset[i], ds[i]));
+            let weight = wx * wy * wz;
+            value += weight * dot;
+            gradient += DVec3::new(dwx * wy * wz, wx * dwy * wz, wx * wy * dwz) * dot + weight * g;
         }
-        accum
+        (value, gradient)
     }
 
-    fn int_noise(&self, idx: IVec3) -> DVec3 {
-        let idx = idx.as_uvec3() % POINT_COUNT as u32;
-        self.ranvec[self.perm_x[idx.x as usize]
-            ^ self.perm_y[idx.y as usize]
-            ^ self.perm_z[idx.z as usize]]
+    pub fn noise_2(&self, point: DVec2) -> f64 {
+        self.noise_2_impl(point, None)
     }
 
+    /// 2D noise that repeats every `period` lattice cells along each axis,
+    /// e.g. for tiling textures in uv space.
+    pub fn periodic_noise_2(&self, point: DVec2, period: IVec2) -> f64 {
+        self.noise_2_impl(point, Some(period))
+    }
+
+    fn noise_2_impl(&self, point: DVec2, period: Option<IVec2>) -> f64 {
+        let cell = point.floor();
+        let frac = point - cell;
+        let cell = cell.as_ivec2();
+        let s = frac.to_array().map(fade);
+
+        let mut value = 0.0;
+        for corner in 0..4 {
+            let offset = IVec2::new(corner & 1, (corner >> 1) & 1);
+            let mut lattice = (cell + offset).to_array();
+            if let Some(period) = period {
+                wrap(&mut lattice, &period.to_array());
+            }
+            let g = GRADIENTS_2[self.hash(&lattice) & 7];
+            let weight = corner_weight(offset.x, s[0]) * corner_weight(offset.y, s[1]);
+            value += weight * g.dot(frac - offset.as_dvec2());
+        }
+        value
+    }
+
+    /// 4D noise, e.g. 3D noise animated over time in the fourth coordinate.
+    pub fn noise_4(&self, point: DVec4) -> f64 {
+        let cell = point.floor();
+        let frac = point - cell;
+        let cell = cell.as_ivec4();
+        let s = frac.to_array().map(fade);
+
+        let mut value = 0.0;
+        for corner in 0..16 {
+            let offset = IVec4::new(
+                corner & 1,
+                (corner >> 1) & 1,
+                (corner >> 2) & 1,
+                (corner >> 3) & 1,
+            );
+            let g = gradient_4(self.hash(&(cell + offset).to_array()));
+            let weight = (0..4)
+                .map(|i| corner_weight(offset[i], s[i]))
+                .product::<f64>();
+            value += weight * g.dot(frac - offset.as_dvec4());
+        }
+        value
+    }
+
+    /// Fractal Brownian motion: a sum of `octaves` layers of noise, each
+    /// `lacunarity` times the frequency and `gain` times the amplitude of the
+    /// previous one.
+    pub fn fbm(&self, point: DVec3, octaves: usize, lacunarity: f64, gain: f64) -> f64 {
+        let mut accum = 0.0;
+        let mut point = point;
+        let mut weight = 1.0;
+
+        for _ in 0..octaves {
+            accum += weight * self.noise(point);
+            point *= lacunarity;
+            weight *= gain;
+        }
+
+        accum
+    }
+
+    /// Sum of the absolute values of `depth` octaves of noise, each with
+    /// double the frequency and half the amplitude of the previous one.
     pub fn turbulence(&self, point: DVec3, depth: usize) -> f64 {
         let mut accum = 0.0;
         let mut point = point;
         let mut weight = 1.0;
 
         for _ in 0..depth {
-            accum += weight * self.noise(point);
+            accum += weight * self.noise(point).abs();
             point *= 2.0;
             weight *= 0.5;
         }
 
-        accum.abs()
+        accum
+    }
+}
+
+fn wrap(lattice: &mut [i32], period: &[i32]) {
+    for (coord, &period) in lattice.iter_mut().zip(period) {
+        *coord = coord.rem_euclid(period.max(1));
+    }
+}
+
+/// Quintic interpolant with zero first and second derivatives at 0 and 1.
+fn fade(t: f64) -> f64 {
+    t * t * t * (t * (6.0 * t - 15.0) + 10.0)
+}
+
+fn fade_derivative(t: f64) -> f64 {
+    30.0 * t * t * (t * (t - 2.0) + 1.0)
+}
+
+fn corner_weight(offset: i32, s: f64) -> f64 {
+    if offset == 0 {
+        1.0 - s
+    } else {
+        s
+    }
+}
+
+fn corner_weight_derivative(offset: i32, ds: f64) -> f64 {
+    if offset == 0 {
+        -ds
+    } else {
+        ds
     }
 }
 
-fn shuffle<R: Rng, T>(items: &mut [T], rng: &mut R) {
-    for i in (1..POINT_COUNT).rev() {
-        let target = rng.gen_range(0..=i);
-        items.swap(i, target);
+/// One of the 32 gradients towards the edge midpoints of a 4D hypercube,
+/// which have one zero and three ±1 components.
+fn gradient_4(hash: usize) -> DVec4 {
+    let zero = (hash >> 3) & 3;
+    let mut g = [0.0; 4];
+    let mut bit = 0;
+    for (i, component) in g.iter_mut().enumerate() {
+        if i != zero {
+            *component = if (hash >> bit) & 1 == 0 { 1.0 } else { -1.0 };
+            bit += 1;
+        }
     }
+    DVec4::from(g)
 }
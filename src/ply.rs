@@ -0,0 +1,399 @@
+use std::{
+    fs::File,
+    io::{BufRead, BufReader, Read},
+    path::Path,
+};
+
+use anyhow::{bail, Context};
+
+/// Type of a value in a PLY file.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PlyType {
+    I8,
+    U8,
+    I16,
+    U16,
+    I32,
+    U32,
+    F32,
+    F64,
+}
+
+impl PlyType {
+    fn parse(name: &str) -> anyhow::Result<Self> {
+        Ok(match name {
+            "char" | "int8" => Self::I8,
+            "uchar" | "uint8" => Self::U8,
+            "short" | "int16" => Self::I16,
+            "ushort" | "uint16" => Self::U16,
+            "int" | "int32" => Self::I32,
+            "uint" | "uint32" => Self::U32,
+            "float" | "float32" => Self::F32,
+            "double" | "float64" => Self::F64,
+            _ => bail!("unknown PLY type {name}"),
+        })
+    }
+
+    fn size(self) -> usize {
+        match self {
+            Self::I8 | Self::U8 => 1,
+            Self::I16 | Self::U16 => 2,
+            Self::I32 | Self::U32 | Self::F32 => 4,
+            Self::F64 => 8,
+        }
+    }
+
+    /// Largest value of integer types, by which colors are divided to bring
+    /// them into `0..=1`.
+    fn scale(self) -> f64 {
+        match self {
+            Self::I8 => i8::MAX.into(),
+            Self::U8 => u8::MAX.into(),
+            Self::I16 => i16::MAX.into(),
+            Self::U16 => u16::MAX.into(),
+            Self::I32 => i32::MAX.into(),
+            Self::U32 => u32::MAX.into(),
+            Self::F32 | Self::F64 => 1.0,
+        }
+    }
+
+    fn decode(self, bytes: &[u8], big_endian: bool) -> f64 {
+        macro_rules! decode {
+            ($t:ty) => {{
+                let bytes = bytes.try_into().unwrap();
+                let value = if big_endian {
+                    <$t>::from_be_bytes(bytes)
+                } else {
+                    <$t>::from_le_bytes(bytes)
+                };
+                value as f64
+            }};
+        }
+        match self {
+            Self::I8 => decode!(i8),
+            Self::U8 => decode!(u8),
+            Self::I16 => decode!(i16),
+            Self::U16 => decode!(u16),
+            Self::I32 => decode!(i32),
+            Self::U32 => decode!(u32),
+            Self::F32 => decode!(f32),
+            Self::F64 => decode!(f64),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+enum Values {
+    Scalar(Vec<f64>),
+    List(Vec<Vec<f64>>),
+}
+
+#[derive(Debug, Clone)]
+struct Property {
+    name: String,
+    ty: PlyType,
+    /// Type of the length of list properties.
+    count_ty: Option<PlyType>,
+    values: Values,
+}
+
+/// A named table of a PLY file, such as `vertex` or `face`, stored column by
+/// column.
+#[derive(Debug, Clone)]
+pub struct PlyElement {
+    pub name: String,
+    pub count: usize,
+    properties: Vec<Property>,
+}
+
+impl PlyElement {
+    fn property(&self, name: &str) -> Option<&Property> {
+        self.properties.iter().find(|p| p.name == name)
+    }
+
+    /// Values of a scalar property, converted to `f64`.
+    pub fn scalar(&self, name: &str) -> Option<&[f64]> {
+        match &self.property(name)?.values {
+            Values::Scalar(values) => Some(values),
+            Values::List(_) => None,
+        }
+    }
+
+    /// Values of the first of `names` that is a scalar property.
+    pub fn any_scalar(&self, names: &[&str]) -> Option<&[f64]> {
+        names.iter().find_map(|name| self.scalar(name))
+    }
+
+    /// Values of a scalar property, with integers divided by the largest
+    /// value of their type as is usual for colors.
+    pub fn normalized(&self, name: &str) -> Option<Vec<f64>> {
+        let property = self.property(name)?;
+        let scale = property.ty.scale();
+        match &property.values {
+            Values::Scalar(values) => Some(values.iter().map(|v| v / scale).collect()),
+            Values::List(_) => None,
+        }
+    }
+
+    /// Values of a list property, such as the vertex indices of faces.
+    pub fn list(&self, name: &str) -> Option<&[Vec<f64>]> {
+        match &self.property(name)?.values {
+            Values::List(values) => Some(values),
+            Values::Scalar(_) => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Ascii,
+    BinaryLittleEndian,
+    BinaryBigEndian,
+}
+
+/// Most values reserved per property before reading them, so that the count
+/// in a corrupt header can't exhaust memory.
+const MAX_PREALLOCATED: usize = 1 << 20;
+
+/// The contents of a Polygon File Format file, in ASCII or binary encoding.
+#[derive(Debug, Clone)]
+pub struct Ply {
+    pub elements: Vec<PlyElement>,
+}
+
+impl Ply {
+    pub fn open(path: impl AsRef<Path>) -> anyhow::Result<Self> {
+        let path = path.as_ref();
+        File::open(path)
+            .map_err(anyhow::Error::from)
+            .and_then(|file| Self::read(BufReader::new(file)))
+            .with_context(|| format!("failed to load PLY file {}", path.display()))
+    }
+
+    pub fn read(mut reader: impl BufRead) -> anyhow::Result<Self> {
+        let mut line = String::new();
+        let mut read_line = |line: &mut String| -> anyhow::Result<()> {
+            line.clear();
+            if reader.read_line(line)? == 0 {
+                bail!("unexpected end of PLY header");
+            }
+            Ok(())
+        };
+
+        read_line(&mut line)?;
+        if line.trim() != "ply" {
+            bail!("not a PLY file");
+        }
+        let mut format = None;
+        let mut elements: Vec<PlyElement> = Vec::new();
+        loop {
+            read_line(&mut line)?;
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[..] {
+                ["end_header"] => break,
+                ["format", encoding, _version] => {
+                    format = Some(match encoding {
+                        "ascii" => Format::Ascii,
+                        "binary_little_endian" => Format::BinaryLittleEndian,
+                        "binary_big_endian" => Format::BinaryBigEndian,
+                        _ => bail!("unknown PLY format {encoding}"),
+                    });
+                }
+                ["element", name, count] => elements.push(PlyElement {
+                    name: name.to_owned(),
+                    count: count
+                        .parse()
+                        .with_context(|| format!("invalid count of PLY element {name}"))?,
+                    properties: Vec::new(),
+                }),
+                ["property", "list", count_ty, ty, name] => {
+                    let element = elements.last_mut().context("PLY property before element")?;
+                    element.properties.push(Property {
+                        name: name.to_owned(),
+                        ty: PlyType::parse(ty)?,
+                        count_ty: Some(PlyType::parse(count_ty)?),
+                        values: Values::List(Vec::with_capacity(
+                            element.count.min(MAX_PREALLOCATED),
+                        )),
+                    });
+                }
+                ["property", ty, name] => {
+                    let element = elements.last_mut().context("PLY property before element")?;
+                    element.properties.push(Property {
+                        name: name.to_owned(),
+                        ty: PlyType::parse(ty)?,
+                        count_ty: None,
+                        values: Values::Scalar(Vec::with_capacity(
+                            element.count.min(MAX_PREALLOCATED),
+                        )),
+                    });
+                }
+                ["comment", ..] | ["obj_info", ..] | [] => {}
+                _ => bail!("invalid PLY header line {:?}", line.trim()),
+            }
+        }
+
+        match format.context("PLY header has no format")? {
+            Format::Ascii => {
+                let mut text = String::new();
+                reader.read_to_string(&mut text)?;
+                read_ascii(&mut elements, &text)?;
+            }
+            format => read_binary(
+                &mut elements,
+                &mut reader,
+                format == Format::BinaryBigEndian,
+            )?,
+        }
+        Ok(Self { elements })
+    }
+
+    pub fn element(&self, name: &str) -> Option<&PlyElement> {
+        self.elements.iter().find(|e| e.name == name)
+    }
+}
+
+fn read_ascii(elements: &mut [PlyElement], text: &str) -> anyhow::Result<()> {
+    let mut tokens = text.split_whitespace();
+    let mut next = || -> anyhow::Result<f64> {
+        let token = tokens.next().context("unexpected end of PLY data")?;
+        token
+            .parse()
+            .with_context(|| format!("invalid PLY value {token}"))
+    };
+    for element in elements {
+        for _ in 0..element.count {
+            for property in &mut element.properties {
+                match &mut property.values {
+                    Values::Scalar(values) => values.push(next()?),
+                    Values::List(lists) => {
+                        let len = next()? as usize;
+                        lists.push((0..len).map(|_| next()).collect::<Result<_, _>>()?);
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn read_binary(
+    elements: &mut [PlyElement],
+    reader: &mut impl Read,
+    big_endian: bool,
+) -> anyhow::Result<()> {
+    let mut buffer = [0; 8];
+    let mut next = |ty: PlyType| -> anyhow::Result<f64> {
+        let bytes = &mut buffer[..ty.size()];
+        reader
+            .read_exact(bytes)
+            .context("unexpected end of PLY data")?;
+        Ok(ty.decode(bytes, big_endian))
+    };
+    for element in elements {
+        for _ in 0..element.count {
+            for property in &mut element.properties {
+                match &mut property.values {
+                    Values::Scalar(values) => values.push(next(property.ty)?),
+                    Values::List(lists) => {
+                        let len = next(property.count_ty.unwrap())? as usize;
+                        lists.push(
+                            (0..len)
+                                .map(|_| next(property.ty))
+                                .collect::<Result<_, _>>()?,
+                        );
+                    }
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reads_ascii() {
+        let text = "ply\n\
+            format ascii 1.0\n\
+            comment a single triangle\n\
+            element vertex 3\n\
+            property float x\n\
+            property float y\n\
+            property float z\n\
+            element face 1\n\
+            property list uchar int vertex_indices\n\
+            end_header\n\
+            0 0 0\n\
+            1 0 0\n\
+            0 1 0.5\n\
+            3 0 1 2\n";
+        let ply = Ply::read(text.as_bytes()).unwrap();
+        let vertex = ply.element("vertex").unwrap();
+        assert_eq!(vertex.count, 3);
+        assert_eq!(vertex.scalar("x").unwrap(), &[0.0, 1.0, 0.0]);
+        assert_eq!(vertex.scalar("z").unwrap(), &[0.0, 0.0, 0.5]);
+        assert_eq!(vertex.any_scalar(&["w", "y"]).unwrap(), &[0.0, 0.0, 1.0]);
+        let face = ply.element("face").unwrap();
+        assert_eq!(face.list("vertex_indices").unwrap(), &[vec![0.0, 1.0, 2.0]]);
+    }
+
+    #[test]
+    fn reads_big_endian_lists() {
+        let mut data = b"ply\n\
+            format binary_big_endian 1.0\n\
+            element vertex 2\n\
+            property float x\n\
+            element face 2\n\
+            property list uchar int vertex_indices\n\
+            end_header\n"
+            .to_vec();
+        for x in [1.5f32, -2.0] {
+            data.extend(x.to_be_bytes());
+        }
+        for face in [&[0i32, 1, 1][..], &[1, 0, 1, 0]] {
+            data.push(face.len() as u8);
+            for index in face {
+                data.extend(index.to_be_bytes());
+            }
+        }
+        let ply = Ply::read(&data[..]).unwrap();
+        assert_eq!(
+            ply.element("vertex").unwrap().scalar("x").unwrap(),
+            &[1.5, -2.0]
+        );
+        assert_eq!(
+            ply.element("face").unwrap().list("vertex_indices").unwrap(),
+            &[vec![0.0, 1.0, 1.0], vec![1.0, 0.0, 1.0, 0.0]]
+        );
+    }
+
+    #[test]
+    fn normalizes_integer_colors() {
+        let text = "ply\n\
+            format ascii 1.0\n\
+            element vertex 2\n\
+            property uchar red\n\
+            property float green\n\
+            end_header\n\
+            255 0.25\n\
+            51 1\n";
+        let ply = Ply::read(text.as_bytes()).unwrap();
+        let vertex = ply.element("vertex").unwrap();
+        assert_eq!(vertex.normalized("red").unwrap(), vec![1.0, 0.2]);
+        assert_eq!(vertex.normalized("green").unwrap(), vec![0.25, 1.0]);
+    }
+
+    #[test]
+    fn rejects_truncated_data() {
+        let text = "ply\n\
+            format binary_little_endian 1.0\n\
+            element vertex 99999999999\n\
+            property double x\n\
+            end_header\n";
+        let error = Ply::read(text.as_bytes()).unwrap_err();
+        assert!(error.to_string().contains("unexpected end of PLY data"));
+    }
+}
@@ -0,0 +1,176 @@
+use std::f64::consts as f64;
+
+use glam::{DMat2, DVec2, DVec3};
+
+use crate::texture::{Texture, TextureContext};
+
+/// Affine transformation of uv coordinates, applied as scale, then rotation,
+/// then offset.
+#[derive(Debug, Clone, Copy)]
+pub struct UvTransform {
+    pub scale: DVec2,
+    /// Counter-clockwise rotation in radians.
+    pub rotation: f64,
+    pub offset: DVec2,
+}
+
+impl Default for UvTransform {
+    fn default() -> Self {
+        Self {
+            scale: DVec2::ONE,
+            rotation: 0.0,
+            offset: DVec2::ZERO,
+        }
+    }
+}
+
+impl UvTransform {
+    fn matrix(&self) -> DMat2 {
+        DMat2::from_angle(self.rotation) * DMat2::from_diagonal(self.scale)
+    }
+
+    pub fn apply(&self, uv: DVec2) -> DVec2 {
+        self.matrix() * uv + self.offset
+    }
+
+    /// Transforms the extent of a footprint, keeping it axis aligned.
+    pub fn apply_footprint(&self, footprint: DVec2) -> DVec2 {
+        let m = self.matrix();
+        let abs = DMat2::from_cols(m.x_axis.abs(), m.y_axis.abs());
+        abs * footprint
+    }
+}
+
+/// How uv coordinates are derived for a [`Projected`] texture. All
+/// projections except `Surface` work on the object space point, so the
+/// layout follows the object through transforms.
+#[derive(Debug, Clone, Copy)]
+pub enum Projection {
+    /// The uv coordinates of the surface.
+    Surface,
+    /// Parallel projection onto the plane spanned by `u_axis` and `v_axis`,
+    /// where `origin + u_axis` maps to u = 1.
+    Planar {
+        origin: DVec3,
+        u_axis: DVec3,
+        v_axis: DVec3,
+    },
+    /// Angle around a vertical axis through `center` as u, and height above
+    /// `center` in units of `height` as v.
+    Cylindrical { center: DVec3, height: f64 },
+    /// Longitude and latitude around `center`, matching the mapping of
+    /// [`Sphere`](crate::hittable::Sphere).
+    Spherical { center: DVec3 },
+    /// Planar projection along the axis closest to the normal, `scale` units
+    /// of uv per unit of distance.
+    Box { scale: f64 },
+    /// Planar projections along all three axes, blended by the normal raised
+    /// to `sharpness`.
+    Triplanar { scale: f64, sharpness: f64 },
+}
+
+/// Looks up `inner` with uv coordinates computed by `projection` and then
+/// `transform`.
+#[derive(Debug, Clone)]
+pub struct Projected<T> {
+    pub inner: T,
+    pub projection: Projection,
+    pub transform: UvTransform,
+}
+
+impl<T> Projected<T> {
+    pub fn new(inner: T, projection: Projection) -> Self {
+        Self {
+            inner,
+            projection,
+            transform: UvTransform::default(),
+        }
+    }
+}
+
+impl<T: Texture> Projected<T> {
+    fn lookup(&self, ctx: &TextureContext, uv: DVec2, footprint: DVec2) -> DVec3 {
+        self.inner.value(&TextureContext {
+            uv: self.transform.apply(uv),
+            uv_footprint: self.transform.apply_footprint(footprint),
+            ..*ctx
+        })
+    }
+}
+
+/// Uv coordinates of `point` projected along the given axis, oriented so
+/// that images appear upright on the sides.
+fn axis_uv(point: DVec3, axis: usize) -> DVec2 {
+    match axis {
+        0 => DVec2::new(point.z, -point.y),
+        1 => DVec2::new(point.x, point.z),
+        _ => DVec2::new(point.x, -point.y),
+    }
+}
+
+impl<T: Texture> Texture for Projected<T> {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        let p = ctx.object_point;
+        match self.projection {
+            Projection::Surface => self.lookup(ctx, ctx.uv, ctx.uv_footprint),
+            Projection::Planar {
+                origin,
+                u_axis,
+                v_axis,
+            } => {
+                let d = p - origin;
+                let uv = DVec2::new(
+                    d.dot(u_axis) / u_axis.length_squared(),
+                    d.dot(v_axis) / v_axis.length_squared(),
+                );
+                let footprint = ctx.width / DVec2::new(u_axis.length(), v_axis.length());
+                self.lookup(ctx, uv, footprint)
+            }
+            Projection::Cylindrical { center, height } => {
+                let d = p - center;
+                let angle = (-d.z).atan2(d.x) + f64::PI;
+                let radius = DVec2::new(d.x, d.z).length().max(1.0e-8);
+                let uv = DVec2::new(angle / f64::TAU, d.y / height);
+                let footprint = ctx.width / DVec2::new(f64::TAU * radius, height.abs());
+                self.lookup(ctx, uv, footprint)
+            }
+            Projection::Spherical { center } => {
+                let d = p - center;
+                let radius = d.length().max(1.0e-8);
+                let n = d / radius;
+                let latitude = n.y.clamp(-1.0, 1.0).acos();
+                let longitude = (-n.z).atan2(n.x) + f64::PI;
+                let uv = DVec2::new(longitude / f64::TAU, latitude / f64::PI);
+                let sin_latitude = latitude.sin().max(1.0e-3);
+                let footprint =
+                    ctx.width / DVec2::new(f64::TAU * radius * sin_latitude, f64::PI * radius);
+                self.lookup(ctx, uv, footprint)
+            }
+            Projection::Box { scale } => {
+                let n = ctx.object_normal.abs();
+                let axis = if n.x >= n.y && n.x >= n.z {
+                    0
+                } else if n.y >= n.z {
+                    1
+                } else {
+                    2
+                };
+                let footprint = DVec2::splat(ctx.width * scale);
+                self.lookup(ctx, axis_uv(scale * p, axis), footprint)
+            }
+            Projection::Triplanar { scale, sharpness } => {
+                let weights = ctx.object_normal.abs().powf(sharpness);
+                let weights = weights / weights.dot(DVec3::ONE).max(1.0e-8);
+                let footprint = DVec2::splat(ctx.width * scale);
+                let mut color = DVec3::ZERO;
+                for axis in 0..3 {
+                    if weights[axis] > 0.0 {
+                        color +=
+                            weights[axis] * self.lookup(ctx, axis_uv(scale * p, axis), footprint);
+                    }
+                }
+                color
+            }
+        }
+    }
+}
@@ -0,0 +1,646 @@
+use std::f64::consts as f64;
+
+use glam::{DQuat, DVec2, DVec3, Vec3Swizzles};
+
+use crate::{
+    hittable::{compute_face_normal, Aabb, HitRecord, Hittable},
+    material::Material,
+    microfacet::Frame,
+    ray::Ray,
+};
+
+// All shapes in this module are symmetric around an axis through `center`
+// along `axis`. They are intersected in a local frame where that axis is +Z
+// and `center` is the origin, so heights like `z_min` are measured along
+// `axis` from `center`, and the sweep angle `phi` starts at an arbitrary but
+// fixed direction perpendicular to the axis.
+
+/// An intersection in the local frame of a shape.
+struct LocalHit {
+    t: f64,
+    point: DVec3,
+    outward_normal: DVec3,
+    uv: DVec2,
+    dpdu: DVec3,
+    dpdv: DVec3,
+}
+
+fn local_frame(axis: DVec3) -> Frame {
+    Frame::from_normal(axis.normalize())
+}
+
+fn to_local(frame: &Frame, center: DVec3, ray: &Ray) -> (DVec3, DVec3) {
+    (
+        frame.to_local(ray.origin - center),
+        frame.to_local(ray.direction),
+    )
+}
+
+fn to_world<'a>(
+    frame: &Frame,
+    center: DVec3,
+    ray: &Ray,
+    hit: LocalHit,
+    material: &'a dyn Material,
+) -> HitRecord<'a> {
+    let point = center + frame.to_world(hit.point);
+    let outward_normal = frame.to_world(hit.outward_normal).normalize();
+    let (normal, face) = compute_face_normal(ray, outward_normal);
+    HitRecord {
+        t: hit.t,
+        point,
+        normal,
+        object_point: point,
+        object_rotation: DQuat::IDENTITY,
+        uv: hit.uv,
+        dpdu: frame.to_world(hit.dpdu),
+        dpdv: frame.to_world(hit.dpdv),
+        face,
+        color: DVec3::ONE,
+        material,
+    }
+}
+
+/// Bounding box of the local box from `min` to `max`, in world space.
+fn world_bounds(frame: &Frame, center: DVec3, min: DVec3, max: DVec3) -> Aabb {
+    let mut minimum = DVec3::splat(f64::INFINITY);
+    let mut maximum = DVec3::splat(-f64::INFINITY);
+    for x in [min.x, max.x] {
+        for y in [min.y, max.y] {
+            for z in [min.z, max.z] {
+                let point = center + frame.to_world(DVec3::new(x, y, z));
+                minimum = minimum.min(point);
+                maximum = maximum.max(point);
+            }
+        }
+    }
+    Aabb { minimum, maximum }
+}
+
+fn phi(point: DVec3) -> f64 {
+    point.y.atan2(point.x).rem_euclid(f64::TAU)
+}
+
+/// Real roots of `a t² + b t + c`, in ascending order.
+fn solve_quadratic(a: f64, b: f64, c: f64) -> Option<(f64, f64)> {
+    if a == 0.0 {
+        if b == 0.0 {
+            return None;
+        }
+        let t = -c / b;
+        return Some((t, t));
+    }
+    let discriminant = b * b - 4.0 * a * c;
+    if discriminant < 0.0 {
+        return None;
+    }
+    let q = -0.5 * (b + b.signum() * discriminant.sqrt());
+    let (t0, t1) = if q == 0.0 { (0.0, 0.0) } else { (q / a, c / q) };
+    Some((t0.min(t1), t0.max(t1)))
+}
+
+/// Returns the closest of the surface roots of a quadric that lies within
+/// `t_min..=t_max` and is accepted by `hit_at`.
+fn closest_root(
+    roots: Option<(f64, f64)>,
+    t_min: f64,
+    t_max: f64,
+    mut hit_at: impl FnMut(f64) -> Option<LocalHit>,
+) -> Option<LocalHit> {
+    let (t0, t1) = roots?;
+    [t0, t1]
+        .into_iter()
+        .filter(|t| (t_min..=t_max).contains(t))
+        .find_map(&mut hit_at)
+}
+
+/// A disk closing off a shape at height `z`, facing +Z if `up`.
+struct Cap {
+    z: f64,
+    radius: f64,
+    up: bool,
+}
+
+fn cap_hit(
+    origin: DVec3,
+    direction: DVec3,
+    cap: Cap,
+    phi_max: f64,
+    t_min: f64,
+    t_max: f64,
+) -> Option<LocalHit> {
+    let Cap { z, radius, up } = cap;
+    if direction.z == 0.0 {
+        return None;
+    }
+    let t = (z - origin.z) / direction.z;
+    if t < t_min || t > t_max {
+        return None;
+    }
+    let point = origin + t * direction;
+    let r = point.xy().length();
+    if r > radius {
+        return None;
+    }
+    let phi = phi(point);
+    if phi > phi_max {
+        return None;
+    }
+    let radial = if r > 0.0 {
+        (point.xy() / r).extend(0.0)
+    } else {
+        DVec3::X
+    };
+    Some(LocalHit {
+        t,
+        point,
+        outward_normal: if up { DVec3::Z } else { -DVec3::Z },
+        uv: DVec2::new(phi / phi_max, r / radius),
+        dpdu: phi_max * DVec3::new(-point.y, point.x, 0.0),
+        dpdv: radius * radial,
+    })
+}
+
+fn closer(a: Option<LocalHit>, b: Option<LocalHit>) -> Option<LocalHit> {
+    match (a, b) {
+        (Some(a), Some(b)) => Some(if a.t <= b.t { a } else { b }),
+        (a, b) => a.or(b),
+    }
+}
+
+/// A cylinder around `axis` between the heights `z_min` and `z_max`, swept
+/// by `phi_max` radians. Capped cylinders are closed by disks at both ends.
+#[derive(Debug, Clone)]
+pub struct Cylinder<Mat> {
+    pub center: DVec3,
+    pub axis: DVec3,
+    pub radius: f64,
+    pub z_min: f64,
+    pub z_max: f64,
+    pub phi_max: f64,
+    pub capped: bool,
+    pub material: Mat,
+}
+
+impl<Mat: Material> Hittable for Cylinder<Mat> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        let frame = local_frame(self.axis);
+        let (o, d) = to_local(&frame, self.center, ray);
+        let roots = solve_quadratic(
+            d.x * d.x + d.y * d.y,
+            2.0 * (d.x * o.x + d.y * o.y),
+            o.x * o.x + o.y * o.y - self.radius * self.radius,
+        );
+        let height = self.z_max - self.z_min;
+        let side = closest_root(roots, t_min, t_max, |t| {
+            let point = o + t * d;
+            let phi = phi(point);
+            if point.z < self.z_min || point.z > self.z_max || phi > self.phi_max {
+                return None;
+            }
+            Some(LocalHit {
+                t,
+                point,
+                outward_normal: DVec3::new(point.x, point.y, 0.0) / self.radius,
+                uv: DVec2::new(phi / self.phi_max, (point.z - self.z_min) / height),
+                dpdu: self.phi_max * DVec3::new(-point.y, point.x, 0.0),
+                dpdv: DVec3::new(0.0, 0.0, height),
+            })
+        });
+        let mut closest = side;
+        if self.capped {
+            let radius = self.radius;
+            let caps = [
+                Cap {
+                    z: self.z_min,
+                    radius,
+                    up: false,
+                },
+                Cap {
+                    z: self.z_max,
+                    radius,
+                    up: true,
+                },
+            ];
+            for cap in caps {
+                let t_max = closest.as_ref().map_or(t_max, |hit| hit.t);
+                closest = closer(closest, cap_hit(o, d, cap, self.phi_max, t_min, t_max));
+            }
+        }
+        Some(to_world(&frame, self.center, ray, closest?, &self.material))
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<Aabb> {
+        let frame = local_frame(self.axis);
+        let r = self.radius;
+        Some(world_bounds(
+            &frame,
+            self.center,
+            DVec3::new(-r, -r, self.z_min),
+            DVec3::new(r, r, self.z_max),
+        ))
+    }
+}
+
+/// A cone with its base of radius `radius` at the center and its apex at
+/// `height` along the axis, cut off at `z_max` and swept by `phi_max`
+/// radians. Capped cones are closed at the base and the cut.
+#[derive(Debug, Clone)]
+pub struct Cone<Mat> {
+    pub center: DVec3,
+    pub axis: DVec3,
+    pub radius: f64,
+    pub height: f64,
+    pub z_max: f64,
+    pub phi_max: f64,
+    pub capped: bool,
+    pub material: Mat,
+}
+
+impl<Mat: Material> Hittable for Cone<Mat> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        let frame = local_frame(self.axis);
+        let (o, d) = to_local(&frame, self.center, ray);
+        let h = self.height;
+        let k = (self.radius / h).powi(2);
+        let z_max = self.z_max.min(h);
+        let roots = solve_quadratic(
+            d.x * d.x + d.y * d.y - k * d.z * d.z,
+            2.0 * (d.x * o.x + d.y * o.y - k * d.z * (o.z - h)),
+            o.x * o.x + o.y * o.y - k * (o.z - h) * (o.z - h),
+        );
+        let side = closest_root(roots, t_min, t_max, |t| {
+            let point = o + t * d;
+            let phi = phi(point);
+            if point.z < 0.0 || point.z > z_max || phi > self.phi_max {
+                return None;
+            }
+            let v = point.z / h;
+            Some(LocalHit {
+                t,
+                point,
+                outward_normal: DVec3::new(point.x, point.y, k * (h - point.z)).normalize(),
+                uv: DVec2::new(phi / self.phi_max, v),
+                dpdu: self.phi_max * DVec3::new(-point.y, point.x, 0.0),
+                dpdv: DVec3::new(-point.x / (1.0 - v), -point.y / (1.0 - v), h),
+            })
+        });
+        let mut closest = side;
+        if self.capped {
+            let top_radius = self.radius * (1.0 - z_max / h);
+            let caps = [
+                Cap {
+                    z: 0.0,
+                    radius: self.radius,
+                    up: false,
+                },
+                Cap {
+                    z: z_max,
+                    radius: top_radius,
+                    up: true,
+                },
+            ];
+            for cap in caps {
+                let t_max = closest.as_ref().map_or(t_max, |hit| hit.t);
+                closest = closer(closest, cap_hit(o, d, cap, self.phi_max, t_min, t_max));
+            }
+        }
+        Some(to_world(&frame, self.center, ray, closest?, &self.material))
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<Aabb> {
+        let frame = local_frame(self.axis);
+        let r = self.radius;
+        Some(world_bounds(
+            &frame,
+            self.center,
+            DVec3::new(-r, -r, 0.0),
+            DVec3::new(r, r, self.z_max.min(self.height)),
+        ))
+    }
+}
+
+/// A paraboloid opening along the axis, with radius `radius` at `z_max`,
+/// cut off below `z_min` and swept by `phi_max` radians. Capped paraboloids
+/// are closed at `z_max`.
+#[derive(Debug, Clone)]
+pub struct Paraboloid<Mat> {
+    pub center: DVec3,
+    pub axis: DVec3,
+    pub radius: f64,
+    pub z_min: f64,
+    pub z_max: f64,
+    pub phi_max: f64,
+    pub capped: bool,
+    pub material: Mat,
+}
+
+impl<Mat: Material> Hittable for Paraboloid<Mat> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        let frame = local_frame(self.axis);
+        let (o, d) = to_local(&frame, self.center, ray);
+        let k = self.z_max / (self.radius * self.radius);
+        let z_min = self.z_min.max(0.0);
+        let height = self.z_max - z_min;
+        let roots = solve_quadratic(
+            k * (d.x * d.x + d.y * d.y),
+            2.0 * k * (d.x * o.x + d.y * o.y) - d.z,
+            k * (o.x * o.x + o.y * o.y) - o.z,
+        );
+        let side = closest_root(roots, t_min, t_max, |t| {
+            let point = o + t * d;
+            let phi = phi(point);
+            if point.z < z_min || point.z > self.z_max || phi > self.phi_max {
+                return None;
+            }
+            let z = point.z.max(1.0e-8);
+            Some(LocalHit {
+                t,
+                point,
+                outward_normal: DVec3::new(2.0 * k * point.x, 2.0 * k * point.y, -1.0).normalize(),
+                uv: DVec2::new(phi / self.phi_max, (point.z - z_min) / height),
+                dpdu: self.phi_max * DVec3::new(-point.y, point.x, 0.0),
+                dpdv: height * DVec3::new(point.x / (2.0 * z), point.y / (2.0 * z), 1.0),
+            })
+        });
+        let mut closest = side;
+        if self.capped {
+            let t_max = closest.as_ref().map_or(t_max, |hit| hit.t);
+            let cap = Cap {
+                z: self.z_max,
+                radius: self.radius,
+                up: true,
+            };
+            closest = closer(closest, cap_hit(o, d, cap, self.phi_max, t_min, t_max));
+        }
+        Some(to_world(&frame, self.center, ray, closest?, &self.material))
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<Aabb> {
+        let frame = local_frame(self.axis);
+        let r = self.radius;
+        Some(world_bounds(
+            &frame,
+            self.center,
+            DVec3::new(-r, -r, self.z_min.max(0.0)),
+            DVec3::new(r, r, self.z_max),
+        ))
+    }
+}
+
+/// A hyperboloid of one sheet with radius `waist_radius` at the center,
+/// widening by `slope` units of radius per unit of height, between `z_min`
+/// and `z_max` and swept by `phi_max` radians. Capped hyperboloids are closed
+/// at both ends.
+#[derive(Debug, Clone)]
+pub struct Hyperboloid<Mat> {
+    pub center: DVec3,
+    pub axis: DVec3,
+    pub waist_radius: f64,
+    pub slope: f64,
+    pub z_min: f64,
+    pub z_max: f64,
+    pub phi_max: f64,
+    pub capped: bool,
+    pub material: Mat,
+}
+
+impl<Mat> Hyperboloid<Mat> {
+    fn radius_at(&self, z: f64) -> f64 {
+        (self.waist_radius * self.waist_radius + self.slope * self.slope * z * z).sqrt()
+    }
+}
+
+impl<Mat: Material> Hittable for Hyperboloid<Mat> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        let frame = local_frame(self.axis);
+        let (o, d) = to_local(&frame, self.center, ray);
+        let s2 = self.slope * self.slope;
+        let height = self.z_max - self.z_min;
+        let roots = solve_quadratic(
+            d.x * d.x + d.y * d.y - s2 * d.z * d.z,
+            2.0 * (d.x * o.x + d.y * o.y - s2 * d.z * o.z),
+            o.x * o.x + o.y * o.y - s2 * o.z * o.z - self.waist_radius * self.waist_radius,
+        );
+        let side = closest_root(roots, t_min, t_max, |t| {
+            let point = o + t * d;
+            let phi = phi(point);
+            if point.z < self.z_min || point.z > self.z_max || phi > self.phi_max {
+                return None;
+            }
+            let r2 = point.xy().length_squared();
+            Some(LocalHit {
+                t,
+                point,
+                outward_normal: DVec3::new(point.x, point.y, -s2 * point.z).normalize(),
+                uv: DVec2::new(phi / self.phi_max, (point.z - self.z_min) / height),
+                dpdu: self.phi_max * DVec3::new(-point.y, point.x, 0.0),
+                dpdv: height
+                    * DVec3::new(
+                        point.x * s2 * point.z / r2,
+                        point.y * s2 * point.z / r2,
+                        1.0,
+                    ),
+            })
+        });
+        let mut closest = side;
+        if self.capped {
+            let caps = [
+                Cap {
+                    z: self.z_min,
+                    radius: self.radius_at(self.z_min),
+                    up: false,
+                },
+                Cap {
+                    z: self.z_max,
+                    radius: self.radius_at(self.z_max),
+                    up: true,
+                },
+            ];
+            for cap in caps {
+                let t_max = closest.as_ref().map_or(t_max, |hit| hit.t);
+                closest = closer(closest, cap_hit(o, d, cap, self.phi_max, t_min, t_max));
+            }
+        }
+        Some(to_world(&frame, self.center, ray, closest?, &self.material))
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<Aabb> {
+        let frame = local_frame(self.axis);
+        let r = self.radius_at(self.z_min).max(self.radius_at(self.z_max));
+        Some(world_bounds(
+            &frame,
+            self.center,
+            DVec3::new(-r, -r, self.z_min),
+            DVec3::new(r, r, self.z_max),
+        ))
+    }
+}
+
+/// A torus around the axis, with its tube of radius `minor_radius` running
+/// at `major_radius` from the center, swept by `phi_max` radians. u runs
+/// around the axis and v around the tube.
+#[derive(Debug, Clone)]
+pub struct Torus<Mat> {
+    pub center: DVec3,
+    pub axis: DVec3,
+    pub major_radius: f64,
+    pub minor_radius: f64,
+    pub phi_max: f64,
+    pub material: Mat,
+}
+
+impl<Mat: Material> Hittable for Torus<Mat> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        let frame = local_frame(self.axis);
+        let (o, d) = to_local(&frame, self.center, ray);
+        // Solve with a normalized direction for better conditioning.
+        let length = d.length();
+        let d = d / length;
+        let big_r2 = self.major_radius * self.major_radius;
+        let n = o.dot(d);
+        let e = o.length_squared() + big_r2 - self.minor_radius * self.minor_radius;
+        let coefficients = [
+            e * e - 4.0 * big_r2 * (o.x * o.x + o.y * o.y),
+            4.0 * n * e - 8.0 * big_r2 * (o.x * d.x + o.y * d.y),
+            4.0 * n * n + 2.0 * e - 4.0 * big_r2 * (d.x * d.x + d.y * d.y),
+            4.0 * n,
+        ];
+
+        let local = solve_quartic(coefficients)
+            .into_iter()
+            .map(|s| s / length)
+            .filter(|t| (t_min..=t_max).contains(t))
+            .find_map(|t| {
+                let point = o + t * length * d;
+                let phi = phi(point);
+                if phi > self.phi_max {
+                    return None;
+                }
+                let rho = point.xy().length();
+                let ring = if rho > 0.0 {
+                    (point.xy() / rho).extend(0.0)
+                } else {
+                    DVec3::X
+                };
+                let outward_normal = (point - self.major_radius * ring).normalize_or_zero();
+                let theta = point.z.atan2(rho - self.major_radius).rem_euclid(f64::TAU);
+                Some(LocalHit {
+                    t,
+                    point,
+                    outward_normal,
+                    uv: DVec2::new(phi / self.phi_max, theta / f64::TAU),
+                    dpdu: self.phi_max * DVec3::new(-point.y, point.x, 0.0),
+                    dpdv: f64::TAU
+                        * self.minor_radius
+                        * (-theta.sin() * ring + theta.cos() * DVec3::Z),
+                })
+            })?;
+        Some(to_world(&frame, self.center, ray, local, &self.material))
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<Aabb> {
+        let frame = local_frame(self.axis);
+        let r = self.major_radius + self.minor_radius;
+        let h = self.minor_radius;
+        Some(world_bounds(
+            &frame,
+            self.center,
+            DVec3::new(-r, -r, -h),
+            DVec3::new(r, r, h),
+        ))
+    }
+}
+
+/// Real roots of `t³ + a t² + b t + c`.
+fn solve_cubic(a: f64, b: f64, c: f64) -> Vec<f64> {
+    // Substitute t = x - a/3 to get the depressed cubic x³ + p x + q.
+    let a2 = a * a;
+    let p = (3.0 * b - a2) / 9.0;
+    let q = (2.0 * a2 * a - 9.0 * a * b + 27.0 * c) / 54.0;
+    let shift = a / 3.0;
+    let p3 = p * p * p;
+    let discriminant = q * q + p3;
+
+    if discriminant.abs() < 1.0e-14 {
+        if q == 0.0 {
+            vec![-shift]
+        } else {
+            let u = (-q).cbrt();
+            vec![2.0 * u - shift, -u - shift]
+        }
+    } else if discriminant < 0.0 {
+        let phi = (-q / (-p3).sqrt()).clamp(-1.0, 1.0).acos() / 3.0;
+        let m = 2.0 * (-p).sqrt();
+        vec![
+            m * phi.cos() - shift,
+            -m * (phi + f64::FRAC_PI_3).cos() - shift,
+            -m * (phi - f64::FRAC_PI_3).cos() - shift,
+        ]
+    } else {
+        let sqrt_d = discriminant.sqrt();
+        vec![(sqrt_d - q).cbrt() - (sqrt_d + q).cbrt() - shift]
+    }
+}
+
+/// Real roots of `t⁴ + c[3] t³ + c[2] t² + c[1] t + c[0]`, in ascending order
+/// and refined with Newton's method.
+fn solve_quartic(c: [f64; 4]) -> Vec<f64> {
+    let [c0, c1, c2, c3] = c;
+    // Substitute t = x - c3/4 to get the depressed quartic x⁴ + p x² + q x + r.
+    let c3_2 = c3 * c3;
+    let p = c2 - 3.0 / 8.0 * c3_2;
+    let q = c1 - 0.5 * c2 * c3 + 0.125 * c3_2 * c3;
+    let r = c0 - 0.25 * c1 * c3 + c2 * c3_2 / 16.0 - 3.0 * c3_2 * c3_2 / 256.0;
+    let shift = c3 / 4.0;
+
+    let mut roots = Vec::with_capacity(4);
+    if q.abs() < 1.0e-12 {
+        // Biquadratic: solve for x².
+        if let Some((y0, y1)) = solve_quadratic(1.0, p, r) {
+            for y in [y0, y1] {
+                if y >= 0.0 {
+                    roots.push(y.sqrt());
+                    roots.push(-y.sqrt());
+                }
+            }
+        }
+    } else {
+        // Ferrari: pick a root z of the resolvent cubic, then factor into two
+        // quadratics.
+        let z = solve_cubic(-0.5 * p, -r, 0.5 * r * p - 0.125 * q * q)
+            .into_iter()
+            .fold(f64::NEG_INFINITY, f64::max);
+        let u2 = z * z - r;
+        let v2 = 2.0 * z - p;
+        if u2 < -1.0e-12 || v2 < -1.0e-12 {
+            return roots;
+        }
+        let u = u2.max(0.0).sqrt();
+        let v = v2.max(0.0).sqrt() * q.signum().max(0.0).mul_add(2.0, -1.0);
+        for (b, c) in [(v, z - u), (-v, z + u)] {
+            if let Some((x0, x1)) = solve_quadratic(1.0, b, c) {
+                roots.push(x0);
+                roots.push(x1);
+            }
+        }
+    }
+
+    let eval = |t: f64| (((t + c3) * t + c2) * t + c1) * t + c0;
+    let derivative = |t: f64| ((4.0 * t + 3.0 * c3) * t + 2.0 * c2) * t + c1;
+    let mut roots: Vec<f64> = roots
+        .into_iter()
+        .map(|x| {
+            let mut t = x - shift;
+            for _ in 0..2 {
+                let slope = derivative(t);
+                if slope != 0.0 {
+                    t -= eval(t) / slope;
+                }
+            }
+            t
+        })
+        .collect();
+    roots.sort_by(f64::total_cmp);
+    roots
+}
@@ -5,10 +5,20 @@ pub struct Ray {
     pub origin: DVec3,
     pub direction: DVec3,
     pub time: f64,
+    /// Width of the cone of rays represented by this ray, at its origin.
+    /// Used to estimate the footprint of texture lookups.
+    pub width: f64,
+    /// Rate at which the width of the cone grows with distance.
+    pub spread: f64,
 }
 
 impl Ray {
     pub fn at(&self, t: f64) -> DVec3 {
         self.origin + self.direction * t
     }
+
+    /// Width of the cone of rays at `at(t)`.
+    pub fn width_at(&self, t: f64) -> f64 {
+        self.width + self.spread * t * self.direction.length()
+    }
 }
@@ -0,0 +1,71 @@
+use glam::DVec3;
+
+use crate::{
+    camera::{Camera, CameraDescriptor},
+    hittable::{Sphere, World},
+    material::{BumpMap, Conductor, Lambertian},
+    sky::{Sky, SkyDescriptor},
+    texture::{Marble, Solid},
+};
+
+use super::Scene;
+
+pub fn build(aspect_ratio: f64) -> Scene {
+    let camera_desc = CameraDescriptor {
+        origin: DVec3::new(13.0, 2.0, 3.0),
+        look_at: DVec3::new(0.0, 1.0, 0.0),
+        vfov: 20.0,
+        aspect_ratio,
+        ..Default::default()
+    };
+    let camera = Camera::new(&camera_desc);
+
+    let sky = Sky::new(&SkyDescriptor {
+        sun_direction: DVec3::new(1.0, 1.0, 2.0),
+        ..Default::default()
+    });
+
+    let mut world = World::new();
+    world.add(Sphere {
+        center: DVec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: BumpMap {
+            inner: Lambertian {
+                albedo: Solid {
+                    color: DVec3::new(0.6, 0.55, 0.45),
+                },
+            },
+            height: Marble::new(1, 2.0),
+            scale: 0.05,
+        },
+    });
+    world.add(Sphere {
+        center: DVec3::new(0.0, 1.0, -1.2),
+        radius: 1.0,
+        material: BumpMap {
+            inner: Lambertian {
+                albedo: Solid {
+                    color: DVec3::new(0.8, 0.3, 0.2),
+                },
+            },
+            height: Marble::new(2, 8.0),
+            scale: 0.02,
+        },
+    });
+    world.add(Sphere {
+        center: DVec3::new(0.0, 1.0, 1.2),
+        radius: 1.0,
+        material: BumpMap {
+            inner: Conductor::silver(0.1),
+            height: Marble::new(3, 4.0),
+            scale: 0.01,
+        },
+    });
+
+    Scene {
+        world,
+        camera,
+        lights: vec![Box::new(sky.sun())],
+        background: Box::new(sky),
+    }
+}
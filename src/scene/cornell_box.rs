@@ -4,7 +4,8 @@ use glam::{DVec2, DVec3};
 
 use crate::{
     camera::{Camera, CameraDescriptor},
-    hittable::{Cuboid, Plane, Rect, RotateY, Translate, World},
+    hittable::{Cuboid, FlipFace, Plane, Rect, RotateY, Translate, World},
+    light::Area,
     material::{DiffuseLight, Lambertian},
     texture::Solid,
 };
@@ -40,6 +41,8 @@ pub fn build(aspect_ratio: f64) -> Scene {
         emit: Solid {
             color: DVec3::new(15.0, 15.0, 15.0),
         },
+        intensity: 1.0,
+        two_sided: false,
     });
 
     let mut world = World::new();
@@ -57,13 +60,16 @@ pub fn build(aspect_ratio: f64) -> Scene {
         k: 0.0,
         material: Arc::clone(&red),
     });
-    world.add(Rect {
-        plane: Plane::ZX,
-        min: DVec2::new(227.0, 213.0),
-        max: DVec2::new(332.0, 343.0),
-        k: 554.0,
-        material: Arc::clone(&light),
+    let ceiling_light = Arc::new(FlipFace {
+        inner: Rect {
+            plane: Plane::ZX,
+            min: DVec2::new(227.0, 213.0),
+            max: DVec2::new(332.0, 343.0),
+            k: 554.0,
+            material: Arc::clone(&light),
+        },
     });
+    world.add(Arc::clone(&ceiling_light));
     world.add(Rect {
         plane: Plane::ZX,
         min: DVec2::new(0.0, 0.0),
@@ -112,6 +118,9 @@ pub fn build(aspect_ratio: f64) -> Scene {
     Scene {
         world,
         camera,
-        background: DVec3::ZERO,
+        background: Box::new(DVec3::ZERO),
+        lights: vec![Box::new(Area {
+            shape: ceiling_light,
+        })],
     }
 }
@@ -0,0 +1,147 @@
+use std::{f64::consts as f64, sync::Arc};
+
+use glam::DVec3;
+
+use crate::{
+    camera::{Camera, CameraDescriptor},
+    csg::{Difference, Intersection, Union},
+    hittable::{Cuboid, Sphere, World},
+    material::{Dielectric, Lambertian, Metal},
+    quadric::Cylinder,
+    sky::{Sky, SkyDescriptor},
+    texture::{Checker, Solid},
+};
+
+use super::Scene;
+
+pub fn build(aspect_ratio: f64) -> Scene {
+    let camera_desc = CameraDescriptor {
+        origin: DVec3::new(3.0, 5.0, 12.0),
+        look_at: DVec3::new(0.0, 1.0, 0.0),
+        vfov: 35.0,
+        aspect_ratio,
+        ..Default::default()
+    };
+    let camera = Camera::new(&camera_desc);
+
+    let sky = Sky::new(&SkyDescriptor {
+        sun_direction: DVec3::new(1.0, 1.5, 1.0),
+        ..Default::default()
+    });
+
+    let red = Arc::new(Lambertian {
+        albedo: Solid {
+            color: DVec3::new(0.7, 0.15, 0.1),
+        },
+    });
+    let white = Arc::new(Lambertian {
+        albedo: Solid {
+            color: DVec3::splat(0.8),
+        },
+    });
+
+    let mut world = World::new();
+    world.add(Sphere {
+        center: DVec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Lambertian {
+            albedo: Checker {
+                even: Solid {
+                    color: DVec3::splat(0.6),
+                },
+                odd: Solid {
+                    color: DVec3::splat(0.3),
+                },
+                scale: 1.0,
+            },
+        },
+    });
+
+    // A cube with a sphere carved out of its corner.
+    world.add(Difference {
+        a: Cuboid::new(
+            DVec3::new(-4.0, 0.0, -1.0),
+            DVec3::new(-2.0, 2.0, 1.0),
+            Arc::clone(&red),
+        ),
+        b: Sphere {
+            center: DVec3::new(-2.0, 2.0, 1.0),
+            radius: 1.3,
+            material: Arc::clone(&white),
+        },
+    });
+
+    // A rounded glass cube.
+    world.add(Intersection {
+        a: Cuboid::new(
+            DVec3::new(-1.0, 0.0, -1.0),
+            DVec3::new(1.0, 2.0, 1.0),
+            Dielectric::default(),
+        ),
+        b: Sphere {
+            center: DVec3::new(0.0, 1.0, 0.0),
+            radius: 1.35,
+            material: Dielectric::default(),
+        },
+    });
+
+    // A glass capsule without inner surfaces.
+    world.add(Union {
+        a: Union {
+            a: Sphere {
+                center: DVec3::new(2.5, 0.6, 0.0),
+                radius: 0.6,
+                material: Dielectric::default(),
+            },
+            b: Sphere {
+                center: DVec3::new(4.5, 0.6, 0.0),
+                radius: 0.6,
+                material: Dielectric::default(),
+            },
+        },
+        b: Cylinder {
+            center: DVec3::new(2.5, 0.6, 0.0),
+            axis: DVec3::X,
+            radius: 0.6,
+            z_min: 0.0,
+            z_max: 2.0,
+            phi_max: f64::TAU,
+            capped: true,
+            material: Dielectric::default(),
+        },
+    });
+
+    // A pipe.
+    world.add(Difference {
+        a: Cylinder {
+            center: DVec3::new(0.0, 0.0, 3.0),
+            axis: DVec3::Y,
+            radius: 0.7,
+            z_min: 0.0,
+            z_max: 1.0,
+            phi_max: f64::TAU,
+            capped: true,
+            material: Metal {
+                albedo: DVec3::new(0.9, 0.8, 0.5),
+                fuzz: 0.2,
+            },
+        },
+        b: Cylinder {
+            center: DVec3::new(0.0, -1.0, 3.0),
+            axis: DVec3::Y,
+            radius: 0.5,
+            z_min: 0.0,
+            z_max: 3.0,
+            phi_max: f64::TAU,
+            capped: true,
+            material: white,
+        },
+    });
+
+    Scene {
+        world,
+        camera,
+        lights: vec![Box::new(sky.sun())],
+        background: Box::new(sky),
+    }
+}
@@ -4,12 +4,14 @@ use crate::{
     camera::{Camera, CameraDescriptor},
     hittable::{Sphere, World},
     material::Lambertian,
+    mipmap::ColorSpace,
     texture::Image,
+    texture_manager::TextureManager,
 };
 
 use super::Scene;
 
-pub fn build(aspect_ratio: f64) -> Scene {
+pub fn build(aspect_ratio: f64, textures: &mut TextureManager) -> anyhow::Result<Scene> {
     let camera_desc = CameraDescriptor {
         origin: DVec3::new(13.0, 2.0, 3.0),
         look_at: DVec3::ZERO,
@@ -24,15 +26,14 @@ pub fn build(aspect_ratio: f64) -> Scene {
         center: DVec3::ZERO,
         radius: 2.0,
         material: Lambertian {
-            albedo: Image {
-                image: image::open("assets/earthmap.jpg").unwrap(),
-            },
+            albedo: Image::new(textures.load("assets/earthmap.jpg", ColorSpace::Srgb)?),
         },
     });
 
-    Scene {
+    Ok(Scene {
         world,
         camera,
-        background: DVec3::new(0.7, 0.8, 1.0),
-    }
+        background: Box::new(DVec3::new(0.7, 0.8, 1.0)),
+        lights: Vec::new(),
+    })
 }
@@ -0,0 +1,120 @@
+use std::{f64::consts as f64, sync::Arc};
+
+use glam::DVec3;
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    camera::{Camera, CameraDescriptor},
+    curve::{Curve, CurveShape},
+    hittable::{BvhNode, Hittable, Sphere, World},
+    material::{Hair, Lambertian},
+    sky::{Sky, SkyDescriptor},
+    texture::Solid,
+};
+
+use super::Scene;
+
+fn random_on_sphere(rng: &mut StdRng) -> DVec3 {
+    let z: f64 = rng.gen_range(-1.0..1.0);
+    let phi = f64::TAU * rng.gen::<f64>();
+    let r = (1.0 - z * z).sqrt();
+    DVec3::new(r * phi.cos(), r * phi.sin(), z)
+}
+
+pub fn build(aspect_ratio: f64) -> Scene {
+    let camera_desc = CameraDescriptor {
+        origin: DVec3::new(0.0, 2.0, 7.0),
+        look_at: DVec3::new(0.0, 0.9, 0.0),
+        vfov: 35.0,
+        aspect_ratio,
+        ..Default::default()
+    };
+    let camera = Camera::new(&camera_desc);
+
+    let sky = Sky::new(&SkyDescriptor {
+        sun_direction: DVec3::new(1.0, 1.5, 1.0),
+        ..Default::default()
+    });
+
+    let mut rng = StdRng::seed_from_u64(5);
+    let mut objects: Vec<Arc<dyn Hittable>> = Vec::new();
+
+    // A furry ball, with strands drooping under gravity.
+    let center = DVec3::new(-1.0, 1.0, 0.0);
+    let radius = 0.6;
+    let fur = Arc::new(Hair::new(Solid {
+        color: DVec3::new(0.85, 0.6, 0.3),
+    }));
+    objects.push(Arc::new(Sphere {
+        center,
+        radius,
+        material: Lambertian {
+            albedo: Solid {
+                color: DVec3::new(0.3, 0.15, 0.05),
+            },
+        },
+    }));
+    for _ in 0..20000 {
+        let normal = random_on_sphere(&mut rng);
+        let length = rng.gen_range(0.25..0.35);
+        let root = center + radius * normal;
+        let droop = -0.3 * length * DVec3::Y;
+        objects.push(Arc::new(Curve {
+            control_points: [
+                root,
+                root + length / 3.0 * normal,
+                root + 2.0 * length / 3.0 * normal + 0.5 * droop,
+                root + length * normal + droop,
+            ],
+            widths: [0.006, 0.002],
+            shape: CurveShape::Cylinder,
+            material: Arc::clone(&fur),
+        }));
+    }
+
+    // A patch of grass blades.
+    let grass = Arc::new(Lambertian {
+        albedo: Solid {
+            color: DVec3::new(0.2, 0.5, 0.1),
+        },
+    });
+    for _ in 0..4000 {
+        let root = DVec3::new(rng.gen_range(0.2..2.4), 0.0, rng.gen_range(-1.2..1.2));
+        let height = rng.gen_range(0.3..0.6);
+        let lean = DVec3::new(rng.gen_range(-0.2..0.2), 0.0, rng.gen_range(-0.2..0.2));
+        let facing = f64::TAU * rng.gen::<f64>();
+        let normal = DVec3::new(facing.cos(), 0.0, facing.sin());
+        objects.push(Arc::new(Curve {
+            control_points: [
+                root,
+                root + height / 3.0 * DVec3::Y,
+                root + 2.0 * height / 3.0 * DVec3::Y + 0.5 * lean,
+                root + height * DVec3::Y + lean,
+            ],
+            widths: [0.03, 0.0],
+            shape: CurveShape::Ribbon {
+                normals: [normal, normal],
+            },
+            material: Arc::clone(&grass),
+        }));
+    }
+
+    let mut world = World::new();
+    world.add(Sphere {
+        center: DVec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Lambertian {
+            albedo: Solid {
+                color: DVec3::new(0.3, 0.25, 0.2),
+            },
+        },
+    });
+    world.add(BvhNode::new(&mut objects, 0.0, 1.0));
+
+    Scene {
+        world,
+        camera,
+        lights: vec![Box::new(sky.sun())],
+        background: Box::new(sky),
+    }
+}
@@ -1,16 +1,54 @@
+pub mod bumpy_spheres;
 pub mod cornell_box;
+pub mod csg;
 pub mod earth;
+pub mod fur;
+pub mod particles;
+pub mod principled_spheres;
+pub mod procedural_textures;
+pub mod quadrics;
 pub mod random_scene;
+pub mod sdf_shapes;
 pub mod simple_light;
+pub mod stage_lights;
+pub mod subdivision;
+pub mod sunny_day;
+pub mod terrain;
 pub mod two_perlin_spheres;
 pub mod two_spheres;
 
-use glam::DVec3;
-
-use crate::{camera::Camera, hittable::World};
+use crate::{camera::Camera, hittable::World, light::Light, sky::Background};
 
 pub struct Scene {
     pub world: World,
     pub camera: Camera,
-    pub background: DVec3,
+    pub background: Box<dyn Background>,
+    pub lights: Vec<Box<dyn Light>>,
+}
+
+/// Image size and sampling parameters for rendering a [`Scene`].
+#[derive(Debug, Clone)]
+pub struct RenderSettings {
+    pub width: usize,
+    pub height: usize,
+    pub samples_per_pixel: usize,
+    /// Maximum number of surfaces a path bounces off.
+    pub max_depth: u32,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        Self {
+            width: 600,
+            height: 600,
+            samples_per_pixel: 400,
+            max_depth: 50,
+        }
+    }
+}
+
+impl RenderSettings {
+    pub fn aspect_ratio(&self) -> f64 {
+        self.width as f64 / self.height as f64
+    }
 }
@@ -0,0 +1,91 @@
+use std::f64::consts as f64;
+
+use glam::{DVec3, Vec3};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+
+use crate::{
+    camera::{Camera, CameraDescriptor},
+    hittable::{Sphere, World},
+    material::Lambertian,
+    particles::{Particles, SphereCloud},
+    sky::{Sky, SkyDescriptor},
+    texture::{Checker, Solid, VertexColor},
+};
+
+use super::Scene;
+
+/// A spiral galaxy of small particles, colored from the core to the arms.
+fn galaxy(rng: &mut StdRng, count: usize) -> Particles {
+    let core = DVec3::new(1.0, 0.8, 0.4);
+    let arms = DVec3::new(0.3, 0.5, 1.0);
+    let mut particles = Particles::default();
+    for _ in 0..count {
+        let distance: f64 = rng.gen::<f64>().powf(1.5);
+        let arm = f64::PI * rng.gen_range(0..2) as f64;
+        let angle = arm + 4.0 * distance + rng.gen_range(-0.3..0.3);
+        let spread = 0.15 * (1.0 - distance) + 0.05;
+        let position = DVec3::new(
+            2.5 * distance * angle.cos() + rng.gen_range(-spread..spread),
+            rng.gen_range(-spread..spread),
+            2.5 * distance * angle.sin() + rng.gen_range(-spread..spread),
+        );
+        let color = core.lerp(arms, distance.sqrt());
+        particles.centers.push(position.as_vec3());
+        particles.radii.push(rng.gen_range(0.005..0.015));
+        particles.colors.push(color.as_vec3());
+    }
+    particles
+}
+
+pub fn build(aspect_ratio: f64) -> anyhow::Result<Scene> {
+    let camera_desc = CameraDescriptor {
+        origin: DVec3::new(0.0, 4.0, 6.0),
+        look_at: DVec3::new(0.0, 0.8, 0.0),
+        vfov: 40.0,
+        aspect_ratio,
+        ..Default::default()
+    };
+    let camera = Camera::new(&camera_desc);
+
+    let sky = Sky::new(&SkyDescriptor {
+        sun_direction: DVec3::new(1.0, 1.5, 1.0),
+        ..Default::default()
+    });
+
+    let mut rng = StdRng::seed_from_u64(7);
+    let mut particles = galaxy(&mut rng, 300_000);
+    for center in &mut particles.centers {
+        *center += Vec3::new(0.0, 1.0, 0.0);
+    }
+
+    let mut world = World::new();
+    world.add(Sphere {
+        center: DVec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Lambertian {
+            albedo: Checker {
+                even: Solid {
+                    color: DVec3::splat(0.6),
+                },
+                odd: Solid {
+                    color: DVec3::splat(0.3),
+                },
+                scale: 1.0,
+            },
+        },
+    });
+    world.add(SphereCloud::new(
+        particles,
+        0.01,
+        Lambertian {
+            albedo: VertexColor,
+        },
+    )?);
+
+    Ok(Scene {
+        world,
+        camera,
+        lights: vec![Box::new(sky.sun())],
+        background: Box::new(sky),
+    })
+}
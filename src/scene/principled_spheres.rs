@@ -0,0 +1,97 @@
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::{
+    camera::{Camera, CameraDescriptor},
+    hittable::{Sphere, World},
+    material::{Lambertian, Principled},
+    sky::{Sky, SkyDescriptor},
+    texture::{Checker, Solid, Texture},
+};
+
+use super::Scene;
+
+fn solid(color: DVec3) -> Arc<dyn Texture> {
+    Arc::new(Solid { color })
+}
+
+pub fn build(aspect_ratio: f64) -> Scene {
+    let camera_desc = CameraDescriptor {
+        origin: DVec3::new(0.0, 3.0, 12.0),
+        look_at: DVec3::new(0.0, 0.8, 0.0),
+        vfov: 50.0,
+        aspect_ratio,
+        ..Default::default()
+    };
+    let camera = Camera::new(&camera_desc);
+
+    let sky = Sky::new(&SkyDescriptor {
+        sun_direction: DVec3::new(1.0, 1.5, 1.0),
+        ..Default::default()
+    });
+
+    let mut world = World::new();
+    world.add(Sphere {
+        center: DVec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Lambertian {
+            albedo: Checker {
+                even: Solid {
+                    color: DVec3::splat(0.6),
+                },
+                odd: Solid {
+                    color: DVec3::splat(0.3),
+                },
+                scale: 3.0,
+            },
+        },
+    });
+
+    let red = DVec3::new(0.8, 0.1, 0.1);
+    let materials = [
+        Principled {
+            base_color: solid(red),
+            roughness: solid(DVec3::splat(0.6)),
+            ..Default::default()
+        },
+        Principled {
+            base_color: solid(red),
+            roughness: solid(DVec3::splat(0.2)),
+            clearcoat: solid(DVec3::ONE),
+            ..Default::default()
+        },
+        Principled {
+            base_color: solid(DVec3::new(0.95, 0.64, 0.54)),
+            metallic: solid(DVec3::ONE),
+            roughness: solid(DVec3::splat(0.3)),
+            ..Default::default()
+        },
+        Principled {
+            base_color: solid(DVec3::new(0.1, 0.1, 0.4)),
+            roughness: solid(DVec3::ONE),
+            sheen: solid(DVec3::splat(0.8)),
+            ..Default::default()
+        },
+        Principled {
+            base_color: solid(DVec3::new(0.9, 1.0, 0.9)),
+            roughness: solid(DVec3::ZERO),
+            transmission: solid(DVec3::ONE),
+            ..Default::default()
+        },
+    ];
+    for (i, material) in materials.into_iter().enumerate() {
+        world.add(Sphere {
+            center: DVec3::new(2.2 * (i as f64 - 2.0), 1.0, 0.0),
+            radius: 1.0,
+            material,
+        });
+    }
+
+    Scene {
+        world,
+        camera,
+        lights: vec![Box::new(sky.sun())],
+        background: Box::new(sky),
+    }
+}
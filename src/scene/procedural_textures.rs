@@ -0,0 +1,172 @@
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::{
+    camera::{Camera, CameraDescriptor},
+    hittable::{Sphere, World},
+    material::Lambertian,
+    perlin::Perlin,
+    sky::{Sky, SkyDescriptor},
+    texture::{
+        Checker, ColorRamp, ColorStop, Fbm, Gradient, Marble, Mix, Solid, Voronoi, Wood, Worley,
+        WorleyFeature,
+    },
+};
+
+use super::Scene;
+
+pub fn build(aspect_ratio: f64) -> Scene {
+    let camera_desc = CameraDescriptor {
+        origin: DVec3::new(0.0, 3.0, 12.0),
+        look_at: DVec3::new(0.0, 0.8, 0.0),
+        vfov: 40.0,
+        aspect_ratio,
+        ..Default::default()
+    };
+    let camera = Camera::new(&camera_desc);
+
+    let sky = Sky::new(&SkyDescriptor {
+        sun_direction: DVec3::new(1.0, 1.5, 1.0),
+        ..Default::default()
+    });
+
+    let perlin = Arc::new(Perlin::new(1));
+
+    let mut world = World::new();
+    world.add(Sphere {
+        center: DVec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Lambertian {
+            albedo: Checker {
+                even: Solid {
+                    color: DVec3::splat(0.6),
+                },
+                odd: Solid {
+                    color: DVec3::splat(0.3),
+                },
+                scale: 1.0,
+            },
+        },
+    });
+
+    world.add(Sphere {
+        center: DVec3::new(-4.5, 1.0, 0.0),
+        radius: 1.0,
+        material: Lambertian {
+            albedo: ColorRamp {
+                input: Wood {
+                    perlin: Arc::clone(&perlin),
+                    rings: 8.0,
+                    scale: 2.0,
+                    turbulence: 0.6,
+                },
+                stops: vec![
+                    ColorStop {
+                        position: 0.0,
+                        color: DVec3::new(0.45, 0.25, 0.1),
+                    },
+                    ColorStop {
+                        position: 0.7,
+                        color: DVec3::new(0.7, 0.45, 0.2),
+                    },
+                    ColorStop {
+                        position: 1.0,
+                        color: DVec3::new(0.45, 0.25, 0.1),
+                    },
+                ],
+            },
+        },
+    });
+    world.add(Sphere {
+        center: DVec3::new(-1.5, 1.0, 0.0),
+        radius: 1.0,
+        material: Lambertian {
+            albedo: ColorRamp {
+                input: Marble {
+                    scale: 6.0,
+                    ..Marble::new(2, 0.0)
+                },
+                stops: vec![
+                    ColorStop {
+                        position: 0.0,
+                        color: DVec3::new(0.1, 0.15, 0.2),
+                    },
+                    ColorStop {
+                        position: 1.0,
+                        color: DVec3::new(0.9, 0.9, 0.85),
+                    },
+                ],
+            },
+        },
+    });
+    world.add(Sphere {
+        center: DVec3::new(1.5, 1.0, 0.0),
+        radius: 1.0,
+        material: Lambertian {
+            albedo: Mix {
+                a: Voronoi {
+                    scale: 3.0,
+                    seed: 7,
+                },
+                b: Solid {
+                    color: DVec3::splat(0.05),
+                },
+                factor: ColorRamp {
+                    input: Worley {
+                        scale: 3.0,
+                        seed: 7,
+                        feature: WorleyFeature::F2MinusF1,
+                    },
+                    stops: vec![
+                        ColorStop {
+                            position: 0.05,
+                            color: DVec3::ONE,
+                        },
+                        ColorStop {
+                            position: 0.1,
+                            color: DVec3::ZERO,
+                        },
+                    ],
+                },
+            },
+        },
+    });
+    world.add(Sphere {
+        center: DVec3::new(4.5, 1.0, 0.0),
+        radius: 1.0,
+        material: Lambertian {
+            albedo: Mix {
+                a: Solid {
+                    color: DVec3::new(0.1, 0.3, 0.1),
+                },
+                b: Solid {
+                    color: DVec3::new(0.8, 0.8, 0.7),
+                },
+                factor: Mix {
+                    a: Gradient {
+                        origin: DVec3::ZERO,
+                        axis: 2.0 * DVec3::Y,
+                    },
+                    b: Fbm {
+                        perlin,
+                        scale: 4.0,
+                        octaves: 6,
+                        lacunarity: 2.0,
+                        gain: 0.5,
+                    },
+                    factor: Solid {
+                        color: DVec3::splat(0.5),
+                    },
+                },
+            },
+        },
+    });
+
+    Scene {
+        world,
+        camera,
+        lights: vec![Box::new(sky.sun())],
+        background: Box::new(sky),
+    }
+}
@@ -0,0 +1,137 @@
+use std::{f64::consts as f64, sync::Arc};
+
+use glam::{DVec2, DVec3};
+
+use crate::{
+    camera::{Camera, CameraDescriptor},
+    hittable::{Sphere, World},
+    material::{Lambertian, Metal},
+    quadric::{Cone, Cylinder, Hyperboloid, Paraboloid, Torus},
+    sky::{Sky, SkyDescriptor},
+    texture::{Checker, Solid, UvChecker},
+};
+
+use super::Scene;
+
+pub fn build(aspect_ratio: f64) -> Scene {
+    let camera_desc = CameraDescriptor {
+        origin: DVec3::new(0.0, 4.0, 14.0),
+        look_at: DVec3::new(0.0, 1.0, 0.0),
+        vfov: 40.0,
+        aspect_ratio,
+        ..Default::default()
+    };
+    let camera = Camera::new(&camera_desc);
+
+    let sky = Sky::new(&SkyDescriptor {
+        sun_direction: DVec3::new(1.0, 1.5, 1.0),
+        ..Default::default()
+    });
+
+    let uv_grid = Arc::new(Lambertian {
+        albedo: UvChecker {
+            even: Solid {
+                color: DVec3::new(0.8, 0.3, 0.1),
+            },
+            odd: Solid {
+                color: DVec3::splat(0.9),
+            },
+            scale: DVec2::new(12.0, 4.0),
+        },
+    });
+
+    let mut world = World::new();
+    world.add(Sphere {
+        center: DVec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Lambertian {
+            albedo: Checker {
+                even: Solid {
+                    color: DVec3::splat(0.6),
+                },
+                odd: Solid {
+                    color: DVec3::splat(0.3),
+                },
+                scale: 1.0,
+            },
+        },
+    });
+
+    world.add(Cylinder {
+        center: DVec3::new(-5.0, 0.0, 0.0),
+        axis: DVec3::Y,
+        radius: 1.0,
+        z_min: 0.0,
+        z_max: 2.0,
+        phi_max: f64::TAU,
+        capped: true,
+        material: Arc::clone(&uv_grid),
+    });
+    world.add(Cylinder {
+        center: DVec3::new(-2.5, 0.0, 0.0),
+        axis: DVec3::Y,
+        radius: 1.0,
+        z_min: 0.0,
+        z_max: 2.0,
+        phi_max: 1.5 * f64::PI,
+        capped: false,
+        material: Arc::clone(&uv_grid),
+    });
+    world.add(Cone {
+        center: DVec3::new(0.0, 0.0, 0.0),
+        axis: DVec3::Y,
+        radius: 1.0,
+        height: 2.5,
+        z_max: 2.5,
+        phi_max: f64::TAU,
+        capped: true,
+        material: Arc::clone(&uv_grid),
+    });
+    world.add(Paraboloid {
+        center: DVec3::new(2.5, 0.2, 0.0),
+        axis: DVec3::Y,
+        radius: 1.0,
+        z_min: 0.0,
+        z_max: 1.8,
+        phi_max: 1.5 * f64::PI,
+        capped: false,
+        material: Arc::clone(&uv_grid),
+    });
+    world.add(Hyperboloid {
+        center: DVec3::new(5.0, 1.0, 0.0),
+        axis: DVec3::Y,
+        waist_radius: 0.5,
+        slope: 0.8,
+        z_min: -1.0,
+        z_max: 1.0,
+        phi_max: f64::TAU,
+        capped: false,
+        material: Arc::clone(&uv_grid),
+    });
+    world.add(Torus {
+        center: DVec3::new(-1.5, 0.5, 3.0),
+        axis: DVec3::new(0.0, 1.0, 0.3),
+        major_radius: 1.0,
+        minor_radius: 0.35,
+        phi_max: f64::TAU,
+        material: Arc::clone(&uv_grid),
+    });
+    world.add(Torus {
+        center: DVec3::new(1.5, 1.0, 3.0),
+        axis: DVec3::Z,
+        major_radius: 0.7,
+        minor_radius: 0.25,
+        phi_max: 1.5 * f64::PI,
+        material: Metal {
+            albedo: DVec3::new(0.9, 0.8, 0.5),
+            fuzz: 0.1,
+        },
+    });
+
+    Scene {
+        world,
+        camera,
+        lights: vec![Box::new(sky.sun())],
+        background: Box::new(sky),
+    }
+}
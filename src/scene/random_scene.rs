@@ -38,6 +38,7 @@ pub fn build(aspect_ratio: f64) -> Scene {
                 even: Solid {
                     color: DVec3::new(0.9, 0.9, 0.9),
                 },
+                scale: 3.0,
             },
         },
     }));
@@ -92,7 +93,10 @@ pub fn build(aspect_ratio: f64) -> Scene {
                     material,
                 }));
             } else {
-                let material = Dielectric { ir: 1.5 };
+                let material = Dielectric {
+                    ir: 1.5,
+                    ..Default::default()
+                };
                 objects.push(Arc::new(Sphere {
                     center,
                     radius,
@@ -105,7 +109,10 @@ pub fn build(aspect_ratio: f64) -> Scene {
     objects.push(Arc::new(Sphere {
         center: DVec3::new(0.0, 1.0, 0.0),
         radius: 1.0,
-        material: Dielectric { ir: 1.5 },
+        material: Dielectric {
+            ir: 1.5,
+            ..Default::default()
+        },
     }));
     objects.push(Arc::new(Sphere {
         center: DVec3::new(-4.0, 1.0, 0.0),
@@ -131,6 +138,7 @@ pub fn build(aspect_ratio: f64) -> Scene {
     Scene {
         world,
         camera,
-        background: DVec3::new(0.7, 0.8, 1.0),
+        background: Box::new(DVec3::new(0.7, 0.8, 1.0)),
+        lights: Vec::new(),
     }
 }
@@ -0,0 +1,138 @@
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::{
+    camera::{Camera, CameraDescriptor},
+    hittable::{BvhNode, Hittable, Sphere, World},
+    material::{Dielectric, Lambertian, Metal},
+    sdf::{self, Capsule, Mandelbulb, RoundBox, Sdf, SmoothDifference, SmoothUnion, Torus},
+    sky::{Sky, SkyDescriptor},
+    texture::{Checker, Solid},
+};
+
+use super::Scene;
+
+pub fn build(aspect_ratio: f64) -> Scene {
+    let camera_desc = CameraDescriptor {
+        origin: DVec3::new(0.0, 4.0, 12.0),
+        look_at: DVec3::new(0.0, 1.0, 0.0),
+        vfov: 35.0,
+        aspect_ratio,
+        ..Default::default()
+    };
+    let camera = Camera::new(&camera_desc);
+
+    let sky = Sky::new(&SkyDescriptor {
+        sun_direction: DVec3::new(1.0, 1.5, 1.0),
+        ..Default::default()
+    });
+
+    let mut objects: Vec<Arc<dyn Hittable>> = vec![
+        // Droplets merging into each other.
+        Arc::new(Sdf::new(
+            SmoothUnion {
+                a: SmoothUnion {
+                    a: sdf::Sphere {
+                        center: DVec3::new(-4.0, 0.8, 0.0),
+                        radius: 0.8,
+                    },
+                    b: sdf::Sphere {
+                        center: DVec3::new(-3.2, 1.6, 0.3),
+                        radius: 0.5,
+                    },
+                    smoothness: 0.6,
+                },
+                b: Capsule {
+                    a: DVec3::new(-4.6, 0.3, 0.5),
+                    b: DVec3::new(-3.0, 0.3, 0.8),
+                    radius: 0.3,
+                },
+                smoothness: 0.4,
+            },
+            Dielectric {
+                tint: DVec3::new(0.4, 0.7, 0.9),
+                ..Default::default()
+            },
+        )),
+        // A rounded box with a smooth dent.
+        Arc::new(Sdf::new(
+            SmoothDifference {
+                a: RoundBox {
+                    center: DVec3::new(-1.2, 0.8, 0.0),
+                    half_extent: DVec3::splat(0.6),
+                    radius: 0.2,
+                },
+                b: sdf::Sphere {
+                    center: DVec3::new(-1.2, 1.6, 0.8),
+                    radius: 0.7,
+                },
+                smoothness: 0.2,
+            },
+            Lambertian {
+                albedo: Solid {
+                    color: DVec3::new(0.7, 0.15, 0.1),
+                },
+            },
+        )),
+        Arc::new(Sdf::new(
+            Mandelbulb {
+                center: DVec3::new(1.6, 1.2, 0.0),
+                scale: 1.0,
+                power: 8.0,
+                iterations: 12,
+            },
+            Lambertian {
+                albedo: Solid {
+                    color: DVec3::new(0.8, 0.7, 0.5),
+                },
+            },
+        )),
+        Arc::new(Sdf::new(
+            Torus {
+                center: DVec3::new(4.2, 0.3, 0.0),
+                major_radius: 0.8,
+                minor_radius: 0.3,
+            },
+            Metal {
+                albedo: DVec3::new(0.9, 0.9, 0.9),
+                fuzz: 0.05,
+            },
+        )),
+        // An analytic sphere next to the distance fields in the same hierarchy.
+        Arc::new(Sphere {
+            center: DVec3::new(0.0, 0.4, 2.5),
+            radius: 0.4,
+            material: Lambertian {
+                albedo: Solid {
+                    color: DVec3::new(0.2, 0.4, 0.8),
+                },
+            },
+        }),
+    ];
+
+    let mut world = World::new();
+    world.add(Sphere {
+        center: DVec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Lambertian {
+            albedo: Checker {
+                even: Solid {
+                    color: DVec3::splat(0.6),
+                },
+                odd: Solid {
+                    color: DVec3::splat(0.3),
+                },
+                scale: 1.0,
+            },
+        },
+    });
+    world.add(BvhNode::new(&mut objects, 0.0, 1.0));
+
+    Scene {
+        world,
+        camera,
+        lights: vec![Box::new(sky.sun())],
+        background: Box::new(sky),
+    }
+}
@@ -6,7 +6,7 @@ use crate::{
     camera::{Camera, CameraDescriptor},
     hittable::{Plane, Rect, Sphere, World},
     material::{DiffuseLight, Lambertian},
-    texture::{Noise, Solid},
+    texture::{Marble, Solid},
 };
 
 use super::Scene;
@@ -22,7 +22,7 @@ pub fn build(aspect_ratio: f64) -> Scene {
     let camera = Camera::new(&camera_desc);
 
     let noise = Arc::new(Lambertian {
-        albedo: Noise::new(&mut rand::thread_rng(), 4.0),
+        albedo: Marble::new(0, 4.0),
     });
 
     let mut world = World::new();
@@ -44,6 +44,8 @@ pub fn build(aspect_ratio: f64) -> Scene {
             emit: Solid {
                 color: DVec3::new(4.0, 4.0, 4.0),
             },
+            intensity: 1.0,
+            two_sided: false,
         },
         plane: Plane::XY,
     });
@@ -51,6 +53,7 @@ pub fn build(aspect_ratio: f64) -> Scene {
     Scene {
         world,
         camera,
-        background: DVec3::ZERO,
+        background: Box::new(DVec3::ZERO),
+        lights: Vec::new(),
     }
 }
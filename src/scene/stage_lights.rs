@@ -0,0 +1,78 @@
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::{
+    camera::{Camera, CameraDescriptor},
+    hittable::{Sphere, World},
+    light::{Directional, Point, Spot},
+    material::{Lambertian, Metal},
+    texture::Solid,
+};
+
+use super::Scene;
+
+pub fn build(aspect_ratio: f64) -> Scene {
+    let camera_desc = CameraDescriptor {
+        origin: DVec3::new(13.0, 4.0, 3.0),
+        look_at: DVec3::new(0.0, 1.0, 0.0),
+        vfov: 25.0,
+        aspect_ratio,
+        ..Default::default()
+    };
+    let camera = Camera::new(&camera_desc);
+
+    let white = Arc::new(Lambertian {
+        albedo: Solid {
+            color: DVec3::new(0.73, 0.73, 0.73),
+        },
+    });
+
+    let mut world = World::new();
+    world.add(Sphere {
+        center: DVec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Arc::clone(&white),
+    });
+    world.add(Sphere {
+        center: DVec3::new(-3.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::clone(&white),
+    });
+    world.add(Sphere {
+        center: DVec3::new(0.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Metal {
+            albedo: DVec3::new(0.8, 0.8, 0.8),
+            fuzz: 0.1,
+        },
+    });
+    world.add(Sphere {
+        center: DVec3::new(3.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Arc::clone(&white),
+    });
+
+    Scene {
+        world,
+        camera,
+        background: Box::new(DVec3::ZERO),
+        lights: vec![
+            Box::new(Point {
+                position: DVec3::new(-3.0, 3.5, 2.0),
+                intensity: DVec3::new(8.0, 6.0, 4.0),
+            }),
+            Box::new(Spot {
+                position: DVec3::new(3.0, 6.0, 0.0),
+                direction: DVec3::NEG_Y,
+                intensity: DVec3::new(10.0, 20.0, 40.0),
+                cone_angle: 25.0_f64.to_radians(),
+                falloff_start: 15.0_f64.to_radians(),
+            }),
+            Box::new(Directional {
+                direction: DVec3::new(1.0, 2.0, 2.0),
+                irradiance: DVec3::splat(0.3),
+            }),
+        ],
+    }
+}
@@ -0,0 +1,131 @@
+use std::sync::Arc;
+
+use glam::DVec3;
+
+use crate::{
+    camera::{Camera, CameraDescriptor},
+    hittable::{Sphere, Translate, World},
+    material::{Lambertian, Metal},
+    mesh::Mesh,
+    perlin::Perlin,
+    sky::{Sky, SkyDescriptor},
+    texture::{Checker, Fbm, Solid},
+};
+
+use super::Scene;
+
+fn cube() -> Mesh {
+    let positions = (0..8)
+        .map(|i| DVec3::new((i & 1) as f64, ((i >> 1) & 1) as f64, ((i >> 2) & 1) as f64) - 0.5)
+        .collect();
+    let faces = vec![
+        vec![0, 2, 3, 1],
+        vec![4, 5, 7, 6],
+        vec![0, 1, 5, 4],
+        vec![2, 6, 7, 3],
+        vec![0, 4, 6, 2],
+        vec![1, 3, 7, 5],
+    ];
+    Mesh::new(positions, faces)
+}
+
+fn octahedron() -> Mesh {
+    let positions = vec![
+        DVec3::X,
+        -DVec3::X,
+        DVec3::Y,
+        -DVec3::Y,
+        DVec3::Z,
+        -DVec3::Z,
+    ];
+    let faces = vec![
+        vec![0, 2, 4],
+        vec![4, 2, 1],
+        vec![1, 2, 5],
+        vec![5, 2, 0],
+        vec![4, 3, 0],
+        vec![1, 3, 4],
+        vec![5, 3, 1],
+        vec![0, 3, 5],
+    ];
+    Mesh::new(positions, faces)
+}
+
+pub fn build(aspect_ratio: f64) -> Scene {
+    let camera_desc = CameraDescriptor {
+        origin: DVec3::new(0.0, 3.0, 12.0),
+        look_at: DVec3::new(0.0, 1.0, 0.0),
+        vfov: 35.0,
+        aspect_ratio,
+        ..Default::default()
+    };
+    let camera = Camera::new(&camera_desc);
+
+    let sky = Sky::new(&SkyDescriptor {
+        sun_direction: DVec3::new(1.0, 1.5, 1.0),
+        ..Default::default()
+    });
+
+    let clay = Lambertian {
+        albedo: Solid {
+            color: DVec3::new(0.7, 0.4, 0.3),
+        },
+    };
+
+    let mut world = World::new();
+    world.add(Sphere {
+        center: DVec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Lambertian {
+            albedo: Checker {
+                even: Solid {
+                    color: DVec3::splat(0.6),
+                },
+                odd: Solid {
+                    color: DVec3::splat(0.3),
+                },
+                scale: 1.0,
+            },
+        },
+    });
+
+    // The control cage, and the smooth surfaces after each kind of
+    // subdivision.
+    let cube = cube();
+    world.add(Translate {
+        inner: cube.to_bvh(clay.clone()),
+        offset: DVec3::new(-3.6, 0.5, 0.0),
+    });
+    world.add(Translate {
+        inner: cube.subdivide_catmull_clark(4).to_bvh(clay.clone()),
+        offset: DVec3::new(-1.4, 0.5, 0.0),
+    });
+    world.add(Translate {
+        inner: cube.subdivide_loop(4).to_bvh(clay),
+        offset: DVec3::new(0.8, 0.5, 0.0),
+    });
+
+    // A rock, displaced by noise.
+    let noise = Fbm {
+        perlin: Arc::new(Perlin::new(4)),
+        scale: 2.0,
+        octaves: 5,
+        lacunarity: 2.0,
+        gain: 0.5,
+    };
+    let rock = octahedron().subdivide_loop(6).displace(&noise, 0.3);
+    world.add(Translate {
+        inner: rock.to_bvh(Metal {
+            albedo: DVec3::new(0.8, 0.7, 0.6),
+            fuzz: 0.3,
+        }),
+        offset: DVec3::new(3.3, 0.9, 0.0),
+    });
+
+    Scene {
+        world,
+        camera,
+        lights: vec![Box::new(sky.sun())],
+        background: Box::new(sky),
+    }
+}
@@ -0,0 +1,88 @@
+use glam::DVec3;
+
+use crate::{
+    camera::{Camera, CameraDescriptor},
+    hittable::{Sphere, World},
+    material::{Conductor, Dielectric, Lambertian},
+    sky::{Sky, SkyDescriptor},
+    texture::Solid,
+};
+
+use super::Scene;
+
+pub fn build(aspect_ratio: f64) -> Scene {
+    let camera_desc = CameraDescriptor {
+        origin: DVec3::new(13.0, 2.0, 3.0),
+        look_at: DVec3::new(0.0, 1.0, 0.0),
+        vfov: 25.0,
+        aspect_ratio,
+        ..Default::default()
+    };
+    let camera = Camera::new(&camera_desc);
+
+    let sky = Sky::new(&SkyDescriptor {
+        sun_direction: DVec3::new(-1.0, 0.8, 1.5),
+        turbidity: 2.5,
+        ..Default::default()
+    });
+
+    let mut world = World::new();
+    world.add(Sphere {
+        center: DVec3::new(0.0, -1000.0, 0.0),
+        radius: 1000.0,
+        material: Lambertian {
+            albedo: Solid {
+                color: DVec3::new(0.5, 0.5, 0.45),
+            },
+        },
+    });
+    world.add(Sphere {
+        center: DVec3::new(-4.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Lambertian {
+            albedo: Solid {
+                color: DVec3::new(0.7, 0.2, 0.1),
+            },
+        },
+    });
+    world.add(Sphere {
+        center: DVec3::new(0.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Dielectric {
+            ir: 1.5,
+            ..Default::default()
+        },
+    });
+    world.add(Sphere {
+        center: DVec3::new(4.0, 1.0, 0.0),
+        radius: 1.0,
+        material: Conductor::gold(0.25),
+    });
+    world.add(Sphere {
+        center: DVec3::new(2.5, 0.4, 2.0),
+        radius: 0.4,
+        material: Conductor::copper(0.5),
+    });
+    world.add(Sphere {
+        center: DVec3::new(1.5, 0.4, 3.0),
+        radius: 0.4,
+        material: Conductor::aluminium(0.0),
+    });
+    world.add(Sphere {
+        center: DVec3::new(6.5, 0.5, 1.5),
+        radius: 0.5,
+        material: Dielectric {
+            ir: 1.5,
+            roughness: 0.3,
+            tint: DVec3::new(0.2, 0.6, 0.9),
+            ..Default::default()
+        },
+    });
+
+    Scene {
+        world,
+        camera,
+        lights: vec![Box::new(sky.sun())],
+        background: Box::new(sky),
+    }
+}
@@ -0,0 +1,67 @@
+use glam::DVec3;
+
+use crate::{
+    camera::{Camera, CameraDescriptor},
+    heightfield::{HeightMap, Heightfield},
+    hittable::World,
+    material::Lambertian,
+    perlin::Perlin,
+    sky::{Sky, SkyDescriptor},
+    texture::{ColorRamp, ColorStop, Gradient},
+};
+
+use super::Scene;
+
+pub fn build(aspect_ratio: f64) -> anyhow::Result<Scene> {
+    let camera_desc = CameraDescriptor {
+        origin: DVec3::new(0.0, 14.0, 40.0),
+        look_at: DVec3::new(0.0, 2.0, 0.0),
+        vfov: 40.0,
+        aspect_ratio,
+        ..Default::default()
+    };
+    let camera = Camera::new(&camera_desc);
+
+    let sky = Sky::new(&SkyDescriptor {
+        sun_direction: DVec3::new(-1.0, 0.6, -0.3),
+        ..Default::default()
+    });
+
+    let height = 12.0;
+    let map = HeightMap::from_noise(&Perlin::new(3), 512, 512, 6.0, 8)?;
+    let mut world = World::new();
+    world.add(Heightfield::new(
+        map,
+        DVec3::new(-30.0, -0.5 * height, -40.0),
+        DVec3::new(60.0, height, 60.0),
+        Lambertian {
+            albedo: ColorRamp {
+                input: Gradient {
+                    origin: DVec3::new(0.0, -0.5 * height, 0.0),
+                    axis: DVec3::new(0.0, height, 0.0),
+                },
+                stops: vec![
+                    ColorStop {
+                        position: 0.45,
+                        color: DVec3::new(0.15, 0.3, 0.1),
+                    },
+                    ColorStop {
+                        position: 0.6,
+                        color: DVec3::new(0.35, 0.3, 0.2),
+                    },
+                    ColorStop {
+                        position: 0.75,
+                        color: DVec3::new(0.9, 0.9, 0.9),
+                    },
+                ],
+            },
+        },
+    )?);
+
+    Ok(Scene {
+        world,
+        camera,
+        lights: vec![Box::new(sky.sun())],
+        background: Box::new(sky),
+    })
+}
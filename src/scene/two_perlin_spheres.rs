@@ -6,7 +6,7 @@ use crate::{
     camera::{Camera, CameraDescriptor},
     hittable::{Sphere, World},
     material::Lambertian,
-    texture::Noise,
+    texture::Marble,
 };
 
 use super::Scene;
@@ -22,7 +22,7 @@ pub fn build(aspect_ratio: f64) -> Scene {
     let camera = Camera::new(&camera_desc);
 
     let noise = Arc::new(Lambertian {
-        albedo: Noise::new(&mut rand::thread_rng(), 4.0),
+        albedo: Marble::new(0, 4.0),
     });
 
     let mut world = World::new();
@@ -40,6 +40,7 @@ pub fn build(aspect_ratio: f64) -> Scene {
     Scene {
         world,
         camera,
-        background: DVec3::new(0.7, 0.8, 1.0),
+        background: Box::new(DVec3::new(0.7, 0.8, 1.0)),
+        lights: Vec::new(),
     }
 }
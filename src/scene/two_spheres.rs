@@ -31,6 +31,7 @@ pub fn build(aspect_ratio: f64) -> Scene {
             odd: Solid {
                 color: DVec3::new(0.9, 0.9, 0.9),
             },
+            scale: 3.0,
         },
     });
 
@@ -48,6 +49,7 @@ pub fn build(aspect_ratio: f64) -> Scene {
     Scene {
         world,
         camera,
-        background: DVec3::new(0.7, 0.8, 1.0),
+        background: Box::new(DVec3::new(0.7, 0.8, 1.0)),
+        lights: Vec::new(),
     }
 }
@@ -0,0 +1,435 @@
+use glam::{DQuat, DVec2, DVec3, Vec3Swizzles};
+
+use crate::{
+    hittable::{compute_face_normal, Aabb, HitRecord, Hittable},
+    material::Material,
+    microfacet::Frame,
+    ray::Ray,
+};
+
+/// A signed distance function, negative inside the shape.
+pub trait Distance: Send + Sync {
+    /// Distance from `point` to the surface. It may underestimate, but never
+    /// overestimate, the true distance.
+    fn distance(&self, point: DVec3) -> f64;
+
+    /// Box containing the whole shape, or `None` if it is unbounded or not
+    /// known.
+    fn bounds(&self) -> Option<Aabb> {
+        None
+    }
+}
+
+impl<F: Fn(DVec3) -> f64 + Send + Sync> Distance for F {
+    fn distance(&self, point: DVec3) -> f64 {
+        self(point)
+    }
+}
+
+/// A surface where a [`Distance`] is zero, found by sphere tracing.
+#[derive(Debug, Clone)]
+pub struct Sdf<D, Mat> {
+    pub shape: D,
+    pub material: Mat,
+    /// Region the ray is marched through. Without it, marching goes on until
+    /// `max_steps` or `max_distance` run out, and the shape can't be put in a
+    /// [`BvhNode`](crate::hittable::BvhNode).
+    pub bounds: Option<Aabb>,
+    pub max_steps: usize,
+    pub max_distance: f64,
+    /// Distance below which the surface counts as hit.
+    pub epsilon: f64,
+    /// Fraction of the distance to step, below 1 for functions which
+    /// overestimate, e.g. after a displacement.
+    pub step_scale: f64,
+}
+
+impl<D: Distance, Mat> Sdf<D, Mat> {
+    pub fn new(shape: D, material: Mat) -> Self {
+        Self {
+            bounds: shape.bounds(),
+            shape,
+            material,
+            max_steps: 256,
+            max_distance: 1.0e3,
+            epsilon: 1.0e-4,
+            step_scale: 1.0,
+        }
+    }
+}
+
+impl<D: Distance, Mat> Sdf<D, Mat> {
+    /// Normal from the gradient of the distance, with the four samples of
+    /// the tetrahedron technique.
+    fn normal(&self, point: DVec3) -> DVec3 {
+        let h = self.epsilon;
+        let gradient = [
+            DVec3::new(1.0, -1.0, -1.0),
+            DVec3::new(-1.0, -1.0, 1.0),
+            DVec3::new(-1.0, 1.0, -1.0),
+            DVec3::new(1.0, 1.0, 1.0),
+        ]
+        .into_iter()
+        .fold(DVec3::ZERO, |sum, k| {
+            sum + k * self.shape.distance(point + h * k)
+        });
+        gradient.normalize_or_zero()
+    }
+}
+
+impl<D: Distance, Mat: Material> Hittable for Sdf<D, Mat> {
+    fn hit<'a>(&'a self, ray: &Ray, t_min: f64, t_max: f64) -> Option<HitRecord<'a>> {
+        let (t_start, t_end) = match &self.bounds {
+            Some(bounds) => bounds.clip(ray, t_min, t_max)?,
+            None => (t_min, t_max),
+        };
+        let length = ray.direction.length();
+        let t_end = t_end.min(t_start + self.max_distance / length);
+
+        // March from the side of the surface the ray starts on. A hit only
+        // counts while approaching the surface, which takes at least one
+        // step to tell, so rays leaving it, e.g. after a bounce, don't hit
+        // it again right away.
+        let sign = self.shape.distance(ray.at(t_start)).signum();
+        let mut t = t_start;
+        let mut previous = f64::INFINITY;
+        let mut steps = 0;
+        loop {
+            let distance = sign * self.shape.distance(ray.at(t));
+            if steps > 0 && distance < self.epsilon && distance < previous {
+                break;
+            }
+            previous = distance;
+            t += self.step_scale * distance.max(self.epsilon) / length;
+            steps += 1;
+            if t > t_end || steps >= self.max_steps {
+                return None;
+            }
+        }
+
+        let point = ray.at(t);
+        let outward_normal = self.normal(point);
+        let (normal, face) = compute_face_normal(ray, outward_normal);
+        let frame = Frame::from_normal(outward_normal);
+        Some(HitRecord {
+            t,
+            point,
+            normal,
+            object_point: point,
+            object_rotation: DQuat::IDENTITY,
+            uv: DVec2::ZERO,
+            dpdu: frame.tangent,
+            dpdv: frame.bitangent,
+            face,
+            color: DVec3::ONE,
+            material: &self.material,
+        })
+    }
+
+    fn bounding_box(&self, _start_time: f64, _end_time: f64) -> Option<Aabb> {
+        self.bounds.map(|bounds| Aabb {
+            minimum: bounds.minimum - DVec3::splat(self.epsilon),
+            maximum: bounds.maximum + DVec3::splat(self.epsilon),
+        })
+    }
+}
+
+fn bounds_around(center: DVec3, half_extent: DVec3) -> Option<Aabb> {
+    Some(Aabb {
+        minimum: center - half_extent,
+        maximum: center + half_extent,
+    })
+}
+
+fn grow(bounds: Option<Aabb>, amount: f64) -> Option<Aabb> {
+    bounds.map(|bounds| Aabb {
+        minimum: bounds.minimum - DVec3::splat(amount),
+        maximum: bounds.maximum + DVec3::splat(amount),
+    })
+}
+
+fn union_bounds(a: Option<Aabb>, b: Option<Aabb>) -> Option<Aabb> {
+    Some(a?.union(&b?))
+}
+
+fn intersection_bounds(a: Option<Aabb>, b: Option<Aabb>) -> Option<Aabb> {
+    match (a, b) {
+        (Some(a), Some(b)) => {
+            let minimum = a.minimum.max(b.minimum);
+            Some(Aabb {
+                minimum,
+                maximum: a.maximum.min(b.maximum).max(minimum),
+            })
+        }
+        (a, b) => a.or(b),
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Sphere {
+    pub center: DVec3,
+    pub radius: f64,
+}
+
+impl Distance for Sphere {
+    fn distance(&self, point: DVec3) -> f64 {
+        (point - self.center).length() - self.radius
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        bounds_around(self.center, DVec3::splat(self.radius))
+    }
+}
+
+/// A box with edges rounded off by `radius`, which extends beyond
+/// `half_extent`.
+#[derive(Debug, Clone)]
+pub struct RoundBox {
+    pub center: DVec3,
+    pub half_extent: DVec3,
+    pub radius: f64,
+}
+
+impl Distance for RoundBox {
+    fn distance(&self, point: DVec3) -> f64 {
+        let q = (point - self.center).abs() - self.half_extent;
+        q.max(DVec3::ZERO).length() + q.max_element().min(0.0) - self.radius
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        bounds_around(self.center, self.half_extent + self.radius)
+    }
+}
+
+/// A torus around the vertical axis through `center`.
+#[derive(Debug, Clone)]
+pub struct Torus {
+    pub center: DVec3,
+    pub major_radius: f64,
+    pub minor_radius: f64,
+}
+
+impl Distance for Torus {
+    fn distance(&self, point: DVec3) -> f64 {
+        let p = point - self.center;
+        let q = DVec2::new(p.xz().length() - self.major_radius, p.y);
+        q.length() - self.minor_radius
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        let r = self.major_radius + self.minor_radius;
+        bounds_around(self.center, DVec3::new(r, self.minor_radius, r))
+    }
+}
+
+/// The points within `radius` of the segment from `a` to `b`.
+#[derive(Debug, Clone)]
+pub struct Capsule {
+    pub a: DVec3,
+    pub b: DVec3,
+    pub radius: f64,
+}
+
+impl Distance for Capsule {
+    fn distance(&self, point: DVec3) -> f64 {
+        let pa = point - self.a;
+        let ba = self.b - self.a;
+        let h = (pa.dot(ba) / ba.length_squared()).clamp(0.0, 1.0);
+        (pa - h * ba).length() - self.radius
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        Some(Aabb {
+            minimum: self.a.min(self.b) - DVec3::splat(self.radius),
+            maximum: self.a.max(self.b) + DVec3::splat(self.radius),
+        })
+    }
+}
+
+/// The Mandelbulb fractal of the given `power`, scaled by `scale` around
+/// `center`, using its distance estimate.
+#[derive(Debug, Clone)]
+pub struct Mandelbulb {
+    pub center: DVec3,
+    pub scale: f64,
+    pub power: f64,
+    pub iterations: usize,
+}
+
+impl Distance for Mandelbulb {
+    fn distance(&self, point: DVec3) -> f64 {
+        let c = (point - self.center) / self.scale;
+        let mut z = c;
+        let mut dr = 1.0;
+        let mut r = z.length();
+        for _ in 0..self.iterations {
+            if r > 2.0 {
+                break;
+            }
+            let theta = (z.z / r).clamp(-1.0, 1.0).acos() * self.power;
+            let phi = z.y.atan2(z.x) * self.power;
+            dr = r.powf(self.power - 1.0) * self.power * dr + 1.0;
+            let zr = r.powf(self.power);
+            z =
+                zr * DVec3::new(
+                    theta.sin() * phi.cos(),
+                    theta.sin() * phi.sin(),
+                    theta.cos(),
+                ) + c;
+            r = z.length();
+        }
+        self.scale * 0.5 * r.ln() * r / dr
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        bounds_around(self.center, DVec3::splat(1.2 * self.scale))
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Union<A, B> {
+    pub a: A,
+    pub b: B,
+}
+
+impl<A: Distance, B: Distance> Distance for Union<A, B> {
+    fn distance(&self, point: DVec3) -> f64 {
+        self.a.distance(point).min(self.b.distance(point))
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        union_bounds(self.a.bounds(), self.b.bounds())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Intersection<A, B> {
+    pub a: A,
+    pub b: B,
+}
+
+impl<A: Distance, B: Distance> Distance for Intersection<A, B> {
+    fn distance(&self, point: DVec3) -> f64 {
+        self.a.distance(point).max(self.b.distance(point))
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        intersection_bounds(self.a.bounds(), self.b.bounds())
+    }
+}
+
+/// `a` with `b` cut out.
+#[derive(Debug, Clone)]
+pub struct Difference<A, B> {
+    pub a: A,
+    pub b: B,
+}
+
+impl<A: Distance, B: Distance> Distance for Difference<A, B> {
+    fn distance(&self, point: DVec3) -> f64 {
+        self.a.distance(point).max(-self.b.distance(point))
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        self.a.bounds()
+    }
+}
+
+/// Polynomial smooth minimum, which differs from `min` only where `a` and
+/// `b` are within `k` of each other, and by at most `k / 4`.
+fn smooth_min(a: f64, b: f64, k: f64) -> f64 {
+    if k <= 0.0 {
+        return a.min(b);
+    }
+    let h = (k - (a - b).abs()).max(0.0) / k;
+    a.min(b) - 0.25 * h * h * k
+}
+
+/// Union that fills in the seams between `a` and `b` over a distance of
+/// about `smoothness`.
+#[derive(Debug, Clone)]
+pub struct SmoothUnion<A, B> {
+    pub a: A,
+    pub b: B,
+    pub smoothness: f64,
+}
+
+impl<A: Distance, B: Distance> Distance for SmoothUnion<A, B> {
+    fn distance(&self, point: DVec3) -> f64 {
+        smooth_min(
+            self.a.distance(point),
+            self.b.distance(point),
+            self.smoothness,
+        )
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        grow(
+            union_bounds(self.a.bounds(), self.b.bounds()),
+            0.25 * self.smoothness,
+        )
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SmoothIntersection<A, B> {
+    pub a: A,
+    pub b: B,
+    pub smoothness: f64,
+}
+
+impl<A: Distance, B: Distance> Distance for SmoothIntersection<A, B> {
+    fn distance(&self, point: DVec3) -> f64 {
+        -smooth_min(
+            -self.a.distance(point),
+            -self.b.distance(point),
+            self.smoothness,
+        )
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        intersection_bounds(self.a.bounds(), self.b.bounds())
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SmoothDifference<A, B> {
+    pub a: A,
+    pub b: B,
+    pub smoothness: f64,
+}
+
+impl<A: Distance, B: Distance> Distance for SmoothDifference<A, B> {
+    fn distance(&self, point: DVec3) -> f64 {
+        -smooth_min(
+            -self.a.distance(point),
+            self.b.distance(point),
+            self.smoothness,
+        )
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        self.a.bounds()
+    }
+}
+
+/// Morphs from `a` at a `factor` of 0 to `b` at 1 by interpolating the
+/// distances.
+#[derive(Debug, Clone)]
+pub struct Blend<A, B> {
+    pub a: A,
+    pub b: B,
+    pub factor: f64,
+}
+
+impl<A: Distance, B: Distance> Distance for Blend<A, B> {
+    fn distance(&self, point: DVec3) -> f64 {
+        let a = self.a.distance(point);
+        let b = self.b.distance(point);
+        a + self.factor * (b - a)
+    }
+
+    fn bounds(&self) -> Option<Aabb> {
+        union_bounds(self.a.bounds(), self.b.bounds())
+    }
+}
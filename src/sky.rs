@@ -0,0 +1,214 @@
+use std::f64::consts as f64;
+
+use glam::DVec3;
+
+use crate::light::Sun;
+
+/// Radiance arriving from directions in which a ray escapes the scene.
+pub trait Background: Send + Sync {
+    /// `direction` is normalized.
+    fn value(&self, direction: DVec3) -> DVec3;
+}
+
+impl Background for DVec3 {
+    fn value(&self, _direction: DVec3) -> DVec3 {
+        *self
+    }
+}
+
+impl<B: Background + ?Sized> Background for Box<B> {
+    fn value(&self, direction: DVec3) -> DVec3 {
+        B::value(self, direction)
+    }
+}
+
+/// Illuminance of the sun outside of the atmosphere, in klx.
+const EXTRATERRESTRIAL_SUN_ILLUMINANCE: f64 = 128.0;
+
+/// Representative wavelengths (in micrometers) of the red, green and blue
+/// channels, used to evaluate the spectral attenuation of sunlight.
+const RGB_WAVELENGTHS: [f64; 3] = [0.680, 0.550, 0.440];
+
+/// Ozone absorption coefficients (in 1/cm) at `RGB_WAVELENGTHS`.
+const OZONE_ABSORPTION: [f64; 3] = [0.040, 0.085, 0.003];
+
+/// Thickness of the ozone layer in cm.
+const OZONE_THICKNESS: f64 = 0.35;
+
+#[derive(Debug, Clone)]
+pub struct SkyDescriptor {
+    /// Direction towards the sun. Y is up.
+    pub sun_direction: DVec3,
+    /// Atmospheric turbidity, from about 2 (very clear) to 10 (hazy).
+    pub turbidity: f64,
+    /// Albedo of the ground below the horizon.
+    pub ground_albedo: DVec3,
+    /// Apparent angular diameter of the sun disk in radians.
+    pub sun_angular_diameter: f64,
+    /// Multiplier converting physical units (kcd/m² for radiance) into scene
+    /// units.
+    pub scale: f64,
+}
+
+impl Default for SkyDescriptor {
+    fn default() -> Self {
+        Self {
+            sun_direction: DVec3::new(0.0, 1.0, 1.0).normalize(),
+            turbidity: 3.0,
+            ground_albedo: DVec3::splat(0.3),
+            sun_angular_diameter: 0.53_f64.to_radians(),
+            scale: 0.03,
+        }
+    }
+}
+
+/// Coefficients of the Perez sky luminance distribution.
+#[derive(Debug, Clone, Copy)]
+struct Perez {
+    a: f64,
+    b: f64,
+    c: f64,
+    d: f64,
+    e: f64,
+}
+
+impl Perez {
+    fn eval(&self, cos_theta: f64, gamma: f64) -> f64 {
+        let cos_gamma = gamma.cos();
+        (1.0 + self.a * (self.b / cos_theta.max(0.01)).exp())
+            * (1.0 + self.c * (self.d * gamma).exp() + self.e * cos_gamma * cos_gamma)
+    }
+}
+
+/// Analytic daylight model by Preetham, Shirley and Smits.
+#[derive(Debug, Clone)]
+pub struct Sky {
+    sun_direction: DVec3,
+    /// Perez distributions for the Y, x and y channels.
+    perez: [Perez; 3],
+    /// Zenith values divided by the Perez distribution at the zenith.
+    normalized_zenith: [f64; 3],
+    ground: DVec3,
+    sun: Sun,
+}
+
+impl Sky {
+    pub fn new(desc: &SkyDescriptor) -> Self {
+        let t = desc.turbidity;
+        let sun_direction = desc.sun_direction.normalize();
+        let theta_s = sun_direction.y.clamp(-1.0, 1.0).acos();
+
+        let perez = [
+            Perez {
+                a: 0.1787 * t - 1.4630,
+                b: -0.3554 * t + 0.4275,
+                c: -0.0227 * t + 5.3251,
+                d: 0.1206 * t - 2.5771,
+                e: -0.0670 * t + 0.3703,
+            },
+            Perez {
+                a: -0.0193 * t - 0.2592,
+                b: -0.0665 * t + 0.0008,
+                c: -0.0004 * t + 0.2125,
+                d: -0.0641 * t - 0.8989,
+                e: -0.0033 * t + 0.0452,
+            },
+            Perez {
+                a: -0.0167 * t - 0.2608,
+                b: -0.0950 * t + 0.0092,
+                c: -0.0079 * t + 0.2102,
+                d: -0.0441 * t - 1.6537,
+                e: -0.0109 * t + 0.0529,
+            },
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (f64::PI - 2.0 * theta_s);
+        let zenith_luminance = ((4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192).max(0.0);
+        let theta = DVec3::new(theta_s.powi(3), theta_s.powi(2), theta_s);
+        let zenith_x = t * t * theta.dot(DVec3::new(0.00166, -0.00375, 0.00209))
+            + t * (theta.dot(DVec3::new(-0.02903, 0.06377, -0.03202)) + 0.00394)
+            + theta.dot(DVec3::new(0.11693, -0.21196, 0.06052))
+            + 0.25886;
+        let zenith_y = t * t * theta.dot(DVec3::new(0.00275, -0.00610, 0.00317))
+            + t * (theta.dot(DVec3::new(-0.04214, 0.08970, -0.04153)) + 0.00516)
+            + theta.dot(DVec3::new(0.15346, -0.26756, 0.06670))
+            + 0.26688;
+        let zenith = [desc.scale * zenith_luminance, zenith_x, zenith_y];
+
+        let mut normalized_zenith = [0.0; 3];
+        for i in 0..3 {
+            normalized_zenith[i] = zenith[i] / perez[i].eval(1.0, theta_s);
+        }
+
+        let sun_irradiance =
+            desc.scale * EXTRATERRESTRIAL_SUN_ILLUMINANCE * sun_transmittance(t, theta_s);
+        let sun = Sun {
+            direction: sun_direction,
+            angular_diameter: desc.sun_angular_diameter,
+            irradiance: sun_irradiance,
+        };
+
+        // The ground is treated as a Lambertian plane lit by the sun and a
+        // uniform sky as bright as the zenith.
+        let sky_irradiance = f64::PI * yxy_to_rgb(zenith[0], zenith[1], zenith[2]);
+        let ground = desc.ground_albedo / f64::PI
+            * (sun_irradiance * sun_direction.y.max(0.0) + sky_irradiance);
+
+        Self {
+            sun_direction,
+            perez,
+            normalized_zenith,
+            ground,
+            sun,
+        }
+    }
+
+    /// The sun light matching this sky, to be sampled explicitly.
+    pub fn sun(&self) -> Sun {
+        self.sun.clone()
+    }
+}
+
+impl Background for Sky {
+    fn value(&self, direction: DVec3) -> DVec3 {
+        if direction.y < 0.0 {
+            return self.ground;
+        }
+        let gamma = direction.dot(self.sun_direction).clamp(-1.0, 1.0).acos();
+        let [luminance, x, y] =
+            [0, 1, 2].map(|i| self.normalized_zenith[i] * self.perez[i].eval(direction.y, gamma));
+        yxy_to_rgb(luminance, x, y)
+    }
+}
+
+/// Fraction of extraterrestrial sunlight that reaches the ground in each RGB
+/// channel, accounting for Rayleigh scattering, aerosols and ozone.
+fn sun_transmittance(turbidity: f64, theta_s: f64) -> DVec3 {
+    if theta_s >= f64::FRAC_PI_2 {
+        return DVec3::ZERO;
+    }
+    let relative_mass = 1.0 / (theta_s.cos() + 0.15 * (93.885 - theta_s.to_degrees()).powf(-1.253));
+    let beta = 0.04608 * turbidity - 0.04586;
+    let alpha = 1.3;
+    let channel = |i: usize| {
+        let lambda: f64 = RGB_WAVELENGTHS[i];
+        let rayleigh = (-0.008735 * lambda.powf(-4.08) * relative_mass).exp();
+        let aerosol = (-beta * lambda.powf(-alpha) * relative_mass).exp();
+        let ozone = (-OZONE_ABSORPTION[i] * OZONE_THICKNESS * relative_mass).exp();
+        rayleigh * aerosol * ozone
+    };
+    DVec3::new(channel(0), channel(1), channel(2))
+}
+
+fn yxy_to_rgb(luminance: f64, x: f64, y: f64) -> DVec3 {
+    if y <= 0.0 {
+        return DVec3::ZERO;
+    }
+    let xyz = DVec3::new(x / y * luminance, luminance, (1.0 - x - y) / y * luminance);
+    let rgb = DVec3::new(
+        xyz.dot(DVec3::new(3.2406, -1.5372, -0.4986)),
+        xyz.dot(DVec3::new(-0.9689, 1.8758, 0.0415)),
+        xyz.dot(DVec3::new(0.0557, -0.2040, 1.0570)),
+    );
+    rgb.max(DVec3::ZERO)
+}
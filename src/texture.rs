@@ -1,24 +1,68 @@
-use ::image::{GenericImageView, Pixel, Primitive, Rgb};
-use glam::{DVec2, DVec3, UVec2};
-use num_traits::ToPrimitive;
-use rand::Rng;
+use glam::{DVec2, DVec3, IVec3};
 use std::{fmt::Debug, sync::Arc};
 
-use crate::perlin::Perlin;
+use crate::{
+    hittable::HitRecord,
+    material::luminance,
+    mipmap::{Filter, MipMap, Wrap},
+    perlin::Perlin,
+    ray::Ray,
+};
 
-pub trait Texture {
-    fn value(&self, uv: DVec2, point: DVec3) -> DVec3;
+/// Everything a texture can depend on at a shaded point.
+#[derive(Debug, Clone, Copy)]
+pub struct TextureContext {
+    pub uv: DVec2,
+    /// Extent in uv space of the area to filter over.
+    pub uv_footprint: DVec2,
+    pub point: DVec3,
+    /// Shading normal, facing against `direction`.
+    pub normal: DVec3,
+    /// `point` and `normal` in the space of the primitive, so that solid
+    /// textures move along with transformed objects.
+    pub object_point: DVec3,
+    pub object_normal: DVec3,
+    /// Normalized direction of the incoming ray.
+    pub direction: DVec3,
+    pub time: f64,
+    /// Width in scene units of the area to filter over.
+    pub width: f64,
+    /// Color of the vertices or particles around the point.
+    pub color: DVec3,
 }
 
-impl<T: Texture> Texture for &T {
-    fn value(&self, uv: DVec2, point: DVec3) -> DVec3 {
-        T::value(*self, uv, point)
+impl TextureContext {
+    pub fn new(ray: &Ray, hit: &HitRecord) -> Self {
+        Self {
+            uv: hit.uv,
+            uv_footprint: hit.uv_footprint(ray),
+            point: hit.point,
+            normal: hit.normal,
+            object_point: hit.object_point,
+            object_normal: hit.object_rotation.inverse() * hit.normal,
+            direction: ray.direction.normalize(),
+            time: ray.time,
+            width: ray.width_at(hit.t),
+            color: hit.color,
+        }
     }
 }
 
-impl<T: Texture> Texture for Arc<T> {
-    fn value(&self, uv: DVec2, point: DVec3) -> DVec3 {
-        T::value(&*self, uv, point)
+pub trait Texture: Send + Sync {
+    /// Textures that alias should average over the footprint given in
+    /// `ctx`; others can ignore it.
+    fn value(&self, ctx: &TextureContext) -> DVec3;
+}
+
+impl<T: Texture + ?Sized> Texture for &T {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        T::value(*self, ctx)
+    }
+}
+
+impl<T: Texture + ?Sized> Texture for Arc<T> {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        T::value(self, ctx)
     }
 }
 
@@ -28,15 +72,35 @@ pub struct Solid {
 }
 
 impl Texture for Solid {
-    fn value(&self, _uv: DVec2, _point: DVec3) -> DVec3 {
+    fn value(&self, _ctx: &TextureContext) -> DVec3 {
         self.color
     }
 }
 
+/// The color of the vertices or particles of the shape, e.g. of a
+/// [`SphereCloud`](crate::particles::SphereCloud).
+#[derive(Debug, Clone, Copy, Default)]
+pub struct VertexColor;
+
+impl Texture for VertexColor {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        ctx.color
+    }
+}
+
+/// A 3D checkerboard of solid cubes, `1 / scale` units wide.
 #[derive(Debug, Clone)]
 pub struct Checker<Even, Odd> {
     pub even: Even,
     pub odd: Odd,
+    pub scale: f64,
+}
+
+impl<Even, Odd> Checker<Even, Odd> {
+    fn is_odd(&self, point: DVec3) -> bool {
+        let cell = (self.scale * point).floor().as_ivec3();
+        (cell.x + cell.y + cell.z).rem_euclid(2) == 1
+    }
 }
 
 impl<Even, Odd> Texture for Checker<Even, Odd>
@@ -44,64 +108,424 @@ where
     Even: Texture,
     Odd: Texture,
 {
-    fn value(&self, uv: DVec2, point: DVec3) -> DVec3 {
-        let sines = (10.0 * point.x).sin() * (10.0 * point.y).sin() * (10.0 * point.z).sin();
-        if sines < 0.0 {
-            self.odd.value(uv, point)
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        if self.is_odd(ctx.object_point) {
+            self.odd.value(ctx)
         } else {
-            self.even.value(uv, point)
+            self.even.value(ctx)
         }
     }
 }
 
-#[derive(Debug)]
+/// A checkerboard in uv space, with `scale` squares along each axis of the
+/// unit square.
+#[derive(Debug, Clone)]
+pub struct UvChecker<Even, Odd> {
+    pub even: Even,
+    pub odd: Odd,
+    pub scale: DVec2,
+}
+
+impl<Even, Odd> UvChecker<Even, Odd> {
+    fn is_odd(&self, uv: DVec2) -> bool {
+        let cell = (self.scale * uv).floor().as_ivec2();
+        (cell.x + cell.y).rem_euclid(2) == 1
+    }
+}
+
+impl<Even, Odd> Texture for UvChecker<Even, Odd>
+where
+    Even: Texture,
+    Odd: Texture,
+{
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        // Once a square is smaller than the footprint, the pattern averages
+        // out to an even mix of both textures.
+        let squares = (ctx.uv_footprint * self.scale).max_element();
+        let even = self.even.value(ctx);
+        let odd = self.odd.value(ctx);
+        let sharp = if self.is_odd(ctx.uv) { odd } else { even };
+        sharp.lerp(0.5 * (even + odd), squares.clamp(0.0, 1.0))
+    }
+}
+
+/// Gradient noise remapped to `0..=1`.
+#[derive(Debug, Clone)]
 pub struct Noise {
-    perlin: Perlin,
-    scale: f64,
+    pub perlin: Arc<Perlin>,
+    pub scale: f64,
+    /// Rate at which the noise changes over time, or zero for static noise.
+    pub speed: f64,
 }
 
 impl Noise {
-    pub fn new<R: Rng>(rng: &mut R, scale: f64) -> Self {
+    pub fn new(seed: u64, scale: f64) -> Self {
         Self {
-            perlin: Perlin::new(rng),
+            perlin: Arc::new(Perlin::new(seed)),
             scale,
+            speed: 0.0,
         }
     }
 }
 
 impl Texture for Noise {
-    fn value(&self, _uv: DVec2, point: DVec3) -> DVec3 {
-        // DVec3::splat(0.5 * (1.0 + self.perlin.noise(point * self.scale)))
-        // DVec3::splat(self.perlin.turbulence(point * self.scale, 7))
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        let point = self.scale * ctx.object_point;
+        let noise = if self.speed == 0.0 {
+            self.perlin.noise(point)
+        } else {
+            self.perlin.noise_4(point.extend(self.speed * ctx.time))
+        };
+        DVec3::splat(0.5 * (1.0 + noise))
+    }
+}
+
+/// Fractal Brownian motion, remapped to `0..=1`.
+#[derive(Debug, Clone)]
+pub struct Fbm {
+    pub perlin: Arc<Perlin>,
+    pub scale: f64,
+    pub octaves: usize,
+    /// Frequency multiplier between successive octaves.
+    pub lacunarity: f64,
+    /// Amplitude multiplier between successive octaves.
+    pub gain: f64,
+}
+
+impl Texture for Fbm {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        let fbm = self.perlin.fbm(
+            self.scale * ctx.object_point,
+            self.octaves,
+            self.lacunarity,
+            self.gain,
+        );
+        DVec3::splat((0.5 * (1.0 + fbm)).clamp(0.0, 1.0))
+    }
+}
+
+/// Absolute value of fractal noise, giving billowy, cloud-like patterns.
+#[derive(Debug, Clone)]
+pub struct Turbulence {
+    pub perlin: Arc<Perlin>,
+    pub scale: f64,
+    pub octaves: usize,
+}
+
+impl Texture for Turbulence {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
         DVec3::splat(
-            0.5 * (1.0 + (self.scale * point.z + 10.0 * self.perlin.turbulence(point, 7)).sin()),
+            self.perlin
+                .turbulence(self.scale * ctx.object_point, self.octaves),
+        )
+    }
+}
+
+/// Sine bands along the z axis, distorted by turbulence.
+#[derive(Debug, Clone)]
+pub struct Marble {
+    pub perlin: Arc<Perlin>,
+    /// Frequency of the bands.
+    pub scale: f64,
+    /// Strength of the distortion.
+    pub turbulence: f64,
+    pub octaves: usize,
+}
+
+impl Marble {
+    pub fn new(seed: u64, scale: f64) -> Self {
+        Self {
+            perlin: Arc::new(Perlin::new(seed)),
+            scale,
+            turbulence: 10.0,
+            octaves: 7,
+        }
+    }
+}
+
+impl Texture for Marble {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        let point = ctx.object_point;
+        let turbulence = self.perlin.turbulence(point, self.octaves);
+        DVec3::splat(0.5 * (1.0 + (self.scale * point.z + self.turbulence * turbulence).sin()))
+    }
+}
+
+/// Concentric rings around the y axis, perturbed by noise. Returns the
+/// position within a ring in `0..1`, to be colored with a [`ColorRamp`].
+#[derive(Debug, Clone)]
+pub struct Wood {
+    pub perlin: Arc<Perlin>,
+    /// Number of rings per unit of distance from the axis.
+    pub rings: f64,
+    /// Frequency of the noise perturbing the rings.
+    pub scale: f64,
+    /// Strength of the perturbation, in rings.
+    pub turbulence: f64,
+}
+
+impl Texture for Wood {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        let point = ctx.object_point;
+        let radius = DVec2::new(point.x, point.z).length();
+        let noise = self.perlin.fbm(self.scale * point, 4, 2.0, 0.5);
+        DVec3::splat((self.rings * radius + self.turbulence * noise).rem_euclid(1.0))
+    }
+}
+
+/// Which distance [`Worley`] noise returns.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorleyFeature {
+    /// Distance to the closest feature point.
+    F1,
+    /// Distance to the second closest feature point.
+    F2,
+    /// Difference between the two, which outlines the cells.
+    F2MinusF1,
+}
+
+/// Cellular noise, based on the distances to feature points scattered
+/// randomly with one per unit cell.
+#[derive(Debug, Clone)]
+pub struct Worley {
+    pub scale: f64,
+    pub seed: u32,
+    pub feature: WorleyFeature,
+}
+
+impl Texture for Worley {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        let [(f1, _), (f2, _)] = closest_feature_points(self.scale * ctx.object_point, self.seed);
+        DVec3::splat(match self.feature {
+            WorleyFeature::F1 => f1,
+            WorleyFeature::F2 => f2,
+            WorleyFeature::F2MinusF1 => f2 - f1,
+        })
+    }
+}
+
+/// Cells around randomly scattered feature points, each with a random flat
+/// color.
+#[derive(Debug, Clone)]
+pub struct Voronoi {
+    pub scale: f64,
+    pub seed: u32,
+}
+
+impl Texture for Voronoi {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        let [(_, cell), _] = closest_feature_points(self.scale * ctx.object_point, self.seed);
+        let hash = hash_cell(cell, self.seed.wrapping_add(1));
+        DVec3::new(
+            unit_float(hash),
+            unit_float(hash_u32(hash)),
+            unit_float(hash_u32(hash_u32(hash))),
         )
     }
 }
 
-pub struct Image<I> {
-    pub image: I,
+/// Distances to and cells of the two feature points closest to `point`.
+fn closest_feature_points(point: DVec3, seed: u32) -> [(f64, IVec3); 2] {
+    let base = point.floor().as_ivec3();
+    let mut closest = [(f64::INFINITY, base); 2];
+    for z in -1..=1 {
+        for y in -1..=1 {
+            for x in -1..=1 {
+                let cell = base + IVec3::new(x, y, z);
+                let hash = hash_cell(cell, seed);
+                let jitter = DVec3::new(
+                    unit_float(hash),
+                    unit_float(hash_u32(hash)),
+                    unit_float(hash_u32(hash_u32(hash))),
+                );
+                let distance = point.distance(cell.as_dvec3() + jitter);
+                if distance < closest[0].0 {
+                    closest[1] = closest[0];
+                    closest[0] = (distance, cell);
+                } else if distance < closest[1].0 {
+                    closest[1] = (distance, cell);
+                }
+            }
+        }
+    }
+    closest
+}
+
+fn hash_u32(x: u32) -> u32 {
+    // Integer finalizer from "Hash Functions for GPU Rendering" (Jarzynski
+    // and Olano, 2020).
+    let state = x.wrapping_mul(747796405).wrapping_add(2891336453);
+    let word = ((state >> ((state >> 28) + 4)) ^ state).wrapping_mul(277803737);
+    (word >> 22) ^ word
+}
+
+fn hash_cell(cell: IVec3, seed: u32) -> u32 {
+    let h = hash_u32(seed ^ cell.x as u32);
+    let h = hash_u32(h ^ cell.y as u32);
+    hash_u32(h ^ cell.z as u32)
+}
+
+fn unit_float(hash: u32) -> f64 {
+    hash as f64 / (u32::MAX as f64 + 1.0)
+}
+
+/// Position along `axis` from `origin`, where `origin + axis` maps to 1.
+/// Clamped to `0..=1`.
+#[derive(Debug, Clone)]
+pub struct Gradient {
+    pub origin: DVec3,
+    pub axis: DVec3,
+}
+
+impl Texture for Gradient {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        let t = (ctx.object_point - self.origin).dot(self.axis) / self.axis.length_squared();
+        DVec3::splat(t.clamp(0.0, 1.0))
+    }
+}
+
+/// Interpolates between `a` and `b` by the luminance of `factor`.
+#[derive(Debug, Clone)]
+pub struct Mix<A, B, F> {
+    pub a: A,
+    pub b: B,
+    pub factor: F,
+}
+
+impl<A: Texture, B: Texture, F: Texture> Texture for Mix<A, B, F> {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        let t = luminance(self.factor.value(ctx)).clamp(0.0, 1.0);
+        self.a.value(ctx).lerp(self.b.value(ctx), t)
+    }
+}
+
+/// Component-wise product of two textures.
+#[derive(Debug, Clone)]
+pub struct Multiply<A, B> {
+    pub a: A,
+    pub b: B,
+}
+
+impl<A: Texture, B: Texture> Texture for Multiply<A, B> {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        self.a.value(ctx) * self.b.value(ctx)
+    }
+}
+
+/// One channel of `inner` in all three, e.g. to read the metalness from the
+/// blue channel of a packed image.
+#[derive(Debug, Clone)]
+pub struct Channel<T> {
+    pub inner: T,
+    /// 0 for red, 1 for green and 2 for blue.
+    pub channel: usize,
+}
+
+impl<T: Texture> Texture for Channel<T> {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        DVec3::splat(self.inner.value(ctx)[self.channel])
+    }
+}
+
+/// Linearly maps each channel of `inner` from `from` to `to`, optionally
+/// clamping the result to `to`.
+#[derive(Debug, Clone)]
+pub struct Remap<T> {
+    pub inner: T,
+    pub from: (f64, f64),
+    pub to: (f64, f64),
+    pub clamp: bool,
+}
+
+impl<T> Remap<T> {
+    fn remap(&self, value: DVec3) -> DVec3 {
+        let t = (value - self.from.0) / (self.from.1 - self.from.0);
+        let mapped = self.to.0 + t * (self.to.1 - self.to.0);
+        if self.clamp {
+            let low = self.to.0.min(self.to.1);
+            let high = self.to.0.max(self.to.1);
+            mapped.clamp(DVec3::splat(low), DVec3::splat(high))
+        } else {
+            mapped
+        }
+    }
+}
+
+impl<T: Texture> Texture for Remap<T> {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        self.remap(self.inner.value(ctx))
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct ColorStop {
+    pub position: f64,
+    pub color: DVec3,
 }
 
-impl<I: GenericImageView> Texture for Image<I> {
-    fn value(&self, uv: DVec2, _point: DVec3) -> DVec3 {
-        let dims = UVec2::from(self.image.dimensions());
-        let pixel_coordinate = (uv * dims.as_dvec2())
-            .clamp(DVec2::ZERO, (dims - 1).as_dvec2())
-            .as_uvec2();
-        let pixel = self.image.get_pixel(pixel_coordinate.x, pixel_coordinate.y);
+/// Maps the luminance of `input` to a color by interpolating between
+/// `stops`, which must be sorted by position.
+#[derive(Debug, Clone)]
+pub struct ColorRamp<T> {
+    pub input: T,
+    pub stops: Vec<ColorStop>,
+}
+
+impl<T> ColorRamp<T> {
+    fn color(&self, t: f64) -> DVec3 {
+        let upper = self.stops.partition_point(|stop| stop.position <= t);
+        match (upper.checked_sub(1), self.stops.get(upper)) {
+            (Some(i), Some(b)) => {
+                let a = self.stops[i];
+                let span = b.position - a.position;
+                let t = if span > 0.0 {
+                    (t - a.position) / span
+                } else {
+                    0.0
+                };
+                a.color.lerp(b.color, t)
+            }
+            (Some(i), None) => self.stops[i].color,
+            (None, Some(b)) => b.color,
+            (None, None) => DVec3::ZERO,
+        }
+    }
+}
+
+impl<T: Texture> Texture for ColorRamp<T> {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        self.color(luminance(self.input.value(ctx)))
+    }
+}
+
+/// A texture looked up from an image, with (0, 0) at its top left corner.
+#[derive(Debug, Clone)]
+pub struct Image {
+    pub mipmap: Arc<MipMap>,
+    pub filter: Filter,
+    pub wrap: Wrap,
+    /// Transformation applied to uv coordinates before the lookup, as
+    /// `uv * uv_scale + uv_offset`.
+    pub uv_scale: DVec2,
+    pub uv_offset: DVec2,
+}
+
+impl Image {
+    pub fn new(mipmap: Arc<MipMap>) -> Self {
+        Self {
+            mipmap,
+            filter: Filter::Trilinear,
+            wrap: Wrap::Repeat,
+            uv_scale: DVec2::ONE,
+            uv_offset: DVec2::ZERO,
+        }
+    }
+}
 
-        // TODO non-linear color spaces
-        let Rgb([r, g, b]) = pixel.to_rgb();
-        let r = r.to_f64().unwrap();
-        let g = g.to_f64().unwrap();
-        let b = b.to_f64().unwrap();
-        let min = <I::Pixel as Pixel>::Subpixel::DEFAULT_MIN_VALUE
-            .to_f64()
-            .unwrap();
-        let max = <I::Pixel as Pixel>::Subpixel::DEFAULT_MAX_VALUE
-            .to_f64()
-            .unwrap();
-        (DVec3::new(r, g, b) - min) / (max - min)
+impl Texture for Image {
+    fn value(&self, ctx: &TextureContext) -> DVec3 {
+        let uv = ctx.uv * self.uv_scale + self.uv_offset;
+        let footprint = ctx.uv_footprint * self.uv_scale.abs();
+        self.mipmap.lookup(uv, footprint, self.filter, self.wrap)
     }
 }
@@ -0,0 +1,81 @@
+use std::{
+    collections::HashMap,
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use anyhow::Context;
+
+use crate::mipmap::{ColorSpace, MipMap, TexelFormat};
+
+#[derive(Debug)]
+struct Loaded {
+    color_space: ColorSpace,
+    format: TexelFormat,
+    mipmap: Arc<MipMap>,
+}
+
+/// Loads image textures, decoding each file only once and sharing the result
+/// between all materials that use it.
+#[derive(Debug)]
+pub struct TextureManager {
+    /// Format in which newly loaded textures are stored.
+    pub format: TexelFormat,
+    loaded: HashMap<PathBuf, Vec<Loaded>>,
+}
+
+impl TextureManager {
+    pub fn new(format: TexelFormat) -> Self {
+        Self {
+            format,
+            loaded: HashMap::new(),
+        }
+    }
+
+    /// Returns the texture at `path` decoded with `color_space`, loading it
+    /// if this is the first request for it.
+    pub fn load(
+        &mut self,
+        path: impl AsRef<Path>,
+        color_space: ColorSpace,
+    ) -> anyhow::Result<Arc<MipMap>> {
+        let path = path.as_ref();
+        let key = path
+            .canonicalize()
+            .with_context(|| format!("failed to load texture {}", path.display()))?;
+        let cached = self.loaded.get(&key).and_then(|entries| {
+            entries
+                .iter()
+                .find(|l| l.color_space == color_space && l.format == self.format)
+                .map(|l| l.mipmap.clone())
+        });
+        if let Some(mipmap) = cached {
+            return Ok(mipmap);
+        }
+
+        let image = image::open(path)
+            .with_context(|| format!("failed to load texture {}", path.display()))?;
+        let mipmap = Arc::new(MipMap::with_format(&image, color_space, self.format));
+        self.loaded.entry(key).or_default().push(Loaded {
+            color_space,
+            format: self.format,
+            mipmap: mipmap.clone(),
+        });
+        Ok(mipmap)
+    }
+
+    /// Number of distinct textures currently loaded.
+    pub fn len(&self) -> usize {
+        self.loaded.values().map(Vec::len).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.loaded.is_empty()
+    }
+}
+
+impl Default for TextureManager {
+    fn default() -> Self {
+        Self::new(TexelFormat::Float)
+    }
+}